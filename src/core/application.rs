@@ -1,37 +1,123 @@
-use winit::{event::*, keyboard::NamedKey};
+use winit::event::*;
 use std::sync::Arc;
 use logfather::*;
-use crate::core::color;
+use crate::core::error::KozyError;
+use crate::core::renderer::{Batch, Renderer};
+use crate::structure::dispatch::Dispatcher;
+use crate::structure::layout::Rect;
+use crate::structure::markup::LayoutWatcher;
+use crate::structure::theme::Theme;
+use crate::structure::window::Window;
+use crate::text::{FontId, Fonts, TextError};
 
 #[repr(C)]
+#[derive(Copy, Clone, Debug, bytemuck::Pod, bytemuck::Zeroable)]
 pub struct Vertex {
     pub position: [f32; 3],
     pub color: [f32; 3],
 }
 
+impl Vertex {
+    const ATTRIBUTES: [wgpu::VertexAttribute; 2] = wgpu::vertex_attr_array![0 => Float32x3, 1 => Float32x3];
+
+    pub fn layout() -> wgpu::VertexBufferLayout<'static> {
+        wgpu::VertexBufferLayout {
+            array_stride: std::mem::size_of::<Self>() as wgpu::BufferAddress,
+            step_mode: wgpu::VertexStepMode::Vertex,
+            attributes: &Self::ATTRIBUTES,
+        }
+    }
+}
+
 pub struct Mouse {
     pub x_pos: f64,
     pub y_pos: f64,
 }
 
-pub struct Application<'window> {
-    pub instance: wgpu::Instance,
+/// State owned by the event loop of a running window
+pub struct Application {
     pub window: std::sync::Arc<winit::window::Window>,
-    pub adapter: wgpu::Adapter,
-    pub surface: wgpu::Surface<'window>,
-    pub device: wgpu::Device,
-    pub queue: wgpu::Queue,
-    pub config: wgpu::SurfaceConfiguration,
     pub size: winit::dpi::PhysicalSize<u32>,
     pub clear_color: wgpu::Color,
-    pub render_pipeline: wgpu::RenderPipeline,
+    pub renderer: Renderer,
+    /// Geometry for the next frame
+    pub batch: Batch,
+    /// Widget tree laid out and drawn every frame
+    pub root: Window,
 
     pub mouse: Mouse,
+    /// Routes input to the widgets in `root`
+    pub dispatcher: Dispatcher,
+    /// Caret area last given to the input method, `None` while it is off
+    ime: Option<Rect>,
+    /// Set by resizes, fonts and new trees - input and bound data mark widgets dirty instead
+    layout_pending: bool,
+    /// Light or dark setting of the OS, followed by `root` unless it was given a theme
+    pub system_theme: Theme,
     pub cursor_active: bool,
+}
+
+impl Application {
+    fn new(context: &Core, window: std::sync::Arc<winit::window::Window>, mut root: Window, fonts: Fonts) -> Self {
+        let system_theme = window.theme().map(Theme::from).unwrap_or_default();
+        root.system_theme(system_theme);
+        let mut renderer = Renderer::new(context);
+        renderer.fonts = fonts;
+
+        Self {
+            root,
+            size: window.inner_size(),
+            window,
+            clear_color: wgpu::Color::BLACK,
+            renderer,
+            batch: Batch::new(),
+            mouse: Mouse { x_pos: 0.0, y_pos: 0.0 },
+            dispatcher: Dispatcher::new(),
+            ime: None,
+            layout_pending: true,
+            system_theme,
+            cursor_active: false,
+        }
+    }
+
+    /// Show a new widget tree - focus and hover state belong to the old one, so they are dropped
+    pub fn replace_root(&mut self, mut root: Window) {
+        root.system_theme(self.system_theme);
+        self.root = root;
+        self.dispatcher = Dispatcher::new();
+        self.layout_pending = true;
+    }
+
+    /// Make a TTF or OTF font available to text widgets, laying them out again on the next frame
+    pub fn load_font(&mut self, data: Vec<u8>) -> Result<FontId, TextError> {
+        let id = self.renderer.fonts.load(data)?;
+        self.layout_pending = true;
+        Ok(id)
+    }
+
+    /// Switch to the OS light or dark setting, restyled on the next frame
+    pub fn set_system_theme(&mut self, theme: Theme) {
+        self.system_theme = theme;
+        self.root.system_theme(theme);
+    }
+
+    /// Turn the input method on while a text widget has focus and keep its candidate window at
+    /// the caret
+    fn update_ime(&mut self) {
+        let area = self.dispatcher.ime_area(&self.root);
+        if area == self.ime {
+            return;
+        }
 
-    pub frame_count: u32,
-    pub last_frame_time: std::time::Instant,
-    pub last_fps_update: std::time::Instant,
+        self.window.set_ime_allowed(area.is_some());
+        if let Some(r) = area {
+            self.window.set_ime_cursor_area(
+                winit::dpi::PhysicalPosition::new(r.x, r.y),
+                winit::dpi::PhysicalSize::new(r.width, r.height),
+            );
+        }
+        self.ime = area;
+    }
 }
 
 
@@ -41,32 +127,26 @@ struct EventLoop {
 }
 
 impl EventLoop {
-    pub fn new(title: &str) -> Self {
-        let event_loop = winit::event_loop::EventLoop::new().unwrap();
-        let mut builder = winit::window::WindowBuilder::new().with_title(title);
-        let window = Arc::new(builder.build(&event_loop).unwrap());
+    pub fn new(title: &str) -> Result<Self, KozyError> {
+        let event_loop = winit::event_loop::EventLoop::new()?;
+        let builder = winit::window::WindowBuilder::new().with_title(title);
+        let window = Arc::new(builder.build(&event_loop)?);
 
-        Self { event_loop, window }
+        Ok(Self { event_loop, window })
     }
 }
 
-struct Core {
-    instance: wgpu::Instance,
-    adapter: wgpu::Adapter,
-    device: wgpu::Device,
-    queue: wgpu::Queue,
+pub(crate) struct Core {
+    pub instance: wgpu::Instance,
+    pub adapter: wgpu::Adapter,
+    pub device: wgpu::Device,
+    pub queue: wgpu::Queue,
 }
 
 impl Core {
-    pub async fn new(window: Arc<winit::window::Window>) -> Self {
-        // Instance creates Adapters and Surfaces
-        // BackendBit::Primary => Vulkan + Metal + DX12 + Browser WebGPU
-        let instance = wgpu::Instance::new(wgpu::InstanceDescriptor {
-            backends: wgpu::Backends::all(),
-            ..Default::default()
-        });
-
-        let surface = instance.create_surface(window.clone()).unwrap();
+    pub async fn new(window: Arc<winit::window::Window>) -> Result<Self, KozyError> {
+        let instance = Self::instance();
+        let surface = instance.create_surface(window.clone())?;
 
         // Handle for the GPU
         let adapter = instance.request_adapter(
@@ -79,9 +159,53 @@ impl Core {
                 // Forces wgpu to pick an adapter that will work on all hardware
                 force_fallback_adapter: false,
             },
-        ).await.unwrap();
+        ).await.ok_or(KozyError::NoAdapter)?;
+
+        let (device, queue) = Self::request_device(&adapter).await?;
 
-        let (device, queue) = adapter.request_device(
+        Ok(Self { instance, adapter, device, queue })
+    }
+
+    /// Create a context without a window - prefers a hardware adapter, but falls back to the
+    /// software adapter when there is no GPU (i.e. CI machines without a display)
+    pub async fn headless() -> Result<Self, KozyError> {
+        let instance = Self::instance();
+
+        let mut adapter = None;
+        for force_fallback_adapter in [false, true] {
+            adapter = instance.request_adapter(
+                &wgpu::RequestAdapterOptions {
+                    power_preference: wgpu::PowerPreference::HighPerformance,
+                    // Nothing is presented, so any adapter will do
+                    compatible_surface: None,
+                    force_fallback_adapter,
+                },
+            ).await;
+
+            if adapter.is_some() {
+                break;
+            }
+        }
+        let adapter = adapter.ok_or(KozyError::NoAdapter)?;
+        let adapter_info = adapter.get_info();
+        info!("Headless adapter: {adapter_info:?}");
+
+        let (device, queue) = Self::request_device(&adapter).await?;
+
+        Ok(Self { instance, adapter, device, queue })
+    }
+
+    fn instance() -> wgpu::Instance {
+        // Instance creates Adapters and Surfaces
+        // BackendBit::Primary => Vulkan + Metal + DX12 + Browser WebGPU
+        wgpu::Instance::new(wgpu::InstanceDescriptor {
+            backends: wgpu::Backends::all(),
+            ..Default::default()
+        })
+    }
+
+    async fn request_device(adapter: &wgpu::Adapter) -> Result<(wgpu::Device, wgpu::Queue), KozyError> {
+        adapter.request_device(
             &wgpu::DeviceDescriptor {
                 // Full list of features: https://docs.rs/wgpu/latest/wgpu/struct.Features.html
                 required_features: wgpu::Features::empty(),
@@ -94,14 +218,10 @@ impl Core {
                 label: None,
             },
             None,
-        ).await.unwrap();
-
-        Self { instance, adapter, device, queue }
+        ).await.map_err(KozyError::from)
     }
 }
 
-
-
 struct Surface {
     surface: Option<wgpu::Surface<'static>>,
     config: Option<wgpu::SurfaceConfiguration>,
@@ -120,22 +240,18 @@ impl Surface {
         }
     }
 
-    fn init(&mut self, context: &Core, window: std::sync::Arc<winit::window::Window>, srgb: bool) {
+    fn init(&mut self, context: &Core, window: std::sync::Arc<winit::window::Window>, srgb: bool) -> Result<(), KozyError> {
         let window_size = window.inner_size();
         let width = window_size.width.max(1);
         let height = window_size.height.max(1);
 
         info!("Surface initiation: {window_size:?}");
 
-        self.surface = Some(match context.instance.create_surface(window) {
-            Ok(surface) => surface,
-            Err(e) => panic!("Could not create surface: {e}"), // TODO: Handle this better
-        });
+        self.surface = Some(context.instance.create_surface(window)?);
 
-        let mut config = match self.surface().get_default_config(&context.adapter, width, height) {
-            Some(cfg) => cfg,
-            None => panic!("Could not generate default configuration"), // TODO: Handle this better
-        };
+        let mut config = self.surface()?
+            .get_default_config(&context.adapter, width, height)
+            .ok_or(KozyError::UnsupportedSurfaceFormat)?;
 
         let view_format = if srgb { 
             config.format.add_srgb_suffix() 
@@ -146,56 +262,58 @@ impl Surface {
         };
         config.view_formats.push(view_format);
 
-        self.surface().configure(&context.device, &config);
+        self.surface()?.configure(&context.device, &config);
         self.config = Some(config);
+
+        Ok(())
     }
 
     /// Resize the surface - 0 is not allowed
-    fn resize(&mut self, context: &Core, size: winit::dpi::PhysicalSize<u32>) {
+    fn resize(&mut self, context: &Core, size: winit::dpi::PhysicalSize<u32>) -> Result<(), KozyError> {
         info!("Surface resize {size:?}");
 
-        let config = self.config.as_mut().unwrap();
+        let config = self.config.as_mut().ok_or(KozyError::SurfaceNotConfigured)?;
         config.width = size.width.max(1);
         config.height = size.height.max(1);
-        let surface = self.surface.as_ref().unwrap();
+        let surface = self.surface.as_ref().ok_or(KozyError::SurfaceNotConfigured)?;
         surface.configure(&context.device, config);
+
+        Ok(())
     }
 
-    /// Get the next surface texture
-    fn acquire(&mut self, context: &Core) -> wgpu::SurfaceTexture {
-        let surface = self.surface.as_ref().unwrap();
+    /// Get the next surface texture and the format to view it in - `None` when the surface timed
+    /// out twice in a row, so the frame is skipped
+    fn acquire(&mut self, context: &Core) -> Result<Option<(wgpu::SurfaceTexture, wgpu::TextureFormat)>, KozyError> {
+        let surface = self.surface()?;
+        let config = self.config()?;
 
-        match surface.get_current_texture() {
+        let frame = match surface.get_current_texture() {
             Ok(frame) => frame,
-            // If we timed out, just try again
-            Err(wgpu::SurfaceError::Timeout) => surface
-                .get_current_texture()
-                .expect("Failed to acquire next surface texture!"),
+            // If we timed out, just try again, and skip the frame if that times out as well
+            Err(wgpu::SurfaceError::Timeout) => match surface.get_current_texture() {
+                Err(wgpu::SurfaceError::Timeout) => return Ok(None),
+                frame => frame?,
+            },
             Err(
                 // If the surface is outdated, or was lost, reconfigure it.
                 wgpu::SurfaceError::Outdated | wgpu::SurfaceError::Lost
                 // If OutOfMemory happens, reconfiguring may not help, but we might as well try
                 | wgpu::SurfaceError::OutOfMemory,
             ) => {
-                surface.configure(&context.device, self.config());
-                surface
-                    .get_current_texture()
-                    .expect("Failed to acquire next surface texture!")
+                surface.configure(&context.device, config);
+                surface.get_current_texture()?
             }
-        }
-    }
-
+        };
 
-    fn get(&self) -> &wgpu::Surface {
-        return self.surface.as_ref().unwrap();
+        Ok(Some((frame, config.view_formats[0])))
     }
 
-    fn surface(&self) -> &wgpu::Surface {
-        return self.surface.as_ref().unwrap();
+    fn surface(&self) -> Result<&wgpu::Surface<'static>, KozyError> {
+        self.surface.as_ref().ok_or(KozyError::SurfaceNotConfigured)
     }
 
-    fn config(&self) -> &wgpu::SurfaceConfiguration {
-        return self.config.as_ref().unwrap();
+    fn config(&self) -> Result<&wgpu::SurfaceConfiguration, KozyError> {
+        self.config.as_ref().ok_or(KozyError::SurfaceNotConfigured)
     }
 }
 
@@ -228,33 +346,87 @@ impl FrameCounter {
     }
 }
 
-pub fn run(title: &'static str, target: f64) {
-    pollster::block_on(start(title, target));
+/// Rebuilds the root window when its layout file changes
+struct Reload {
+    watcher: LayoutWatcher,
+    /// Runs on every rebuilt window, before it is shown
+    setup: Box<dyn FnMut(&mut Window)>,
+}
+
+pub fn run(title: &'static str, target: f64) -> Result<(), KozyError> {
+    run_window(Window::new(0.0, 0.0), Fonts::new(), title, target)
+}
+
+/// Open a window showing `root`, redrawing at up to `target` frames per second. Text widgets draw
+/// with `fonts`, their `FontId`s being the ones returned when loading them
+///
+/// ```ignore
+/// let mut fonts = Fonts::new();
+/// fonts.load_file("assets/Inter.ttf")?;
+/// application::run_window(root, fonts, "Hello", 60.0)?;
+/// ```
+pub fn run_window(root: Window, fonts: Fonts, title: &'static str, target: f64) -> Result<(), KozyError> {
+    pollster::block_on(start(root, fonts, title, target, None))
+}
+
+/// Open a window showing the layout file at `path`. `setup` attaches handlers and bindings to the
+/// built window, looking widgets up by id. Debug builds watch the file and reload the layout
+/// whenever it is saved, running `setup` again - a layout that fails to load is logged and the
+/// previous one kept
+pub fn run_layout(
+    path: &str,
+    fonts: Fonts,
+    title: &'static str,
+    target: f64,
+    mut setup: impl FnMut(&mut Window) + 'static,
+) -> Result<(), KozyError> {
+    let mut root = Window::load(path)?;
+    setup(&mut root);
+
+    let reload = cfg!(debug_assertions).then(|| Reload { watcher: LayoutWatcher::new(path), setup: Box::new(setup) });
+    pollster::block_on(start(root, fonts, title, target, reload))
 }
 
 #[cfg_attr(target_arch = "wasm32", wasm_bindgen(start))]
-async fn start(title: &str, target: f64) {
-    let window_loop = EventLoop::new(title);
+async fn start(root: Window, fonts: Fonts, title: &str, target: f64, mut reload: Option<Reload>) -> Result<(), KozyError> {
+    let window_loop = EventLoop::new(title)?;
     let mut surface = Surface::new();
-    let context = Core::new(window_loop.window.clone()).await;
+    let context = Core::new(window_loop.window.clone()).await?;
+    let mut app = Application::new(&context, window_loop.window.clone(), root, fonts);
     let mut frame_counter = FrameCounter::new(2.0);
 
     let frame_time = std::time::Duration::from_secs_f64(1.0 / target);
     let mut last_frame = std::time::Instant::now();
 
+    // Errors raised inside the event loop are held until it exits
+    let mut failure = None;
+    let failed = &mut failure;
+
     info!("Entering event loop");
-    let _ = window_loop.event_loop.run(move |event, window_target| {
+    window_loop.event_loop.run(move |event, window_target| {
+        if failed.is_some() {
+            return;
+        }
         window_target.set_control_flow(winit::event_loop::ControlFlow::WaitUntil(last_frame + frame_time));
         
         match event {
             ref e if Surface::start_condition(e) => {
-                surface.init(&context, window_loop.window.clone(), true);
+                if let Err(e) = surface.init(&context, window_loop.window.clone(), true) {
+                    *failed = Some(e);
+                    window_target.exit();
+                }
             }
             // Event::NewEvents(_) => todo!(),
             Event::WindowEvent { event, .. } => match event {
                 // WindowEvent::ActivationTokenDone { serial, token } => todo!(),
                 WindowEvent::Resized(size) => {
-                    surface.resize(&context, size);
+                    app.size = size;
+                    app.layout_pending = true;
+                    if let Err(e) = surface.resize(&context, size) {
+                        *failed = Some(e);
+                        window_target.exit();
+                        return;
+                    }
                     window_loop.window.request_redraw();
                 },
                 // WindowEvent::Moved(_) => todo!(),
@@ -263,20 +435,7 @@ async fn start(title: &str, target: f64) {
                 // WindowEvent::DroppedFile(_) => todo!(),
                 // WindowEvent::HoveredFile(_) => todo!(),
                 // WindowEvent::HoveredFileCancelled => todo!(),
-                // WindowEvent::Focused(_) => todo!(),
-                WindowEvent::KeyboardInput { event: KeyEvent {
-                    logical_key: winit::keyboard::Key::Named(NamedKey::Escape),
-                    ..
-                }, .. } => {
-                    window_target.exit();
-                },
-                // WindowEvent::ModifiersChanged(_) => todo!(),
-                // WindowEvent::Ime(_) => todo!(),
-                // WindowEvent::CursorMoved { device_id, position } => todo!(),
                 // WindowEvent::CursorEntered { device_id } => todo!(),
-                // WindowEvent::CursorLeft { device_id } => todo!(),
-                // WindowEvent::MouseWheel { device_id, delta, phase } => todo!(),
-                // WindowEvent::MouseInput { device_id, state, button } => todo!(),
                 // WindowEvent::TouchpadMagnify { device_id, delta, phase } => todo!(),
                 // WindowEvent::SmartMagnify { device_id } => todo!(),
                 // WindowEvent::TouchpadRotate { device_id, delta, phase } => todo!(),
@@ -284,21 +443,83 @@ async fn start(title: &str, target: f64) {
                 // WindowEvent::AxisMotion { device_id, axis, value } => todo!(),
                 // WindowEvent::Touch(_) => todo!(),
                 // WindowEvent::ScaleFactorChanged { scale_factor, inner_size_writer } => todo!(),
-                // WindowEvent::ThemeChanged(_) => todo!(),
+                WindowEvent::ThemeChanged(theme) => {
+                    app.set_system_theme(theme.into());
+                    let theme = app.system_theme;
+                    info!("Switched to the {theme} theme");
+                    window_loop.window.request_redraw();
+                },
                 // WindowEvent::Occluded(_) => todo!(),
                 WindowEvent::RedrawRequested => {
                     frame_counter.update();
-                    // let frame = surface.acquire(&context);
-                    // let view = frame.texture.create_view(&wgpu::TextureViewDescriptor {
-                    //     format: Some(surface.config().view_formats[0]),
-                    //     ..wgpu::TextureViewDescriptor::default()
-                    // });
-
-                    // frame.present();
+                    let (frame, format) = match surface.acquire(&context) {
+                        Ok(Some(acquired)) => acquired,
+                        Ok(None) => {
+                            warning!("Surface timed out - skipping a frame");
+                            window_loop.window.request_redraw();
+                            return;
+                        },
+                        Err(e) => {
+                            *failed = Some(e);
+                            window_target.exit();
+                            return;
+                        },
+                    };
+                    let view = frame.texture.create_view(&wgpu::TextureViewDescriptor {
+                        format: Some(format),
+                        ..wgpu::TextureViewDescriptor::default()
+                    });
+
+                    if let Some(reload) = reload.as_mut() {
+                        match reload.watcher.poll() {
+                            Some(Ok(mut root)) => {
+                                let path = reload.watcher.path().display();
+                                info!("Reloaded layout {path}");
+                                (reload.setup)(&mut root);
+                                app.replace_root(root);
+                            },
+                            Some(Err(e)) => error!("Keeping the previous layout: {e}"),
+                            None => {},
+                        }
+                    }
+
+                    let dt = last_frame.elapsed().as_secs_f64();
+                    app.root.width = app.size.width as f64;
+                    app.root.height = app.size.height as f64;
+                    let moving = app.root.animate(dt);
+                    app.root.sync();
+                    if app.root.restyle_pending {
+                        app.root.restyle();
+                        if let Some(background) = &app.root.style().background {
+                            app.clear_color = background.rgb(1.0);
+                        }
+                    }
+
+                    // Frames without input, animation or changed data reuse the last layout, and
+                    // changes to a few widgets only lay out what they affect
+                    if app.layout_pending || moving {
+                        app.root.layout(&app.renderer.fonts);
+                        app.root.take_dirty();
+                        app.layout_pending = false;
+                    } else if app.root.is_dirty() {
+                        let dirty = app.root.take_dirty();
+                        app.root.relayout(&dirty, &app.renderer.fonts);
+                    }
+                    app.update_ime();
+                    app.batch.clear();
+                    app.root.draw(&mut app.batch);
+                    app.dispatcher.focus().draw(&app.root, &mut app.batch);
+
+                    let size = (app.size.width.max(1), app.size.height.max(1));
+                    app.renderer.render(&context, &view, format, size, app.clear_color, &app.batch);
+                    frame.present();
+                    last_frame = std::time::Instant::now();
 
                     window_loop.window.request_redraw();
                 },
-                _ => {}
+                event => {
+                    app.dispatcher.handle(&mut app.root, &event);
+                },
             },
             // Event::DeviceEvent { device_id, event } => todo!(),
             // Event::UserEvent(_) => todo!(),
@@ -309,5 +530,10 @@ async fn start(title: &str, target: f64) {
             // Event::MemoryWarning => todo!(),
             _ => {}
         }
-    });
+    })?;
+
+    match failure {
+        Some(e) => Err(e),
+        None => Ok(()),
+    }
 }
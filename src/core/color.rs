@@ -1,6 +1,13 @@
 
 
 
+/// A color in sRGB or linear light
+///
+/// Palette, `RGB`, `RGBA`, `Hex` and `Srgb` colors are sRGB encoded, as CSS and image editors give them.
+/// `rgb` always returns sRGB encoded channels, which the renderer decodes when drawing to an sRGB
+/// target
+#[allow(clippy::upper_case_acronyms)]
+#[derive(Clone, Debug, PartialEq)]
 pub enum Color {
     Korple,
     Black,
@@ -24,13 +31,111 @@ pub enum Color {
     Pink,
     Coral,
     RGB(u8, u8, u8),
-    Hex(&'static str)
+    /// Straight alpha, 255 being opaque
+    RGBA(u8, u8, u8, u8),
+    /// `rrggbb` or any form `Color::parse` takes after a `#`, which is optional - build these with
+    /// `Color::hex`, which reports malformed strings rather than drawing them grey
+    Hex(String),
+    /// Channels proportional to light intensity, such as the result of blending
+    Linear(LinearRgb),
+    /// sRGB encoded channels between 0 and 1, as converted from HSL, HSV or OKLab without rounding
+    /// to 8 bits
+    Srgb(Srgb),
 }
 
+/// Encoding of the channels of a `Color`
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum ColorSpace {
+    Srgb,
+    Linear,
+}
+
+/// Why a string is not a color
+#[derive(Clone, Debug, PartialEq)]
+pub enum ColorParseError {
+    Empty,
+    /// Not 3, 4, 6 or 8 hex digits after the `#`, which only `Color::hex` makes optional
+    InvalidHex(String),
+    /// Wrong number of arguments to `rgb()`, `rgba()`, `hsl()` or `hsla()`, or one that is not a
+    /// number
+    InvalidFunction(String),
+    UnknownFunction(String),
+    UnknownName(String),
+}
+
+impl std::fmt::Display for ColorParseError {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            ColorParseError::Empty => write!(f, "empty color"),
+            ColorParseError::InvalidHex(s) => write!(f, "`{s}` is not a color - expected #rgb, #rgba, #rrggbb or #rrggbbaa"),
+            ColorParseError::InvalidFunction(s) => write!(f, "invalid arguments in `{s}`"),
+            ColorParseError::UnknownFunction(name) => write!(f, "unknown color function `{name}()` - expected rgb, rgba, hsl or hsla"),
+            ColorParseError::UnknownName(name) => write!(f, "unknown color name `{name}`"),
+        }
+    }
+}
+
+impl std::error::Error for ColorParseError {}
+
 impl Color {
-    pub fn rgb(&self, opacity: f64) -> wgpu::Color {
+    /// Parse a CSS color - `#rgb`, `#rgba`, `#rrggbb` and `#rrggbbaa` hex, `rgb()`, `rgba()`,
+    /// `hsl()` and `hsla()` in comma or space separated form, and the 148 CSS named colors along
+    /// with `transparent` and `korple`
+    ///
+    /// ```
+    /// # use kozy::core::color::Color;
+    /// Color::parse("#5b3cc480")?;
+    /// Color::parse("rgb(91 60 196 / 50%)")?;
+    /// Color::parse("hsla(254, 54%, 50%, 0.5)")?;
+    /// Color::parse("rebeccapurple")?;
+    /// # Ok::<(), kozy::core::color::ColorParseError>(())
+    /// ```
+    pub fn parse(s: &str) -> Result<Color, ColorParseError> {
+        let s = s.trim();
+        if s.is_empty() {
+            return Err(ColorParseError::Empty);
+        }
+
+        if let Some(hex) = s.strip_prefix('#') {
+            return parse_hex(hex).ok_or_else(|| ColorParseError::InvalidHex(s.to_owned()));
+        }
+
+        if let Some((name, rest)) = s.split_once('(') {
+            let args = rest.strip_suffix(')').ok_or_else(|| ColorParseError::InvalidFunction(s.to_owned()))?;
+            let name = name.trim().to_ascii_lowercase();
+            let [r, g, b, a] = match name.as_str() {
+                "rgb" | "rgba" => parse_rgb(args),
+                "hsl" | "hsla" => parse_hsl(args),
+                _ => return Err(ColorParseError::UnknownFunction(name)),
+            }.ok_or_else(|| ColorParseError::InvalidFunction(s.to_owned()))?;
+            return Ok(Color::RGBA(r, g, b, a));
+        }
+
+        let name = s.to_ascii_lowercase();
+        match name.as_str() {
+            "korple" => Ok(Color::Korple),
+            "transparent" => Ok(Color::RGBA(0, 0, 0, 0)),
+            _ => NAMED.binary_search_by_key(&name.as_str(), |(n, _)| n)
+                .map(|i| {
+                    let [_, r, g, b] = NAMED[i].1.to_be_bytes();
+                    Color::RGB(r, g, b)
+                })
+                .map_err(|_| ColorParseError::UnknownName(s.to_owned())),
+        }
+    }
+
+    /// A `Hex` color, checking that `s` holds 3, 4, 6 or 8 hex digits with an optional `#`
+    pub fn hex(s: &str) -> Result<Color, ColorParseError> {
+        match parse_hex(strip_hash(s)) {
+            Some(_) => Ok(Color::Hex(s.trim().to_owned())),
+            None => Err(ColorParseError::InvalidHex(s.to_owned())),
+        }
+    }
+
+    /// Red, green, blue and alpha between 0 and 1
+    fn channels(&self) -> (f64, f64, f64, f64) {
         let (r, g, b) = match self {
-            Color::Korple => (1.0, 1.0, 1.0),
+            Color::Korple => (0.439, 0.286, 0.91),
             Color::Black => (0.0, 0.0, 0.0),
             Color::White => (1.0, 1.0, 1.0),
             Color::Red => (1.0, 0.0, 0.0),
@@ -42,7 +147,7 @@ impl Color {
             Color::Silver => (0.753, 0.753, 0.753),
             Color::Grey => (0.5, 0.5, 0.5),
             Color::Maroon => (0.5, 0.0, 0.0),
-            Color::Olive => (0.5, 0.0, 0.0),
+            Color::Olive => (0.5, 0.5, 0.0),
             Color::Green => (0.0, 0.5, 0.0),
             Color::Purple => (0.5, 0.0, 0.5),
             Color::Teal => (0.0, 0.5, 0.5),
@@ -52,29 +157,582 @@ impl Color {
             Color::Pink => (1.0, 0.753, 0.796),
             Color::Coral => (1.0, 0.5, 0.314),
             Color::RGB(x, y, z) => {
-                (x.clone() as f64 / 255.0, y.clone() as f64 / 255.0, z.clone() as f64 / 255.0)
+                (*x as f64 / 255.0, *y as f64 / 255.0, *z as f64 / 255.0)
+            },
+            Color::RGBA(x, y, z, a) => {
+                return (*x as f64 / 255.0, *y as f64 / 255.0, *z as f64 / 255.0, *a as f64 / 255.0);
+            },
+            Color::Linear(c) => {
+                let Srgb { r, g, b, alpha } = c.to_srgb();
+                return (r, g, b, alpha);
+            },
+            Color::Srgb(Srgb { r, g, b, alpha }) => return (*r, *g, *b, *alpha),
+            Color::Hex(s) => match parse_hex(strip_hash(s)) {
+                Some(color) => return color.channels(),
+                None => (0.5, 0.5, 0.5),
             },
-            Color::Hex(s) => {
-                match s.len() {
-                    7 => {
-                        let x = u8::from_str_radix(&s[1..=2], 16).unwrap_or(128) as f64;
-                        let y = u8::from_str_radix(&s[3..=4] ,16).unwrap_or(128) as f64;
-                        let z = u8::from_str_radix(&s[5..=6], 16).unwrap_or(128) as f64;
-                        (x / 255.0, y / 255.0, z / 255.0)
-                    },
-                    6 => {
-                        let x = u8::from_str_radix(&s[0..=1], 16).unwrap_or(128) as f64;
-                        let y = u8::from_str_radix(&s[2..=3] ,16).unwrap_or(128) as f64;
-                        let z = u8::from_str_radix(&s[4..=5], 16).unwrap_or(128) as f64;
-                        (x / 255.0, y / 255.0, z / 255.0)
-                    },
-                    _ => (0.5, 0.5, 0.5),
-                }
-            }
         };
 
+        (r, g, b, 1.0)
+    }
+
+    pub fn space(&self) -> ColorSpace {
+        match self {
+            Color::Linear(_) => ColorSpace::Linear,
+            _ => ColorSpace::Srgb,
+        }
+    }
+
+    /// sRGB encoded channels, as `Batch` and clear colors take them, with the alpha scaled by
+    /// `opacity`
+    pub fn rgb(&self, opacity: f64) -> wgpu::Color {
+        let (r, g, b, a) = self.channels();
         wgpu::Color {
-            r, g, b, a: opacity
+            r, g, b, a: a * opacity
         }
     }
-}
\ No newline at end of file
+
+    pub fn to_srgb(&self) -> Srgb {
+        let (r, g, b, alpha) = self.channels();
+        Srgb { r, g, b, alpha }
+    }
+
+    pub fn to_linear(&self) -> LinearRgb {
+        match self {
+            Color::Linear(c) => *c,
+            _ => self.to_srgb().to_linear(),
+        }
+    }
+
+    pub fn to_hsl(&self) -> Hsl {
+        self.to_srgb().to_hsl()
+    }
+
+    pub fn to_hsv(&self) -> Hsv {
+        self.to_srgb().to_hsv()
+    }
+
+    pub fn to_oklab(&self) -> Oklab {
+        self.to_linear().to_oklab()
+    }
+
+    pub fn to_oklch(&self) -> Oklch {
+        self.to_oklab().to_oklch()
+    }
+
+    /// Raise the perceptual lightness by `amount`, between 0 and 1, keeping hue and chroma
+    ///
+    /// ```ignore
+    /// let hover = Color::Korple.lighten(0.08);
+    /// let pressed = Color::Korple.darken(0.08);
+    /// ```
+    pub fn lighten(&self, amount: f64) -> Color {
+        let mut lch = self.to_oklch();
+        lch.l = (lch.l + amount).clamp(0.0, 1.0);
+        lch.into()
+    }
+
+    pub fn darken(&self, amount: f64) -> Color {
+        self.lighten(-amount)
+    }
+
+    /// Scale the chroma by `1 + amount` - negative amounts desaturate, -1 giving a grey of the
+    /// same lightness
+    pub fn saturate(&self, amount: f64) -> Color {
+        let mut lch = self.to_oklch();
+        lch.c = (lch.c * (1.0 + amount)).max(0.0);
+        lch.into()
+    }
+
+    /// `t` of the way from this color to `other` in OKLab, so midpoints keep their brightness
+    pub fn mix(&self, other: &Color, t: f64) -> Color {
+        let (from, to) = (self.to_oklab(), other.to_oklab());
+        let lerp = |a: f64, b: f64| a + (b - a) * t;
+        Oklab {
+            l: lerp(from.l, to.l),
+            a: lerp(from.a, to.a),
+            b: lerp(from.b, to.b),
+            alpha: lerp(from.alpha, to.alpha),
+        }.into()
+    }
+
+    /// The same color with an alpha between 0 and 1
+    pub fn with_alpha(&self, alpha: f64) -> Color {
+        Srgb { alpha, ..self.to_srgb() }.into()
+    }
+}
+
+/// Gamma encoded red, green and blue as stored by `Color`, with straight alpha
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub struct Srgb {
+    pub r: f64,
+    pub g: f64,
+    pub b: f64,
+    pub alpha: f64,
+}
+
+/// Red, green and blue proportional to light intensity, for blending and lighting
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub struct LinearRgb {
+    pub r: f64,
+    pub g: f64,
+    pub b: f64,
+    pub alpha: f64,
+}
+
+/// Hue in degrees, saturation and lightness between 0 and 1
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub struct Hsl {
+    pub h: f64,
+    pub s: f64,
+    pub l: f64,
+    pub alpha: f64,
+}
+
+/// Hue in degrees, saturation and value between 0 and 1
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub struct Hsv {
+    pub h: f64,
+    pub s: f64,
+    pub v: f64,
+    pub alpha: f64,
+}
+
+/// Perceptual lightness between 0 and 1, with `a` running green to red and `b` blue to yellow
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub struct Oklab {
+    pub l: f64,
+    pub a: f64,
+    pub b: f64,
+    pub alpha: f64,
+}
+
+/// OKLab in polar form - chroma is about 0.4 at most, hue is in degrees
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub struct Oklch {
+    pub l: f64,
+    pub c: f64,
+    pub h: f64,
+    pub alpha: f64,
+}
+
+impl Srgb {
+    pub fn to_linear(self) -> LinearRgb {
+        let decode = |c: f64| if c.abs() <= 0.04045 { c / 12.92 } else { c.signum() * ((c.abs() + 0.055) / 1.055).powf(2.4) };
+        LinearRgb { r: decode(self.r), g: decode(self.g), b: decode(self.b), alpha: self.alpha }
+    }
+
+    pub fn to_hsl(self) -> Hsl {
+        let (max, min) = (self.r.max(self.g).max(self.b), self.r.min(self.g).min(self.b));
+        let l = (max + min) / 2.0;
+        let s = if max == min { 0.0 } else { (max - min) / (1.0 - (2.0 * l - 1.0).abs()) };
+        Hsl { h: self.hue(), s, l, alpha: self.alpha }
+    }
+
+    pub fn to_hsv(self) -> Hsv {
+        let (max, min) = (self.r.max(self.g).max(self.b), self.r.min(self.g).min(self.b));
+        let s = if max == 0.0 { 0.0 } else { (max - min) / max };
+        Hsv { h: self.hue(), s, v: max, alpha: self.alpha }
+    }
+
+    /// Whether every channel is between 0 and 1, give or take rounding
+    pub fn in_gamut(self) -> bool {
+        [self.r, self.g, self.b].iter().all(|c| (-1e-4..=1.0 + 1e-4).contains(c))
+    }
+
+    /// Hue in degrees shared by HSL and HSV, 0 for greys
+    fn hue(self) -> f64 {
+        let Srgb { r, g, b, .. } = self;
+        let (max, min) = (r.max(g).max(b), r.min(g).min(b));
+        let delta = max - min;
+        let sextant = if delta == 0.0 {
+            0.0
+        } else if max == r {
+            ((g - b) / delta).rem_euclid(6.0)
+        } else if max == g {
+            (b - r) / delta + 2.0
+        } else {
+            (r - g) / delta + 4.0
+        };
+        sextant * 60.0
+    }
+}
+
+impl LinearRgb {
+    pub fn to_srgb(self) -> Srgb {
+        let encode = |c: f64| if c.abs() <= 0.0031308 { c * 12.92 } else { c.signum() * (1.055 * c.abs().powf(1.0 / 2.4) - 0.055) };
+        Srgb { r: encode(self.r), g: encode(self.g), b: encode(self.b), alpha: self.alpha }
+    }
+
+    pub fn to_oklab(self) -> Oklab {
+        let LinearRgb { r, g, b, alpha } = self;
+        let l = (0.4122214708 * r + 0.5363325363 * g + 0.0514459929 * b).cbrt();
+        let m = (0.2119034982 * r + 0.6806995451 * g + 0.1073969566 * b).cbrt();
+        let s = (0.0883024619 * r + 0.2817188376 * g + 0.6299787005 * b).cbrt();
+
+        Oklab {
+            l: 0.2104542553 * l + 0.7936177850 * m - 0.0040720468 * s,
+            a: 1.9779984951 * l - 2.4285922050 * m + 0.4505937099 * s,
+            b: 0.0259040371 * l + 0.7827717662 * m - 0.8086757660 * s,
+            alpha,
+        }
+    }
+}
+
+impl Hsl {
+    pub fn to_srgb(self) -> Srgb {
+        let chroma = (1.0 - (2.0 * self.l - 1.0).abs()) * self.s;
+        let [r, g, b] = from_hue(self.h, chroma).map(|c| c + self.l - chroma / 2.0);
+        Srgb { r, g, b, alpha: self.alpha }
+    }
+}
+
+impl Hsv {
+    pub fn to_srgb(self) -> Srgb {
+        let chroma = self.v * self.s;
+        let [r, g, b] = from_hue(self.h, chroma).map(|c| c + self.v - chroma);
+        Srgb { r, g, b, alpha: self.alpha }
+    }
+}
+
+impl Oklab {
+    pub fn to_linear(self) -> LinearRgb {
+        let Oklab { l, a, b, alpha } = self;
+        let l_ = (l + 0.3963377774 * a + 0.2158037573 * b).powi(3);
+        let m_ = (l - 0.1055613458 * a - 0.0638541728 * b).powi(3);
+        let s_ = (l - 0.0894841775 * a - 1.2914855480 * b).powi(3);
+
+        LinearRgb {
+            r: 4.0767416621 * l_ - 3.3077115913 * m_ + 0.2309699292 * s_,
+            g: -1.2684380046 * l_ + 2.6097574011 * m_ - 0.3413193965 * s_,
+            b: -0.0041960863 * l_ - 0.7034186147 * m_ + 1.7076147010 * s_,
+            alpha,
+        }
+    }
+
+    pub fn to_oklch(self) -> Oklch {
+        Oklch {
+            l: self.l,
+            c: self.a.hypot(self.b),
+            h: self.b.atan2(self.a).to_degrees().rem_euclid(360.0),
+            alpha: self.alpha,
+        }
+    }
+}
+
+impl Oklch {
+    pub fn to_oklab(self) -> Oklab {
+        let (sin, cos) = self.h.to_radians().sin_cos();
+        Oklab { l: self.l, a: self.c * cos, b: self.c * sin, alpha: self.alpha }
+    }
+
+    /// The closest sRGB color of the same lightness and hue - chroma is reduced until it fits,
+    /// rather than clipping channels and shifting the hue
+    pub fn to_srgb(self) -> Srgb {
+        let srgb = self.to_oklab().to_linear().to_srgb();
+        if srgb.in_gamut() {
+            return srgb;
+        }
+
+        let (mut low, mut high) = (0.0, self.c);
+        for _ in 0..16 {
+            let c = (low + high) / 2.0;
+            let candidate = Oklch { c, ..self };
+            if candidate.to_oklab().to_linear().to_srgb().in_gamut() {
+                low = c;
+            } else {
+                high = c;
+            }
+        }
+        Oklch { c: low, ..self }.to_oklab().to_linear().to_srgb()
+    }
+}
+
+/// Red, green and blue of a fully saturated hue scaled to `chroma`, before adding lightness
+fn from_hue(hue: f64, chroma: f64) -> [f64; 3] {
+    let h = hue.rem_euclid(360.0) / 60.0;
+    let x = chroma * (1.0 - (h % 2.0 - 1.0).abs());
+    match h as u32 {
+        0 => [chroma, x, 0.0],
+        1 => [x, chroma, 0.0],
+        2 => [0.0, chroma, x],
+        3 => [0.0, x, chroma],
+        4 => [x, 0.0, chroma],
+        _ => [chroma, 0.0, x],
+    }
+}
+
+impl From<Srgb> for Color {
+    fn from(c: Srgb) -> Self {
+        let [r, g, b, alpha] = [c.r, c.g, c.b, c.alpha].map(|v| v.clamp(0.0, 1.0));
+        Color::Srgb(Srgb { r, g, b, alpha })
+    }
+}
+
+impl From<LinearRgb> for Color {
+    fn from(c: LinearRgb) -> Self {
+        Color::Linear(c)
+    }
+}
+
+impl From<Hsl> for Color {
+    fn from(c: Hsl) -> Self {
+        c.to_srgb().into()
+    }
+}
+
+impl From<Hsv> for Color {
+    fn from(c: Hsv) -> Self {
+        c.to_srgb().into()
+    }
+}
+
+impl From<Oklab> for Color {
+    fn from(c: Oklab) -> Self {
+        c.to_oklch().to_srgb().into()
+    }
+}
+
+impl From<Oklch> for Color {
+    fn from(c: Oklch) -> Self {
+        c.to_srgb().into()
+    }
+}
+
+/// `color`, sRGB encoded, as a target of `format` expects it - sRGB formats take linear values and
+/// encode them on store, so they are decoded first
+pub fn for_format(color: wgpu::Color, format: wgpu::TextureFormat) -> wgpu::Color {
+    if !format.is_srgb() {
+        return color;
+    }
+
+    let LinearRgb { r, g, b, alpha } = Srgb { r: color.r, g: color.g, b: color.b, alpha: color.a }.to_linear();
+    wgpu::Color { r, g, b, a: alpha }
+}
+
+impl std::str::FromStr for Color {
+    type Err = ColorParseError;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        Color::parse(s)
+    }
+}
+
+/// 3, 4, 6 or 8 digits, alpha last
+fn strip_hash(s: &str) -> &str {
+    let s = s.trim();
+    s.strip_prefix('#').unwrap_or(s)
+}
+
+fn parse_hex(hex: &str) -> Option<Color> {
+    let digits = hex.chars().map(|c| c.to_digit(16).map(|d| d as u8)).collect::<Option<Vec<_>>>()?;
+    let bytes: Vec<u8> = match digits.len() {
+        3 | 4 => digits.iter().map(|d| d * 17).collect(),
+        6 | 8 => digits.chunks(2).map(|d| d[0] * 16 + d[1]).collect(),
+        _ => return None,
+    };
+    Some(match bytes[..] {
+        [r, g, b] => Color::RGB(r, g, b),
+        [r, g, b, a] => Color::RGBA(r, g, b, a),
+        _ => unreachable!(),
+    })
+}
+
+/// Arguments separated by commas, or by spaces with the alpha after a `/`
+fn arguments(args: &str) -> Vec<&str> {
+    args.split([',', '/'])
+        .flat_map(str::split_whitespace)
+        .collect()
+}
+
+fn parse_rgb(args: &str) -> Option<[u8; 4]> {
+    let args = arguments(args);
+    let (rgb, alpha) = match args[..] {
+        [r, g, b] => ([r, g, b], None),
+        [r, g, b, a] => ([r, g, b], Some(a)),
+        _ => return None,
+    };
+
+    let channel = |v: &str| match v.strip_suffix('%') {
+        Some(percent) => number(percent).map(|p| p / 100.0 * 255.0),
+        None => number(v),
+    };
+    let [r, g, b] = [channel(rgb[0])?, channel(rgb[1])?, channel(rgb[2])?].map(|c| c.clamp(0.0, 255.0).round() as u8);
+    Some([r, g, b, alpha.map_or(Some(255), parse_alpha)?])
+}
+
+fn parse_hsl(args: &str) -> Option<[u8; 4]> {
+    let args = arguments(args);
+    let (hsl, alpha) = match args[..] {
+        [h, s, l] => ([h, s, l], None),
+        [h, s, l, a] => ([h, s, l], Some(a)),
+        _ => return None,
+    };
+
+    let hue = hsl[0];
+    let degrees = if let Some(v) = hue.strip_suffix("deg") {
+        number(v)?
+    } else if let Some(v) = hue.strip_suffix("grad") {
+        number(v)? * 0.9
+    } else if let Some(v) = hue.strip_suffix("rad") {
+        number(v)?.to_degrees()
+    } else if let Some(v) = hue.strip_suffix("turn") {
+        number(v)? * 360.0
+    } else {
+        number(hue)?
+    };
+    let percent = |v: &str| number(v.strip_suffix('%').unwrap_or(v)).map(|p| (p / 100.0).clamp(0.0, 1.0));
+
+    let Srgb { r, g, b, .. } = Hsl { h: degrees, s: percent(hsl[1])?, l: percent(hsl[2])?, alpha: 1.0 }.to_srgb();
+    let [r, g, b] = [r, g, b].map(|c| (c * 255.0).round() as u8);
+    Some([r, g, b, alpha.map_or(Some(255), parse_alpha)?])
+}
+
+/// A number between 0 and 1, or a percentage
+fn parse_alpha(v: &str) -> Option<u8> {
+    let alpha = match v.strip_suffix('%') {
+        Some(percent) => number(percent)? / 100.0,
+        None => number(v)?,
+    };
+    Some((alpha.clamp(0.0, 1.0) * 255.0).round() as u8)
+}
+
+fn number(v: &str) -> Option<f64> {
+    v.parse().ok().filter(|n: &f64| n.is_finite())
+}
+
+/// CSS named colors, sorted for binary search
+const NAMED: [(&str, u32); 148] = [
+    ("aliceblue", 0xf0f8ff), ("antiquewhite", 0xfaebd7), ("aqua", 0x00ffff), ("aquamarine", 0x7fffd4),
+    ("azure", 0xf0ffff), ("beige", 0xf5f5dc), ("bisque", 0xffe4c4), ("black", 0x000000),
+    ("blanchedalmond", 0xffebcd), ("blue", 0x0000ff), ("blueviolet", 0x8a2be2), ("brown", 0xa52a2a),
+    ("burlywood", 0xdeb887), ("cadetblue", 0x5f9ea0), ("chartreuse", 0x7fff00), ("chocolate", 0xd2691e),
+    ("coral", 0xff7f50), ("cornflowerblue", 0x6495ed), ("cornsilk", 0xfff8dc), ("crimson", 0xdc143c),
+    ("cyan", 0x00ffff), ("darkblue", 0x00008b), ("darkcyan", 0x008b8b), ("darkgoldenrod", 0xb8860b),
+    ("darkgray", 0xa9a9a9), ("darkgreen", 0x006400), ("darkgrey", 0xa9a9a9), ("darkkhaki", 0xbdb76b),
+    ("darkmagenta", 0x8b008b), ("darkolivegreen", 0x556b2f), ("darkorange", 0xff8c00), ("darkorchid", 0x9932cc),
+    ("darkred", 0x8b0000), ("darksalmon", 0xe9967a), ("darkseagreen", 0x8fbc8f), ("darkslateblue", 0x483d8b),
+    ("darkslategray", 0x2f4f4f), ("darkslategrey", 0x2f4f4f), ("darkturquoise", 0x00ced1), ("darkviolet", 0x9400d3),
+    ("deeppink", 0xff1493), ("deepskyblue", 0x00bfff), ("dimgray", 0x696969), ("dimgrey", 0x696969),
+    ("dodgerblue", 0x1e90ff), ("firebrick", 0xb22222), ("floralwhite", 0xfffaf0), ("forestgreen", 0x228b22),
+    ("fuchsia", 0xff00ff), ("gainsboro", 0xdcdcdc), ("ghostwhite", 0xf8f8ff), ("gold", 0xffd700),
+    ("goldenrod", 0xdaa520), ("gray", 0x808080), ("green", 0x008000), ("greenyellow", 0xadff2f),
+    ("grey", 0x808080), ("honeydew", 0xf0fff0), ("hotpink", 0xff69b4), ("indianred", 0xcd5c5c),
+    ("indigo", 0x4b0082), ("ivory", 0xfffff0), ("khaki", 0xf0e68c), ("lavender", 0xe6e6fa),
+    ("lavenderblush", 0xfff0f5), ("lawngreen", 0x7cfc00), ("lemonchiffon", 0xfffacd), ("lightblue", 0xadd8e6),
+    ("lightcoral", 0xf08080), ("lightcyan", 0xe0ffff), ("lightgoldenrodyellow", 0xfafad2), ("lightgray", 0xd3d3d3),
+    ("lightgreen", 0x90ee90), ("lightgrey", 0xd3d3d3), ("lightpink", 0xffb6c1), ("lightsalmon", 0xffa07a),
+    ("lightseagreen", 0x20b2aa), ("lightskyblue", 0x87cefa), ("lightslategray", 0x778899), ("lightslategrey", 0x778899),
+    ("lightsteelblue", 0xb0c4de), ("lightyellow", 0xffffe0), ("lime", 0x00ff00), ("limegreen", 0x32cd32),
+    ("linen", 0xfaf0e6), ("magenta", 0xff00ff), ("maroon", 0x800000), ("mediumaquamarine", 0x66cdaa),
+    ("mediumblue", 0x0000cd), ("mediumorchid", 0xba55d3), ("mediumpurple", 0x9370db), ("mediumseagreen", 0x3cb371),
+    ("mediumslateblue", 0x7b68ee), ("mediumspringgreen", 0x00fa9a), ("mediumturquoise", 0x48d1cc), ("mediumvioletred", 0xc71585),
+    ("midnightblue", 0x191970), ("mintcream", 0xf5fffa), ("mistyrose", 0xffe4e1), ("moccasin", 0xffe4b5),
+    ("navajowhite", 0xffdead), ("navy", 0x000080), ("oldlace", 0xfdf5e6), ("olive", 0x808000),
+    ("olivedrab", 0x6b8e23), ("orange", 0xffa500), ("orangered", 0xff4500), ("orchid", 0xda70d6),
+    ("palegoldenrod", 0xeee8aa), ("palegreen", 0x98fb98), ("paleturquoise", 0xafeeee), ("palevioletred", 0xdb7093),
+    ("papayawhip", 0xffefd5), ("peachpuff", 0xffdab9), ("peru", 0xcd853f), ("pink", 0xffc0cb),
+    ("plum", 0xdda0dd), ("powderblue", 0xb0e0e6), ("purple", 0x800080), ("rebeccapurple", 0x663399),
+    ("red", 0xff0000), ("rosybrown", 0xbc8f8f), ("royalblue", 0x4169e1), ("saddlebrown", 0x8b4513),
+    ("salmon", 0xfa8072), ("sandybrown", 0xf4a460), ("seagreen", 0x2e8b57), ("seashell", 0xfff5ee),
+    ("sienna", 0xa0522d), ("silver", 0xc0c0c0), ("skyblue", 0x87ceeb), ("slateblue", 0x6a5acd),
+    ("slategray", 0x708090), ("slategrey", 0x708090), ("snow", 0xfffafa), ("springgreen", 0x00ff7f),
+    ("steelblue", 0x4682b4), ("tan", 0xd2b48c), ("teal", 0x008080), ("thistle", 0xd8bfd8),
+    ("tomato", 0xff6347), ("turquoise", 0x40e0d0), ("violet", 0xee82ee), ("wheat", 0xf5deb3),
+    ("white", 0xffffff), ("whitesmoke", 0xf5f5f5), ("yellow", 0xffff00), ("yellowgreen", 0x9acd32),
+];
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    /// Well below the 1/255 step of 8-bit channels, above the error of the OKLab matrices
+    fn close(a: f64, b: f64) -> bool {
+        (a - b).abs() < 1e-6
+    }
+
+    #[test]
+    fn parses_hex_in_every_length() {
+        assert_eq!(Color::parse("#f80"), Ok(Color::RGB(0xff, 0x88, 0x00)));
+        assert_eq!(Color::parse("#f808"), Ok(Color::RGBA(0xff, 0x88, 0x00, 0x88)));
+        assert_eq!(Color::parse("#5b3cc4"), Ok(Color::RGB(0x5b, 0x3c, 0xc4)));
+        assert_eq!(Color::parse(" #5B3CC480 "), Ok(Color::RGBA(0x5b, 0x3c, 0xc4, 0x80)));
+        assert_eq!(Color::parse("#5b3cc"), Err(ColorParseError::InvalidHex("#5b3cc".to_owned())));
+        assert_eq!(Color::parse("#ggg"), Err(ColorParseError::InvalidHex("#ggg".to_owned())));
+    }
+
+    #[test]
+    fn parses_functions_in_comma_and_space_form() {
+        let purple = Ok(Color::RGBA(91, 60, 196, 128));
+        assert_eq!(Color::parse("rgba(91, 60, 196, 0.5)"), purple);
+        assert_eq!(Color::parse("rgb(91 60 196 / 50%)"), purple);
+        assert_eq!(Color::parse("RGB(100%, 0%, 0%)"), Ok(Color::RGBA(255, 0, 0, 255)));
+        assert_eq!(Color::parse("hsl(120, 100%, 50%)"), Ok(Color::RGBA(0, 255, 0, 255)));
+        assert_eq!(Color::parse("hsl(0.5turn 100% 50% / 0.5)"), Ok(Color::RGBA(0, 255, 255, 128)));
+
+        assert_eq!(Color::parse("rgb(1, 2)"), Err(ColorParseError::InvalidFunction("rgb(1, 2)".to_owned())));
+        assert_eq!(Color::parse("lab(50 0 0)"), Err(ColorParseError::UnknownFunction("lab".to_owned())));
+    }
+
+    #[test]
+    fn parses_names() {
+        assert_eq!(Color::parse("RebeccaPurple"), Ok(Color::RGB(0x66, 0x33, 0x99)));
+        assert_eq!(Color::parse("korple"), Ok(Color::Korple));
+        assert_eq!(Color::parse("transparent"), Ok(Color::RGBA(0, 0, 0, 0)));
+        assert_eq!(Color::parse(""), Err(ColorParseError::Empty));
+        assert_eq!(Color::parse("blurple"), Err(ColorParseError::UnknownName("blurple".to_owned())));
+    }
+
+    #[test]
+    fn hex_colors_take_an_optional_hash() {
+        assert_eq!(Color::hex("5b3cc4").unwrap().rgb(1.0), Color::RGB(0x5b, 0x3c, 0xc4).rgb(1.0));
+        assert_eq!(Color::hex("#f00").unwrap().rgb(1.0), Color::Red.rgb(1.0));
+        assert_eq!(Color::hex("nope"), Err(ColorParseError::InvalidHex("nope".to_owned())));
+        assert_eq!(Color::hex("#12345"), Err(ColorParseError::InvalidHex("#12345".to_owned())));
+    }
+
+    #[test]
+    fn conversions_keep_full_precision() {
+        let hsl = Hsl { h: 200.0, s: 0.3, l: 0.41, alpha: 0.7 };
+        let back = Color::from(hsl).to_hsl();
+        assert!(close(back.h, hsl.h) && close(back.s, hsl.s) && close(back.l, hsl.l) && close(back.alpha, hsl.alpha));
+
+        let oklab = Oklab { l: 0.6, a: 0.05, b: -0.08, alpha: 1.0 };
+        let back = Color::from(oklab).to_oklab();
+        assert!(close(back.l, oklab.l) && close(back.a, oklab.a) && close(back.b, oklab.b));
+    }
+
+    #[test]
+    fn small_steps_are_not_lost() {
+        let grey = Color::RGB(128, 128, 128);
+        let lighter = grey.lighten(0.001);
+        assert!(lighter.to_oklch().l > grey.to_oklch().l);
+        assert!(close(lighter.to_oklch().l - grey.to_oklch().l, 0.001));
+    }
+
+    #[test]
+    fn srgb_and_linear_round_trip() {
+        let srgb = Srgb { r: 0.2, g: 0.5, b: 0.9, alpha: 1.0 };
+        let back = srgb.to_linear().to_srgb();
+        assert!(close(back.r, srgb.r) && close(back.g, srgb.g) && close(back.b, srgb.b));
+        assert_eq!(Color::RGB(255, 0, 0).to_linear().r, 1.0);
+    }
+
+    #[test]
+    fn out_of_gamut_oklch_keeps_its_hue() {
+        let vivid = Oklch { l: 0.7, c: 0.4, h: 150.0, alpha: 1.0 };
+        let srgb = vivid.to_srgb();
+        assert!(srgb.in_gamut());
+
+        let mapped = Color::from(srgb).to_oklch();
+        assert!((mapped.h - vivid.h).abs() < 0.5 && (mapped.l - vivid.l).abs() < 1e-3);
+        assert!(mapped.c < vivid.c);
+    }
+
+    #[test]
+    fn mix_and_alpha() {
+        let mid = Color::Black.mix(&Color::White, 0.5).to_oklab();
+        assert!(close(mid.l, 0.5) && mid.a.abs() < 1e-6 && mid.b.abs() < 1e-6);
+        assert_eq!(Color::Red.with_alpha(0.25).rgb(1.0).a, 0.25);
+        assert_eq!(Color::Red.with_alpha(0.25).rgb(0.5).a, 0.125);
+    }
+}
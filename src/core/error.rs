@@ -0,0 +1,98 @@
+use crate::structure::markup::MarkupError;
+
+/// Failures while bringing up the window, GPU context or surface
+#[derive(Debug)]
+pub enum KozyError {
+    /// The platform event loop could not be created or exited abnormally
+    EventLoop(winit::error::EventLoopError),
+    /// The OS refused to create the window
+    WindowCreation(winit::error::OsError),
+    CreateSurface(wgpu::CreateSurfaceError),
+    /// No adapter (hardware or fallback) is available on this machine
+    NoAdapter,
+    RequestDevice(wgpu::RequestDeviceError),
+    /// The adapter cannot present to the surface in any supported format
+    UnsupportedSurfaceFormat,
+    /// The surface was used before the window was ready to create it
+    SurfaceNotConfigured,
+    /// The next frame could not be acquired, even after reconfiguring the surface
+    Surface(wgpu::SurfaceError),
+    /// A rendered frame could not be read back from the GPU
+    BufferMap(wgpu::BufferAsyncError),
+    /// The layout file given to `run_layout` could not be loaded
+    Markup(MarkupError),
+}
+
+impl std::fmt::Display for KozyError {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            KozyError::EventLoop(e) => write!(f, "event loop error: {e}"),
+            KozyError::WindowCreation(e) => write!(f, "could not create window: {e}"),
+            KozyError::CreateSurface(e) => write!(f, "could not create surface: {e}"),
+            KozyError::NoAdapter => write!(f, "no suitable graphics adapter found"),
+            KozyError::RequestDevice(e) => write!(f, "could not request graphics device: {e}"),
+            KozyError::UnsupportedSurfaceFormat => write!(f, "surface is not supported by the adapter"),
+            KozyError::SurfaceNotConfigured => write!(f, "surface used before it was configured"),
+            KozyError::Surface(e) => write!(f, "could not acquire surface texture: {e}"),
+            KozyError::BufferMap(e) => write!(f, "could not read back frame: {e}"),
+            KozyError::Markup(e) => write!(f, "{e}"),
+        }
+    }
+}
+
+impl std::error::Error for KozyError {
+    fn source(&self) -> Option<&(dyn std::error::Error + 'static)> {
+        match self {
+            KozyError::EventLoop(e) => Some(e),
+            KozyError::WindowCreation(e) => Some(e),
+            KozyError::CreateSurface(e) => Some(e),
+            KozyError::RequestDevice(e) => Some(e),
+            KozyError::Surface(e) => Some(e),
+            KozyError::BufferMap(e) => Some(e),
+            KozyError::Markup(e) => Some(e),
+            KozyError::NoAdapter | KozyError::UnsupportedSurfaceFormat | KozyError::SurfaceNotConfigured => None,
+        }
+    }
+}
+
+impl From<winit::error::EventLoopError> for KozyError {
+    fn from(e: winit::error::EventLoopError) -> Self {
+        KozyError::EventLoop(e)
+    }
+}
+
+impl From<winit::error::OsError> for KozyError {
+    fn from(e: winit::error::OsError) -> Self {
+        KozyError::WindowCreation(e)
+    }
+}
+
+impl From<wgpu::CreateSurfaceError> for KozyError {
+    fn from(e: wgpu::CreateSurfaceError) -> Self {
+        KozyError::CreateSurface(e)
+    }
+}
+
+impl From<wgpu::RequestDeviceError> for KozyError {
+    fn from(e: wgpu::RequestDeviceError) -> Self {
+        KozyError::RequestDevice(e)
+    }
+}
+
+impl From<wgpu::SurfaceError> for KozyError {
+    fn from(e: wgpu::SurfaceError) -> Self {
+        KozyError::Surface(e)
+    }
+}
+
+impl From<wgpu::BufferAsyncError> for KozyError {
+    fn from(e: wgpu::BufferAsyncError) -> Self {
+        KozyError::BufferMap(e)
+    }
+}
+
+impl From<MarkupError> for KozyError {
+    fn from(e: MarkupError) -> Self {
+        KozyError::Markup(e)
+    }
+}
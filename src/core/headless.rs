@@ -0,0 +1,145 @@
+use logfather::*;
+use crate::core::application::Core;
+use crate::core::error::KozyError;
+use crate::core::renderer::{Batch, Renderer};
+use crate::structure::window::Window;
+
+/// Offscreen targets are always RGBA so frames can be read back without swizzling
+pub const FORMAT: wgpu::TextureFormat = wgpu::TextureFormat::Rgba8UnormSrgb;
+
+/// Renders into an offscreen texture instead of a window surface - no display or winit event
+/// loop is required, so this works on machines without a GPU via the fallback adapter
+pub struct Headless {
+    context: Core,
+    pub renderer: Renderer,
+    texture: wgpu::Texture,
+    width: u32,
+    height: u32,
+    pub clear_color: wgpu::Color,
+}
+
+impl Headless {
+    pub fn new(width: u32, height: u32) -> Result<Self, KozyError> {
+        pollster::block_on(Self::start(width, height))
+    }
+
+    pub async fn start(width: u32, height: u32) -> Result<Self, KozyError> {
+        let context = Core::headless().await?;
+        let width = width.max(1);
+        let height = height.max(1);
+        let texture = Self::target(&context, width, height);
+        let renderer = Renderer::new(&context);
+
+        info!("Headless target: {width}x{height}");
+
+        Ok(Self { context, renderer, texture, width, height, clear_color: wgpu::Color::BLACK })
+    }
+
+    fn target(context: &Core, width: u32, height: u32) -> wgpu::Texture {
+        context.device.create_texture(&wgpu::TextureDescriptor {
+            label: Some("Headless Target"),
+            size: wgpu::Extent3d { width, height, depth_or_array_layers: 1 },
+            mip_level_count: 1,
+            sample_count: 1,
+            dimension: wgpu::TextureDimension::D2,
+            format: FORMAT,
+            usage: wgpu::TextureUsages::RENDER_ATTACHMENT | wgpu::TextureUsages::COPY_SRC,
+            view_formats: &[],
+        })
+    }
+
+    /// Resize the target - 0 is not allowed
+    pub fn resize(&mut self, width: u32, height: u32) {
+        self.width = width.max(1);
+        self.height = height.max(1);
+        self.texture = Self::target(&self.context, self.width, self.height);
+    }
+
+    pub fn size(&self) -> (u32, u32) {
+        (self.width, self.height)
+    }
+
+    pub fn texture(&self) -> &wgpu::Texture {
+        &self.texture
+    }
+
+    /// Render a frame of `batch` into the offscreen texture
+    pub fn render(&mut self, batch: &Batch) {
+        self.render_with(batch, self.clear_color);
+    }
+
+    fn render_with(&mut self, batch: &Batch, clear_color: wgpu::Color) {
+        let view = self.texture.create_view(&wgpu::TextureViewDescriptor::default());
+        let size = self.size();
+        self.renderer.render(&self.context, &view, FORMAT, size, clear_color, batch);
+    }
+
+    /// Render a frame of `window`, resizing the target to match its dimensions
+    pub fn render_window(&mut self, window: &Window) {
+        let (width, height) = (window.width.round() as u32, window.height.round() as u32);
+        if (width.max(1), height.max(1)) != self.size() {
+            self.resize(width, height);
+        }
+
+        let mut batch = Batch::new();
+        window.draw(&mut batch);
+        // A stylesheet background on the window replaces the clear color, as it does on screen
+        let clear_color = window.style().background.as_ref().map(|c| c.rgb(1.0)).unwrap_or(self.clear_color);
+        self.render_with(&batch, clear_color);
+    }
+
+    /// Copy the last rendered frame back to the CPU as tightly packed RGBA8 rows
+    pub fn read_pixels(&self) -> Result<Vec<u8>, KozyError> {
+        let unpadded = self.width as usize * 4;
+        // Buffer copies require rows aligned to 256 bytes
+        let align = wgpu::COPY_BYTES_PER_ROW_ALIGNMENT as usize;
+        let padded = unpadded.div_ceil(align) * align;
+
+        let buffer = self.context.device.create_buffer(&wgpu::BufferDescriptor {
+            label: Some("Headless Readback"),
+            size: (padded * self.height as usize) as wgpu::BufferAddress,
+            usage: wgpu::BufferUsages::COPY_DST | wgpu::BufferUsages::MAP_READ,
+            mapped_at_creation: false,
+        });
+
+        let mut encoder = self.context.device.create_command_encoder(&wgpu::CommandEncoderDescriptor {
+            label: Some("Headless Readback Encoder"),
+        });
+        encoder.copy_texture_to_buffer(
+            self.texture.as_image_copy(),
+            wgpu::ImageCopyBuffer {
+                buffer: &buffer,
+                layout: wgpu::ImageDataLayout {
+                    offset: 0,
+                    bytes_per_row: Some(padded as u32),
+                    rows_per_image: Some(self.height),
+                },
+            },
+            wgpu::Extent3d { width: self.width, height: self.height, depth_or_array_layers: 1 },
+        );
+        self.context.queue.submit(std::iter::once(encoder.finish()));
+
+        let slice = buffer.slice(..);
+        let (sender, receiver) = std::sync::mpsc::channel();
+        slice.map_async(wgpu::MapMode::Read, move |result| {
+            let _ = sender.send(result);
+        });
+        self.context.device.poll(wgpu::Maintain::Wait);
+        // The callback is dropped without being called if the device is lost
+        if let Err(e) = receiver.recv().unwrap_or(Err(wgpu::BufferAsyncError)) {
+            error!("Failed to map readback buffer: {e}");
+            return Err(e.into());
+        }
+
+        let mut pixels = Vec::with_capacity(unpadded * self.height as usize);
+        {
+            let data = slice.get_mapped_range();
+            for row in data.chunks(padded) {
+                pixels.extend_from_slice(&row[..unpadded]);
+            }
+        }
+        buffer.unmap();
+
+        Ok(pixels)
+    }
+}
@@ -1,2 +1,7 @@
 pub mod application;
-pub mod color;
\ No newline at end of file
+pub mod color;
+pub mod error;
+pub mod headless;
+pub mod primitive;
+pub mod renderer;
+pub mod snapshot;
\ No newline at end of file
@@ -0,0 +1,96 @@
+/// A rectangle drawn with a signed distance field - corners, borders and shadows are computed per
+/// pixel in the fragment shader, so no tessellation is needed
+#[derive(Copy, Clone, Debug, PartialEq)]
+pub struct Primitive {
+    /// x, y, width, height in pixels
+    pub rect: [f32; 4],
+    /// Corner radii - top-left, top-right, bottom-right, bottom-left
+    pub radii: [f32; 4],
+    pub color: [f32; 4],
+    /// Drawn inside the rectangle
+    pub border_width: f32,
+    pub border_color: [f32; 4],
+    pub shadow_offset: [f32; 2],
+    /// Approximate gaussian blur radius, 0 for a hard shadow
+    pub shadow_blur: f32,
+    /// Alpha 0 disables the shadow
+    pub shadow_color: [f32; 4],
+}
+
+impl Primitive {
+    pub fn rect(x: f32, y: f32, width: f32, height: f32, color: [f32; 4]) -> Self {
+        Self {
+            rect: [x, y, width, height],
+            radii: [0.0; 4],
+            color,
+            border_width: 0.0,
+            border_color: [0.0; 4],
+            shadow_offset: [0.0; 2],
+            shadow_blur: 0.0,
+            shadow_color: [0.0; 4],
+        }
+    }
+
+    /// Same radius on every corner
+    pub fn radius(self, radius: f32) -> Self {
+        self.radii([radius; 4])
+    }
+
+    pub fn radii(mut self, radii: [f32; 4]) -> Self {
+        self.radii = radii;
+        self
+    }
+
+    pub fn border(mut self, width: f32, color: [f32; 4]) -> Self {
+        self.border_width = width;
+        self.border_color = color;
+        self
+    }
+
+    pub fn shadow(mut self, offset: [f32; 2], blur: f32, color: [f32; 4]) -> Self {
+        self.shadow_offset = offset;
+        self.shadow_blur = blur;
+        self.shadow_color = color;
+        self
+    }
+}
+
+/// GPU layout of a [`Primitive`], one per instance
+#[repr(C)]
+#[derive(Copy, Clone, bytemuck::Pod, bytemuck::Zeroable)]
+pub(crate) struct Instance {
+    rect: [f32; 4],
+    radii: [f32; 4],
+    color: [f32; 4],
+    border_color: [f32; 4],
+    shadow_color: [f32; 4],
+    /// shadow offset x, shadow offset y, shadow blur, border width
+    params: [f32; 4],
+}
+
+impl Instance {
+    const ATTRIBUTES: [wgpu::VertexAttribute; 6] = wgpu::vertex_attr_array![
+        0 => Float32x4, 1 => Float32x4, 2 => Float32x4, 3 => Float32x4, 4 => Float32x4, 5 => Float32x4,
+    ];
+
+    pub fn layout() -> wgpu::VertexBufferLayout<'static> {
+        wgpu::VertexBufferLayout {
+            array_stride: std::mem::size_of::<Self>() as wgpu::BufferAddress,
+            step_mode: wgpu::VertexStepMode::Instance,
+            attributes: &Self::ATTRIBUTES,
+        }
+    }
+}
+
+impl From<&Primitive> for Instance {
+    fn from(p: &Primitive) -> Self {
+        Self {
+            rect: p.rect,
+            radii: p.radii.map(|r| r.max(0.0)),
+            color: p.color,
+            border_color: p.border_color,
+            shadow_color: p.shadow_color,
+            params: [p.shadow_offset[0], p.shadow_offset[1], p.shadow_blur.max(0.0), p.border_width.max(0.0)],
+        }
+    }
+}
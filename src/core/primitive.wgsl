@@ -0,0 +1,136 @@
+struct Globals {
+    // Size of the render target in pixels
+    viewport: vec2<f32>,
+    // 1 when the target has an sRGB format
+    linear: f32,
+}
+
+@group(0) @binding(0)
+var<uniform> globals: Globals;
+
+// Colors arrive sRGB encoded - sRGB targets take linear values and encode them on store
+fn to_target(color: vec4<f32>) -> vec4<f32> {
+    if globals.linear == 0.0 {
+        return color;
+    }
+    let c = color.rgb;
+    let decoded = select(pow((c + 0.055) / 1.055, vec3<f32>(2.4)), c / 12.92, c <= vec3<f32>(0.04045));
+    return vec4<f32>(decoded, color.a);
+}
+
+struct InstanceInput {
+    // x, y, width, height in pixels
+    @location(0) rect: vec4<f32>,
+    // top-left, top-right, bottom-right, bottom-left
+    @location(1) radii: vec4<f32>,
+    @location(2) color: vec4<f32>,
+    @location(3) border_color: vec4<f32>,
+    @location(4) shadow_color: vec4<f32>,
+    // shadow offset x, shadow offset y, shadow blur, border width
+    @location(5) params: vec4<f32>,
+}
+
+struct VertexOutput {
+    @builtin(position) clip_position: vec4<f32>,
+    @location(0) @interpolate(flat) rect: vec4<f32>,
+    @location(1) @interpolate(flat) radii: vec4<f32>,
+    @location(2) @interpolate(flat) color: vec4<f32>,
+    @location(3) @interpolate(flat) border_color: vec4<f32>,
+    @location(4) @interpolate(flat) shadow_color: vec4<f32>,
+    @location(5) @interpolate(flat) params: vec4<f32>,
+}
+
+@vertex
+fn vs_main(@builtin(vertex_index) index: u32, in: InstanceInput) -> VertexOutput {
+    // Grow the quad so the shadow is not clipped - a gaussian is negligible past 3 sigma
+    let offset = in.params.xy;
+    let margin = in.params.z * 1.5 + max(abs(offset.x), abs(offset.y)) + 1.0;
+    let lo = in.rect.xy - margin;
+    let hi = in.rect.xy + in.rect.zw + margin;
+
+    // Two triangles: 0-1-2, 2-1-3
+    var corners = array<u32, 6>(0u, 1u, 2u, 2u, 1u, 3u);
+    let c = corners[index];
+    let uv = vec2<f32>(f32(c & 1u), f32(c >> 1u));
+    let position = mix(lo, hi, uv);
+
+    var out: VertexOutput;
+    out.clip_position = vec4<f32>(
+        position.x / globals.viewport.x * 2.0 - 1.0,
+        1.0 - position.y / globals.viewport.y * 2.0,
+        0.0,
+        1.0,
+    );
+    out.rect = in.rect;
+    out.radii = in.radii;
+    out.color = to_target(in.color);
+    out.border_color = to_target(in.border_color);
+    out.shadow_color = to_target(in.shadow_color);
+    out.params = in.params;
+
+    return out;
+}
+
+/// Signed distance from `p` to a box of `half` extents centred on the origin with per-corner radii
+fn rounded_box(p: vec2<f32>, half: vec2<f32>, radii: vec4<f32>) -> f32 {
+    var r: f32;
+    if p.x < 0.0 {
+        r = select(radii.w, radii.x, p.y < 0.0);
+    } else {
+        r = select(radii.z, radii.y, p.y < 0.0);
+    }
+    r = min(r, min(half.x, half.y));
+
+    let q = abs(p) - half + r;
+    return min(max(q.x, q.y), 0.0) + length(max(q, vec2<f32>(0.0))) - r;
+}
+
+/// Abramowitz-Stegun style approximation, good enough for a soft shadow falloff
+fn erf(x: f32) -> f32 {
+    let s = sign(x);
+    let a = abs(x);
+    let t = 1.0 + (0.278393 + (0.230389 + 0.078108 * (a * a)) * a) * a;
+    let t2 = t * t;
+    return s - s / (t2 * t2);
+}
+
+fn over(top: vec4<f32>, bottom: vec4<f32>) -> vec4<f32> {
+    // Premultiplied alpha
+    return top + bottom * (1.0 - top.a);
+}
+
+@fragment
+fn fs_main(in: VertexOutput) -> @location(0) vec4<f32> {
+    let half = in.rect.zw * 0.5;
+    let p = in.clip_position.xy - (in.rect.xy + half);
+    let blur = in.params.z;
+    let border = in.params.w;
+
+    // Shadow - gaussian blurred along the distance to the offset shape
+    var shadow = vec4<f32>(0.0);
+    if in.shadow_color.a > 0.0 {
+        let ds = rounded_box(p - in.params.xy, half, in.radii);
+        var coverage: f32;
+        if blur > 0.0 {
+            let sigma = blur * 0.5;
+            coverage = 0.5 - 0.5 * erf(ds / (sigma * 1.41421356));
+        } else {
+            coverage = clamp(0.5 - ds, 0.0, 1.0);
+        }
+        let a = in.shadow_color.a * coverage;
+        shadow = vec4<f32>(in.shadow_color.rgb * a, a);
+    }
+
+    // Fill and border - one pixel wide antialiasing on both edges
+    let d = rounded_box(p, half, in.radii);
+    let coverage = clamp(0.5 - d, 0.0, 1.0);
+    var color = in.color;
+    if border > 0.0 {
+        let t = clamp(d + border + 0.5, 0.0, 1.0);
+        color = mix(in.color, in.border_color, t);
+    }
+    let a = color.a * coverage;
+    let fill = vec4<f32>(color.rgb * a, a);
+
+    return over(fill, shadow);
+}
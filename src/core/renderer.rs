@@ -0,0 +1,718 @@
+use std::collections::HashSet;
+use wgpu::util::DeviceExt;
+use logfather::*;
+use crate::core::application::{Core, Vertex};
+use crate::core::color;
+use crate::core::primitive::{Instance, Primitive};
+use crate::text::{atlas::{Atlas, GlyphKey}, FontId, Fonts, TextRun};
+
+/// Start of a run of geometry sharing a clip rectangle and drawn primitives, then triangles, then
+/// text
+#[derive(Copy, Clone, Debug, PartialEq)]
+struct Segment {
+    /// x, y, width, height in pixels, `None` draws unclipped
+    clip: Option<[f32; 4]>,
+    primitives: usize,
+    indices: usize,
+    text: usize,
+}
+
+/// Geometry collected over a frame - primitives are drawn in one instanced draw call, then
+/// triangles in a single indexed draw call, then text in one instanced draw call. Every change of
+/// clip rectangle, and every primitive or triangle added after something drawn later, splits
+/// those into another set of draw calls so geometry is painted in the order it was added
+#[derive(Default)]
+pub struct Batch {
+    pub vertices: Vec<Vertex>,
+    pub indices: Vec<u32>,
+    pub primitives: Vec<Primitive>,
+    pub text: Vec<TextRun>,
+    segments: Vec<Segment>,
+    clips: Vec<[f32; 4]>,
+    /// Latest stage added to the current segment
+    stage: Stage,
+}
+
+/// Draw calls of a segment, in the order they are made
+#[derive(Copy, Clone, Debug, Default, PartialEq, Eq, PartialOrd, Ord)]
+enum Stage {
+    #[default]
+    Empty,
+    Primitives,
+    Triangles,
+    Text,
+}
+
+impl Batch {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    pub fn clear(&mut self) {
+        self.vertices.clear();
+        self.indices.clear();
+        self.primitives.clear();
+        self.text.clear();
+        self.segments.clear();
+        self.clips.clear();
+        self.stage = Stage::Empty;
+    }
+
+    /// Clip everything added until the matching [`Batch::pop_clip`] to `rect` - nested clips
+    /// are intersected
+    pub fn push_clip(&mut self, rect: [f32; 4]) {
+        let clip = match self.clips.last() {
+            Some(&[x, y, w, h]) => {
+                let (left, top) = (rect[0].max(x), rect[1].max(y));
+                let right = (rect[0] + rect[2]).min(x + w);
+                let bottom = (rect[1] + rect[3]).min(y + h);
+                [left, top, (right - left).max(0.0), (bottom - top).max(0.0)]
+            },
+            None => rect,
+        };
+        self.clips.push(clip);
+        self.segment();
+    }
+
+    pub fn pop_clip(&mut self) {
+        self.clips.pop();
+        self.segment();
+    }
+
+    fn segment(&mut self) {
+        self.stage = Stage::Empty;
+        let segment = Segment {
+            clip: self.clips.last().copied(),
+            primitives: self.primitives.len(),
+            indices: self.indices.len(),
+            text: self.text.len(),
+        };
+
+        // Nothing was drawn under the previous clip, so it can be replaced
+        match self.segments.last_mut() {
+            Some(last) if (last.primitives, last.indices, last.text) == (segment.primitives, segment.indices, segment.text) => {
+                *last = segment;
+            },
+            _ => self.segments.push(segment),
+        }
+    }
+
+    /// Start a new segment when `stage` would otherwise be drawn beneath geometry added before it
+    fn stage(&mut self, stage: Stage) {
+        if stage < self.stage {
+            self.segment();
+        }
+        self.stage = stage;
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.indices.is_empty() && self.primitives.is_empty() && self.text.is_empty()
+    }
+
+    /// Add a run of text, shaped and rasterized when the batch is rendered
+    pub fn text(&mut self, run: TextRun) {
+        self.stage(Stage::Text);
+        self.text.push(run);
+    }
+
+    /// Add a rounded rectangle, border or shadow
+    pub fn primitive(&mut self, primitive: Primitive) {
+        self.stage(Stage::Primitives);
+        self.primitives.push(primitive);
+    }
+
+    /// Add a triangle - positions are in pixels with the origin at the top left
+    pub fn triangle(&mut self, a: [f32; 2], b: [f32; 2], c: [f32; 2], color: [f32; 3]) {
+        self.stage(Stage::Triangles);
+        let base = self.vertices.len() as u32;
+        for [x, y] in [a, b, c] {
+            self.vertices.push(Vertex { position: [x, y, 0.0], color });
+        }
+        self.indices.extend_from_slice(&[base, base + 1, base + 2]);
+    }
+
+    /// Add an axis aligned rectangle
+    pub fn quad(&mut self, x: f32, y: f32, width: f32, height: f32, color: [f32; 3]) {
+        self.stage(Stage::Triangles);
+        let base = self.vertices.len() as u32;
+        for (x, y) in [(x, y), (x + width, y), (x + width, y + height), (x, y + height)] {
+            self.vertices.push(Vertex { position: [x, y, 0.0], color });
+        }
+        self.indices.extend_from_slice(&[base, base + 1, base + 2, base, base + 2, base + 3]);
+    }
+}
+
+#[repr(C)]
+#[derive(Copy, Clone, bytemuck::Pod, bytemuck::Zeroable)]
+struct Globals {
+    viewport: [f32; 2],
+    /// 1 when the target has an sRGB format, so shaders decode the sRGB colors of the batch
+    linear: f32,
+    // Uniforms are 16 byte aligned
+    _padding: f32,
+}
+
+/// A glyph quad sampling the atlas, one per instance
+#[repr(C)]
+#[derive(Copy, Clone, bytemuck::Pod, bytemuck::Zeroable)]
+struct Glyph {
+    rect: [f32; 4],
+    uv: [f32; 4],
+    color: [f32; 4],
+}
+
+impl Glyph {
+    const ATTRIBUTES: [wgpu::VertexAttribute; 3] = wgpu::vertex_attr_array![0 => Float32x4, 1 => Float32x4, 2 => Float32x4];
+
+    fn layout() -> wgpu::VertexBufferLayout<'static> {
+        wgpu::VertexBufferLayout {
+            array_stride: std::mem::size_of::<Self>() as wgpu::BufferAddress,
+            step_mode: wgpu::VertexStepMode::Instance,
+            attributes: &Self::ATTRIBUTES,
+        }
+    }
+}
+
+struct Pipelines {
+    format: wgpu::TextureFormat,
+    geometry: wgpu::RenderPipeline,
+    primitive: wgpu::RenderPipeline,
+    text: wgpu::RenderPipeline,
+}
+
+/// Draws [`Batch`]es into any render target - the pipelines are built lazily for the format of
+/// the target, so the same renderer serves window surfaces and headless textures
+pub struct Renderer {
+    /// Fonts available to text runs, also used to measure text for layout
+    pub fonts: Fonts,
+    /// Fonts that failed to shape a run, already warned about
+    unshaped: HashSet<FontId>,
+    pipelines: Option<Pipelines>,
+    globals: wgpu::Buffer,
+    bind_group_layout: wgpu::BindGroupLayout,
+    bind_group: wgpu::BindGroup,
+    vertex_buffer: wgpu::Buffer,
+    index_buffer: wgpu::Buffer,
+    instance_buffer: wgpu::Buffer,
+    glyph_buffer: wgpu::Buffer,
+
+    atlas: Atlas,
+    /// Set once a glyph did not fit, so a full atlas is reported once rather than every frame
+    atlas_full: bool,
+    atlas_texture: wgpu::Texture,
+    atlas_layout: wgpu::BindGroupLayout,
+    atlas_bind_group: wgpu::BindGroup,
+}
+
+impl Renderer {
+    /// Initial vertex capacity - buffers double when a batch outgrows them
+    const CAPACITY: u64 = 1024;
+    /// Width and height of the glyph atlas texture
+    const ATLAS_SIZE: u32 = 1024;
+
+    pub(crate) fn new(context: &Core) -> Self {
+        let device = &context.device;
+
+        let globals = device.create_buffer_init(&wgpu::util::BufferInitDescriptor {
+            label: Some("Globals Buffer"),
+            contents: bytemuck::bytes_of(&Globals { viewport: [1.0, 1.0], linear: 0.0, _padding: 0.0 }),
+            usage: wgpu::BufferUsages::UNIFORM | wgpu::BufferUsages::COPY_DST,
+        });
+
+        let bind_group_layout = device.create_bind_group_layout(&wgpu::BindGroupLayoutDescriptor {
+            label: Some("Globals Layout"),
+            entries: &[wgpu::BindGroupLayoutEntry {
+                binding: 0,
+                visibility: wgpu::ShaderStages::VERTEX_FRAGMENT,
+                ty: wgpu::BindingType::Buffer {
+                    ty: wgpu::BufferBindingType::Uniform,
+                    has_dynamic_offset: false,
+                    min_binding_size: None,
+                },
+                count: None,
+            }],
+        });
+
+        let bind_group = device.create_bind_group(&wgpu::BindGroupDescriptor {
+            label: Some("Globals Bind Group"),
+            layout: &bind_group_layout,
+            entries: &[wgpu::BindGroupEntry {
+                binding: 0,
+                resource: globals.as_entire_binding(),
+            }],
+        });
+
+        let atlas_texture = device.create_texture(&wgpu::TextureDescriptor {
+            label: Some("Glyph Atlas"),
+            size: wgpu::Extent3d { width: Self::ATLAS_SIZE, height: Self::ATLAS_SIZE, depth_or_array_layers: 1 },
+            mip_level_count: 1,
+            sample_count: 1,
+            dimension: wgpu::TextureDimension::D2,
+            format: wgpu::TextureFormat::R8Unorm,
+            usage: wgpu::TextureUsages::TEXTURE_BINDING | wgpu::TextureUsages::COPY_DST,
+            view_formats: &[],
+        });
+
+        let atlas_layout = device.create_bind_group_layout(&wgpu::BindGroupLayoutDescriptor {
+            label: Some("Glyph Atlas Layout"),
+            entries: &[
+                wgpu::BindGroupLayoutEntry {
+                    binding: 0,
+                    visibility: wgpu::ShaderStages::FRAGMENT,
+                    ty: wgpu::BindingType::Texture {
+                        sample_type: wgpu::TextureSampleType::Float { filterable: true },
+                        view_dimension: wgpu::TextureViewDimension::D2,
+                        multisampled: false,
+                    },
+                    count: None,
+                },
+                wgpu::BindGroupLayoutEntry {
+                    binding: 1,
+                    visibility: wgpu::ShaderStages::FRAGMENT,
+                    ty: wgpu::BindingType::Sampler(wgpu::SamplerBindingType::Filtering),
+                    count: None,
+                },
+            ],
+        });
+
+        let sampler = device.create_sampler(&wgpu::SamplerDescriptor {
+            label: Some("Glyph Atlas Sampler"),
+            mag_filter: wgpu::FilterMode::Linear,
+            min_filter: wgpu::FilterMode::Linear,
+            ..Default::default()
+        });
+
+        let atlas_bind_group = device.create_bind_group(&wgpu::BindGroupDescriptor {
+            label: Some("Glyph Atlas Bind Group"),
+            layout: &atlas_layout,
+            entries: &[
+                wgpu::BindGroupEntry {
+                    binding: 0,
+                    resource: wgpu::BindingResource::TextureView(&atlas_texture.create_view(&wgpu::TextureViewDescriptor::default())),
+                },
+                wgpu::BindGroupEntry {
+                    binding: 1,
+                    resource: wgpu::BindingResource::Sampler(&sampler),
+                },
+            ],
+        });
+
+        Self {
+            fonts: Fonts::new(),
+            unshaped: HashSet::new(),
+            atlas: Atlas::new(Self::ATLAS_SIZE),
+            atlas_full: false,
+            atlas_texture,
+            atlas_layout,
+            atlas_bind_group,
+            glyph_buffer: Self::buffer(device, "Glyph Buffer", wgpu::BufferUsages::VERTEX, Self::glyph_size(Self::CAPACITY)),
+            pipelines: None,
+            vertex_buffer: Self::buffer(device, "Vertex Buffer", wgpu::BufferUsages::VERTEX, Self::vertex_size(Self::CAPACITY)),
+            index_buffer: Self::buffer(device, "Index Buffer", wgpu::BufferUsages::INDEX, Self::index_size(Self::CAPACITY)),
+            instance_buffer: Self::buffer(device, "Instance Buffer", wgpu::BufferUsages::VERTEX, Self::instance_size(Self::CAPACITY)),
+            globals,
+            bind_group_layout,
+            bind_group,
+        }
+    }
+
+    fn vertex_size(count: u64) -> u64 {
+        count * std::mem::size_of::<Vertex>() as u64
+    }
+
+    fn index_size(count: u64) -> u64 {
+        count * std::mem::size_of::<u32>() as u64
+    }
+
+    fn instance_size(count: u64) -> u64 {
+        count * std::mem::size_of::<Instance>() as u64
+    }
+
+    fn glyph_size(count: u64) -> u64 {
+        count * std::mem::size_of::<Glyph>() as u64
+    }
+
+    fn buffer(device: &wgpu::Device, label: &str, usage: wgpu::BufferUsages, size: u64) -> wgpu::Buffer {
+        device.create_buffer(&wgpu::BufferDescriptor {
+            label: Some(label),
+            size,
+            usage: usage | wgpu::BufferUsages::COPY_DST,
+            mapped_at_creation: false,
+        })
+    }
+
+    fn pipelines(&mut self, device: &wgpu::Device, format: wgpu::TextureFormat) {
+        if matches!(self.pipelines, Some(Pipelines { format: f, .. }) if f == format) {
+            return;
+        }
+
+        let layout = device.create_pipeline_layout(&wgpu::PipelineLayoutDescriptor {
+            label: Some("Render Pipeline Layout"),
+            bind_group_layouts: &[&self.bind_group_layout],
+            push_constant_ranges: &[],
+        });
+
+        let geometry = Self::pipeline(
+            device,
+            &layout,
+            &device.create_shader_module(wgpu::include_wgsl!("shader.wgsl")),
+            Vertex::layout(),
+            wgpu::ColorTargetState {
+                format,
+                blend: Some(wgpu::BlendState::REPLACE),
+                write_mask: wgpu::ColorWrites::ALL,
+            },
+        );
+
+        let primitive = Self::pipeline(
+            device,
+            &layout,
+            &device.create_shader_module(wgpu::include_wgsl!("primitive.wgsl")),
+            Instance::layout(),
+            wgpu::ColorTargetState {
+                format,
+                // The fragment shader outputs premultiplied colours for antialiased edges
+                blend: Some(wgpu::BlendState::PREMULTIPLIED_ALPHA_BLENDING),
+                write_mask: wgpu::ColorWrites::ALL,
+            },
+        );
+
+        let text = Self::pipeline(
+            device,
+            &device.create_pipeline_layout(&wgpu::PipelineLayoutDescriptor {
+                label: Some("Text Pipeline Layout"),
+                bind_group_layouts: &[&self.bind_group_layout, &self.atlas_layout],
+                push_constant_ranges: &[],
+            }),
+            &device.create_shader_module(wgpu::include_wgsl!("text.wgsl")),
+            Glyph::layout(),
+            wgpu::ColorTargetState {
+                format,
+                blend: Some(wgpu::BlendState::PREMULTIPLIED_ALPHA_BLENDING),
+                write_mask: wgpu::ColorWrites::ALL,
+            },
+        );
+
+        self.pipelines = Some(Pipelines { format, geometry, primitive, text });
+    }
+
+    fn pipeline(
+        device: &wgpu::Device,
+        layout: &wgpu::PipelineLayout,
+        shader: &wgpu::ShaderModule,
+        buffer: wgpu::VertexBufferLayout,
+        target: wgpu::ColorTargetState,
+    ) -> wgpu::RenderPipeline {
+        device.create_render_pipeline(&wgpu::RenderPipelineDescriptor {
+            label: Some("Render Pipeline"),
+            layout: Some(layout),
+            vertex: wgpu::VertexState {
+                module: shader,
+                entry_point: "vs_main",
+                buffers: &[buffer],
+            },
+            fragment: Some(wgpu::FragmentState {
+                module: shader,
+                entry_point: "fs_main",
+                targets: &[Some(target)],
+            }),
+            primitive: wgpu::PrimitiveState {
+                topology: wgpu::PrimitiveTopology::TriangleList,
+                // 2D geometry may be wound either way
+                cull_mode: None,
+                ..Default::default()
+            },
+            depth_stencil: None,
+            multisample: wgpu::MultisampleState::default(),
+            multiview: None,
+        })
+    }
+
+    /// Shape every text run in `batch`, uploading glyphs missing from the atlas - also returns
+    /// the index of the first glyph of each run
+    fn glyphs(&mut self, context: &Core, batch: &Batch) -> (Vec<Glyph>, Vec<usize>) {
+        self.atlas.begin_frame();
+        let atlas_size = self.atlas.size() as f32;
+        let mut glyphs = Vec::new();
+        let mut runs = Vec::with_capacity(batch.text.len() + 1);
+
+        for run in &batch.text {
+            runs.push(glyphs.len());
+            let shaped = match self.fonts.shape(run.font, &run.text, run.size) {
+                Ok(shaped) => shaped,
+                // Without any font text is simply not drawn - otherwise warn once per font
+                Err(_) if self.fonts.is_empty() => continue,
+                Err(e) => {
+                    if self.unshaped.insert(run.font) {
+                        warning!("Could not shape text: {e}");
+                    }
+                    continue;
+                }
+            };
+
+            for g in &shaped.glyphs {
+                let key = GlyphKey::new(run.font, g.glyph, run.size);
+                let entry = match self.atlas.get(&key) {
+                    Some(entry) => entry,
+                    None => {
+                        let Some(([width, height], bearing, pixels)) = self.fonts.rasterize(run.font, g.glyph, run.size) else {
+                            // Nothing to draw, i.e. whitespace
+                            continue;
+                        };
+                        let Some(entry) = self.atlas.insert(key, width, height, bearing) else {
+                            if !std::mem::replace(&mut self.atlas_full, true) {
+                                warning!("Glyph atlas is full - text in this frame does not fit");
+                            }
+                            continue;
+                        };
+
+                        context.queue.write_texture(
+                            wgpu::ImageCopyTexture {
+                                texture: &self.atlas_texture,
+                                mip_level: 0,
+                                origin: wgpu::Origin3d { x: entry.rect[0], y: entry.rect[1], z: 0 },
+                                aspect: wgpu::TextureAspect::All,
+                            },
+                            &pixels,
+                            wgpu::ImageDataLayout { offset: 0, bytes_per_row: Some(width), rows_per_image: Some(height) },
+                            wgpu::Extent3d { width, height, depth_or_array_layers: 1 },
+                        );
+
+                        entry
+                    }
+                };
+
+                // Snap the pen to whole pixels so glyphs stay crisp
+                let x = (run.position[0] + g.x).round() + entry.bearing[0];
+                let y = (run.position[1] + shaped.metrics.ascent + g.y).round() + entry.bearing[1];
+                let [u, v, w, h] = entry.rect.map(|n| n as f32);
+
+                glyphs.push(Glyph {
+                    rect: [x, y, w, h],
+                    uv: [u / atlas_size, v / atlas_size, (u + w) / atlas_size, (v + h) / atlas_size],
+                    color: run.color,
+                });
+            }
+        }
+
+        runs.push(glyphs.len());
+
+        (glyphs, runs)
+    }
+
+    /// Grow the vertex, index, instance and glyph buffers to fit `batch`
+    fn reserve(&mut self, device: &wgpu::Device, batch: &Batch, glyphs: usize) {
+        let vertices = Self::vertex_size(batch.vertices.len() as u64);
+        if vertices > self.vertex_buffer.size() {
+            let size = vertices.next_power_of_two();
+            self.vertex_buffer = Self::buffer(device, "Vertex Buffer", wgpu::BufferUsages::VERTEX, size);
+        }
+
+        let indices = Self::index_size(batch.indices.len() as u64);
+        if indices > self.index_buffer.size() {
+            let size = indices.next_power_of_two();
+            self.index_buffer = Self::buffer(device, "Index Buffer", wgpu::BufferUsages::INDEX, size);
+        }
+
+        let instances = Self::instance_size(batch.primitives.len() as u64);
+        if instances > self.instance_buffer.size() {
+            let size = instances.next_power_of_two();
+            self.instance_buffer = Self::buffer(device, "Instance Buffer", wgpu::BufferUsages::VERTEX, size);
+        }
+
+        let glyphs = Self::glyph_size(glyphs as u64);
+        if glyphs > self.glyph_buffer.size() {
+            let size = glyphs.next_power_of_two();
+            self.glyph_buffer = Self::buffer(device, "Glyph Buffer", wgpu::BufferUsages::VERTEX, size);
+        }
+    }
+
+    /// Clear `view` to `clear_color` and draw `batch` on top of it - colors are sRGB encoded and
+    /// converted for `format`
+    pub(crate) fn render(
+        &mut self,
+        context: &Core,
+        view: &wgpu::TextureView,
+        format: wgpu::TextureFormat,
+        size: (u32, u32),
+        clear_color: wgpu::Color,
+        batch: &Batch,
+    ) {
+        let linear = if format.is_srgb() { 1.0 } else { 0.0 };
+        let globals = Globals { viewport: [size.0 as f32, size.1 as f32], linear, _padding: 0.0 };
+        context.queue.write_buffer(&self.globals, 0, bytemuck::bytes_of(&globals));
+
+        let (glyphs, runs) = self.glyphs(context, batch);
+        self.reserve(&context.device, batch, glyphs.len());
+        if !batch.indices.is_empty() {
+            context.queue.write_buffer(&self.vertex_buffer, 0, bytemuck::cast_slice(&batch.vertices));
+            // Buffer writes must be a multiple of 4 bytes, which u32 indices always are
+            context.queue.write_buffer(&self.index_buffer, 0, bytemuck::cast_slice(&batch.indices));
+        }
+        if !batch.primitives.is_empty() {
+            let instances: Vec<Instance> = batch.primitives.iter().map(Instance::from).collect();
+            context.queue.write_buffer(&self.instance_buffer, 0, bytemuck::cast_slice(&instances));
+        }
+        if !glyphs.is_empty() {
+            context.queue.write_buffer(&self.glyph_buffer, 0, bytemuck::cast_slice(&glyphs));
+        }
+        self.pipelines(&context.device, format);
+        let pipelines = self.pipelines.as_ref().unwrap();
+
+        let mut encoder = context.device.create_command_encoder(&wgpu::CommandEncoderDescriptor {
+            label: Some("Frame Encoder"),
+        });
+
+        {
+            let mut pass = encoder.begin_render_pass(&wgpu::RenderPassDescriptor {
+                label: Some("Frame Pass"),
+                color_attachments: &[Some(wgpu::RenderPassColorAttachment {
+                    view,
+                    resolve_target: None,
+                    ops: wgpu::Operations {
+                        load: wgpu::LoadOp::Clear(color::for_format(clear_color, format)),
+                        store: wgpu::StoreOp::Store,
+                    },
+                })],
+                depth_stencil_attachment: None,
+                timestamp_writes: None,
+                occlusion_query_set: None,
+            });
+
+            pass.set_bind_group(0, &self.bind_group, &[]);
+            if !batch.indices.is_empty() {
+                pass.set_index_buffer(self.index_buffer.slice(..Self::index_size(batch.indices.len() as u64)), wgpu::IndexFormat::Uint32);
+            }
+
+            let end = Segment { clip: None, primitives: batch.primitives.len(), indices: batch.indices.len(), text: batch.text.len() };
+            let first = Segment { clip: None, primitives: 0, indices: 0, text: 0 };
+            let segments: Vec<Segment> = std::iter::once(first).chain(batch.segments.iter().copied()).collect();
+
+            for (segment, next) in segments.iter().zip(segments.iter().skip(1).chain(std::iter::once(&end))) {
+                let [x, y, w, h] = match segment.clip {
+                    Some([x, y, w, h]) => {
+                        // Scissor rectangles must lie inside the target
+                        let (x0, y0) = (x.max(0.0).min(size.0 as f32) as u32, y.max(0.0).min(size.1 as f32) as u32);
+                        let x1 = (x + w).max(0.0).min(size.0 as f32).ceil() as u32;
+                        let y1 = (y + h).max(0.0).min(size.1 as f32).ceil() as u32;
+                        [x0, y0, x1.saturating_sub(x0), y1.saturating_sub(y0)]
+                    },
+                    None => [0, 0, size.0, size.1],
+                };
+                if w == 0 || h == 0 {
+                    continue;
+                }
+                pass.set_scissor_rect(x, y, w, h);
+
+                if segment.primitives < next.primitives {
+                    pass.set_pipeline(&pipelines.primitive);
+                    pass.set_vertex_buffer(0, self.instance_buffer.slice(..Self::instance_size(batch.primitives.len() as u64)));
+                    // Six vertices per instance, expanded to a quad in the vertex shader
+                    pass.draw(0..6, segment.primitives as u32..next.primitives as u32);
+                }
+
+                if segment.indices < next.indices {
+                    pass.set_pipeline(&pipelines.geometry);
+                    pass.set_vertex_buffer(0, self.vertex_buffer.slice(..Self::vertex_size(batch.vertices.len() as u64)));
+                    pass.draw_indexed(segment.indices as u32..next.indices as u32, 0, 0..1);
+                }
+
+                let (first, last) = (runs[segment.text], runs[next.text]);
+                if first < last {
+                    pass.set_pipeline(&pipelines.text);
+                    pass.set_bind_group(1, &self.atlas_bind_group, &[]);
+                    pass.set_vertex_buffer(0, self.glyph_buffer.slice(..Self::glyph_size(glyphs.len() as u64)));
+                    pass.draw(0..6, first as u32..last as u32);
+                }
+            }
+        }
+
+        context.queue.submit(std::iter::once(encoder.finish()));
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn rect() -> Primitive {
+        Primitive::rect(0.0, 0.0, 10.0, 10.0, [1.0; 4])
+    }
+
+    fn run() -> TextRun {
+        TextRun { font: FontId(0), size: 12.0, position: [0.0; 2], color: [1.0; 4], text: "a".into() }
+    }
+
+    fn segment(clip: Option<[f32; 4]>, primitives: usize, indices: usize, text: usize) -> Segment {
+        Segment { clip, primitives, indices, text }
+    }
+
+    #[test]
+    fn stages_in_draw_order_share_one_segment() {
+        let mut batch = Batch::new();
+        batch.primitive(rect());
+        batch.primitive(rect());
+        batch.quad(0.0, 0.0, 5.0, 5.0, [1.0; 3]);
+        batch.triangle([0.0; 2], [1.0, 0.0], [0.0, 1.0], [1.0; 3]);
+        batch.text(run());
+
+        assert!(batch.segments.is_empty());
+        assert_eq!((batch.primitives.len(), batch.indices.len(), batch.text.len()), (2, 9, 1));
+    }
+
+    #[test]
+    fn geometry_added_after_later_stages_starts_a_segment() {
+        let mut batch = Batch::new();
+        batch.text(run());
+        batch.primitive(rect());
+        batch.quad(0.0, 0.0, 5.0, 5.0, [1.0; 3]);
+        batch.primitive(rect());
+        batch.text(run());
+        batch.triangle([0.0; 2], [1.0, 0.0], [0.0, 1.0], [1.0; 3]);
+
+        assert_eq!(batch.segments, [
+            segment(None, 0, 0, 1),
+            segment(None, 1, 6, 1),
+            segment(None, 2, 6, 2),
+        ]);
+    }
+
+    #[test]
+    fn nested_clips_intersect_and_restore() {
+        let mut batch = Batch::new();
+        batch.primitive(rect());
+        batch.push_clip([0.0, 0.0, 100.0, 100.0]);
+        batch.primitive(rect());
+        batch.push_clip([50.0, 60.0, 100.0, 100.0]);
+        batch.text(run());
+        batch.pop_clip();
+        batch.primitive(rect());
+        batch.pop_clip();
+        batch.text(run());
+
+        assert_eq!(batch.segments, [
+            segment(Some([0.0, 0.0, 100.0, 100.0]), 1, 0, 0),
+            segment(Some([50.0, 60.0, 50.0, 40.0]), 2, 0, 0),
+            segment(Some([0.0, 0.0, 100.0, 100.0]), 2, 0, 1),
+            segment(None, 3, 0, 1),
+        ]);
+    }
+
+    #[test]
+    fn clips_with_nothing_drawn_are_replaced() {
+        let mut batch = Batch::new();
+        batch.push_clip([0.0, 0.0, 10.0, 10.0]);
+        batch.push_clip([20.0, 20.0, 10.0, 10.0]);
+        batch.pop_clip();
+        batch.primitive(rect());
+        assert_eq!(batch.segments, [segment(Some([0.0, 0.0, 10.0, 10.0]), 0, 0, 0)]);
+
+        // Disjoint clips intersect to nothing rather than a negative size
+        batch.push_clip([20.0, 20.0, 10.0, 10.0]);
+        batch.primitive(rect());
+        assert_eq!(batch.segments[1], segment(Some([20.0, 20.0, 0.0, 0.0]), 1, 0, 0));
+
+        batch.clear();
+        assert!(batch.is_empty() && batch.segments.is_empty() && batch.clips.is_empty());
+    }
+}
@@ -1,20 +1,46 @@
+struct Globals {
+    // Size of the render target in pixels
+    viewport: vec2<f32>,
+    // 1 when the target has an sRGB format
+    linear: f32,
+}
+
+@group(0) @binding(0)
+var<uniform> globals: Globals;
+
+// Colors arrive sRGB encoded - sRGB targets take linear values and encode them on store
+fn to_target(color: vec4<f32>) -> vec4<f32> {
+    if globals.linear == 0.0 {
+        return color;
+    }
+    let c = color.rgb;
+    let decoded = select(pow((c + 0.055) / 1.055, vec3<f32>(2.4)), c / 12.92, c <= vec3<f32>(0.04045));
+    return vec4<f32>(decoded, color.a);
+}
+
+struct VertexInput {
+    @location(0) position: vec3<f32>,
+    @location(1) color: vec3<f32>,
+}
+
 struct VertexOutput {
     @builtin(position) clip_position: vec4<f32>,
-    @location(0) position: vec2<f32>,
+    @location(0) color: vec3<f32>,
 }
 
 @vertex
-fn vs_main(@builtin(vertex_index) in_vertex_index: u32,) -> VertexOutput {
+fn vs_main(in: VertexInput) -> VertexOutput {
     var out: VertexOutput;
-    let x = f32(1 - i32(in_vertex_index)) * 0.5;
-    let y = f32(i32(in_vertex_index & 1u) * 2 - 1) * 0.5;
-    out.clip_position = vec4<f32>(x, y, 0.0, 1.0);
-    out.position = vec2<f32>(x, y);
-    
+    // Pixel coordinates (origin top-left) to clip space
+    let x = in.position.x / globals.viewport.x * 2.0 - 1.0;
+    let y = 1.0 - in.position.y / globals.viewport.y * 2.0;
+    out.clip_position = vec4<f32>(x, y, in.position.z, 1.0);
+    out.color = to_target(vec4<f32>(in.color, 1.0)).rgb;
+
     return out;
 }
 
 @fragment
 fn fs_main(in: VertexOutput) -> @location(0) vec4<f32> {
-    return vec4<f32>(in.position, 0.5, 1.0);
-}
\ No newline at end of file
+    return vec4<f32>(in.color, 1.0);
+}
@@ -0,0 +1,296 @@
+use std::path::{Path, PathBuf};
+use logfather::*;
+use crate::core::error::KozyError;
+use crate::core::headless::Headless;
+use crate::structure::window::Window;
+
+/// Set to regenerate golden images instead of comparing against them
+pub const UPDATE_ENV: &str = "KOZY_UPDATE_SNAPSHOTS";
+
+#[derive(Debug)]
+pub enum SnapshotError {
+    Io(std::io::Error),
+    Decode(png::DecodingError),
+    Encode(png::EncodingError),
+    /// The frame could not be rendered or read back
+    Render(KozyError),
+    /// The golden image is not 8-bit RGBA
+    Format(PathBuf),
+    /// No golden image to compare against - the frame was written next to where it belongs as
+    /// `<name>.actual.png`
+    Missing(PathBuf),
+    SizeMismatch { expected: (u32, u32), actual: (u32, u32) },
+    /// `mismatched` pixels exceeded the tolerance - the diff image was written to `diff`
+    Mismatch { mismatched: usize, total: usize, diff: PathBuf },
+}
+
+impl std::fmt::Display for SnapshotError {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            SnapshotError::Io(e) => write!(f, "snapshot io error: {e}"),
+            SnapshotError::Decode(e) => write!(f, "could not decode golden image: {e}"),
+            SnapshotError::Encode(e) => write!(f, "could not encode snapshot image: {e}"),
+            SnapshotError::Render(e) => write!(f, "could not render snapshot: {e}"),
+            SnapshotError::Format(path) => write!(f, "golden image {} is not 8-bit RGBA", path.display()),
+            SnapshotError::Missing(path) => write!(
+                f, "golden image {} is missing - set {UPDATE_ENV} to create it",
+                path.display(),
+            ),
+            SnapshotError::SizeMismatch { expected, actual } => write!(
+                f, "snapshot size {}x{} does not match golden {}x{}",
+                actual.0, actual.1, expected.0, expected.1,
+            ),
+            SnapshotError::Mismatch { mismatched, total, diff } => write!(
+                f, "{mismatched} of {total} pixels differ from the golden image, see {}",
+                diff.display(),
+            ),
+        }
+    }
+}
+
+impl std::error::Error for SnapshotError {}
+
+impl From<std::io::Error> for SnapshotError {
+    fn from(e: std::io::Error) -> Self {
+        SnapshotError::Io(e)
+    }
+}
+
+impl From<KozyError> for SnapshotError {
+    fn from(e: KozyError) -> Self {
+        SnapshotError::Render(e)
+    }
+}
+
+impl From<png::DecodingError> for SnapshotError {
+    fn from(e: png::DecodingError) -> Self {
+        SnapshotError::Decode(e)
+    }
+}
+
+impl From<png::EncodingError> for SnapshotError {
+    fn from(e: png::EncodingError) -> Self {
+        SnapshotError::Encode(e)
+    }
+}
+
+/// An RGBA8 image, rows tightly packed
+pub struct Image {
+    pub width: u32,
+    pub height: u32,
+    pub pixels: Vec<u8>,
+}
+
+impl Image {
+    pub fn load(path: &Path) -> Result<Self, SnapshotError> {
+        let decoder = png::Decoder::new(std::fs::File::open(path)?);
+        let mut reader = decoder.read_info()?;
+        let mut pixels = vec![0; reader.output_buffer_size()];
+        let info = reader.next_frame(&mut pixels)?;
+
+        if info.color_type != png::ColorType::Rgba || info.bit_depth != png::BitDepth::Eight {
+            return Err(SnapshotError::Format(path.to_owned()));
+        }
+        pixels.truncate(info.buffer_size());
+
+        Ok(Self { width: info.width, height: info.height, pixels })
+    }
+
+    pub fn save(&self, path: &Path) -> Result<(), SnapshotError> {
+        if let Some(parent) = path.parent() {
+            std::fs::create_dir_all(parent)?;
+        }
+
+        let file = std::io::BufWriter::new(std::fs::File::create(path)?);
+        let mut encoder = png::Encoder::new(file, self.width, self.height);
+        encoder.set_color(png::ColorType::Rgba);
+        encoder.set_depth(png::BitDepth::Eight);
+        encoder.write_header()?.write_image_data(&self.pixels)?;
+
+        Ok(())
+    }
+}
+
+/// Compares rendered frames against golden PNGs stored in `directory`
+pub struct Snapshot {
+    directory: PathBuf,
+    /// Largest per-channel difference for two pixels to still be considered equal
+    tolerance: u8,
+    /// Number of pixels allowed to exceed `tolerance` before the comparison fails
+    max_mismatched: usize,
+}
+
+impl Snapshot {
+    pub fn new(directory: impl Into<PathBuf>) -> Self {
+        Self { directory: directory.into(), tolerance: 0, max_mismatched: 0 }
+    }
+
+    pub fn tolerance(mut self, tolerance: u8) -> Self {
+        self.tolerance = tolerance;
+        self
+    }
+
+    pub fn max_mismatched(mut self, max_mismatched: usize) -> Self {
+        self.max_mismatched = max_mismatched;
+        self
+    }
+
+    fn path(&self, name: &str, suffix: &str) -> PathBuf {
+        self.directory.join(format!("{name}{suffix}.png"))
+    }
+
+    /// Compare `actual` against the golden image `name` - golden images are only written when
+    /// `KOZY_UPDATE_SNAPSHOTS` is set, so a missing one fails. On failure the frame is written next
+    /// to the golden image as `<name>.actual.png` along with `<name>.diff.png`
+    pub fn compare(&self, name: &str, actual: &Image) -> Result<(), SnapshotError> {
+        let golden = self.path(name, "");
+
+        if std::env::var_os(UPDATE_ENV).is_some() {
+            let path = golden.display();
+            info!("Writing golden image {path}");
+            return actual.save(&golden);
+        }
+
+        if !golden.exists() {
+            actual.save(&self.path(name, ".actual"))?;
+            return Err(SnapshotError::Missing(golden));
+        }
+
+        let expected = Image::load(&golden)?;
+        if (expected.width, expected.height) != (actual.width, actual.height) {
+            actual.save(&self.path(name, ".actual"))?;
+            return Err(SnapshotError::SizeMismatch {
+                expected: (expected.width, expected.height),
+                actual: (actual.width, actual.height),
+            });
+        }
+
+        let (diff, mismatched) = self.diff(&expected, actual);
+        if mismatched <= self.max_mismatched {
+            return Ok(());
+        }
+
+        let diff_path = self.path(name, ".diff");
+        actual.save(&self.path(name, ".actual"))?;
+        diff.save(&diff_path)?;
+
+        Err(SnapshotError::Mismatch {
+            mismatched,
+            total: (actual.width * actual.height) as usize,
+            diff: diff_path,
+        })
+    }
+
+    /// Build a diff image - mismatched pixels are drawn red over a faded copy of the golden image
+    fn diff(&self, expected: &Image, actual: &Image) -> (Image, usize) {
+        let mut mismatched = 0;
+        let mut pixels = Vec::with_capacity(expected.pixels.len());
+
+        for (e, a) in expected.pixels.chunks_exact(4).zip(actual.pixels.chunks_exact(4)) {
+            let different = e.iter().zip(a).any(|(e, a)| e.abs_diff(*a) > self.tolerance);
+            if different {
+                mismatched += 1;
+                pixels.extend_from_slice(&[255, 0, 0, 255]);
+            } else {
+                let luma = (e[0] as u32 * 299 + e[1] as u32 * 587 + e[2] as u32 * 114) / 1000;
+                let faded = (luma / 4 + 191) as u8;
+                pixels.extend_from_slice(&[faded, faded, faded, 255]);
+            }
+        }
+
+        (Image { width: expected.width, height: expected.height, pixels }, mismatched)
+    }
+
+    /// Render `window` offscreen and compare it against the golden image `name`
+    pub fn window(&self, headless: &mut Headless, window: &Window, name: &str) -> Result<(), SnapshotError> {
+        headless.render_window(window);
+        let (width, height) = headless.size();
+        let actual = Image { width, height, pixels: headless.read_pixels()? };
+
+        self.compare(name, &actual)
+    }
+
+    /// Panicking form of [`Snapshot::window`] for use inside tests
+    pub fn assert_window(&self, headless: &mut Headless, window: &Window, name: &str) {
+        if let Err(e) = self.window(headless, window, name) {
+            panic!("Snapshot `{name}` failed: {e}");
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::core::color::Color;
+    use crate::core::error::KozyError;
+    use crate::structure::components::{Button, Component, Slider, Toggle};
+    use crate::structure::layout::{Align, Edges};
+    use crate::structure::theme::Theme;
+    use crate::structure::window::{Container, ContainerType};
+    use crate::text::Fonts;
+
+    fn image(width: u32, height: u32, pixel: [u8; 4]) -> Image {
+        Image { width, height, pixels: pixel.repeat((width * height) as usize) }
+    }
+
+    /// A fresh directory under the system temp directory
+    fn scratch(name: &str) -> PathBuf {
+        let directory = std::env::temp_dir().join(format!("kozy-snapshot-{name}-{}", std::process::id()));
+        let _ = std::fs::remove_dir_all(&directory);
+        directory
+    }
+
+    #[test]
+    fn missing_golden_images_fail_without_being_written() {
+        if std::env::var_os(UPDATE_ENV).is_some() {
+            return;
+        }
+        let directory = scratch("missing");
+        let result = Snapshot::new(&directory).compare("frame", &image(2, 2, [0, 0, 0, 255]));
+
+        assert!(matches!(result, Err(SnapshotError::Missing(path)) if path == directory.join("frame.png")));
+        assert!(!directory.join("frame.png").exists());
+        assert!(directory.join("frame.actual.png").exists());
+    }
+
+    #[test]
+    fn differences_within_the_tolerance_pass() {
+        let directory = scratch("tolerance");
+        image(4, 1, [100, 100, 100, 255]).save(&directory.join("frame.png")).unwrap();
+
+        let mut actual = image(4, 1, [102, 100, 100, 255]);
+        assert!(Snapshot::new(&directory).tolerance(2).compare("frame", &actual).is_ok());
+
+        actual.pixels[0] = 110;
+        assert!(Snapshot::new(&directory).tolerance(2).max_mismatched(1).compare("frame", &actual).is_ok());
+        let result = Snapshot::new(&directory).tolerance(2).compare("frame", &actual);
+        assert!(matches!(result, Err(SnapshotError::Mismatch { mismatched: 1, total: 4, .. })));
+        assert!(directory.join("frame.diff.png").exists());
+    }
+
+    #[test]
+    fn widgets_match_their_golden_image() {
+        let mut headless = match Headless::new(1, 1) {
+            Ok(headless) => headless,
+            Err(KozyError::NoAdapter) => return eprintln!("Skipping snapshot: no graphics adapter"),
+            Err(e) => panic!("{e}"),
+        };
+
+        let row = Container::new(ContainerType::Row)
+            .padding(Edges::all(16.0))
+            .spacing(16.0)
+            .align(Align::Center)
+            .component(Component::Button(Button::with_size("", Color::Korple, 96.0, 32.0)))
+            .component(Component::Toggle(Toggle::new(true)))
+            .component(Component::Slider(Slider::new(0.0, 1.0, 0.25)));
+        let mut window = Window::new(320.0, 64.0).theme(Theme::Dark).container(row);
+        window.restyle();
+        window.layout(&Fonts::new());
+
+        // Software and hardware rasterizers differ slightly along antialiased edges
+        Snapshot::new(concat!(env!("CARGO_MANIFEST_DIR"), "/snapshots"))
+            .tolerance(8)
+            .max_mismatched(64)
+            .assert_window(&mut headless, &window, "widgets");
+    }
+}
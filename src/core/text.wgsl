@@ -0,0 +1,66 @@
+struct Globals {
+    // Size of the render target in pixels
+    viewport: vec2<f32>,
+    // 1 when the target has an sRGB format
+    linear: f32,
+}
+
+@group(0) @binding(0)
+var<uniform> globals: Globals;
+
+// Colors arrive sRGB encoded - sRGB targets take linear values and encode them on store
+fn to_target(color: vec4<f32>) -> vec4<f32> {
+    if globals.linear == 0.0 {
+        return color;
+    }
+    let c = color.rgb;
+    let decoded = select(pow((c + 0.055) / 1.055, vec3<f32>(2.4)), c / 12.92, c <= vec3<f32>(0.04045));
+    return vec4<f32>(decoded, color.a);
+}
+
+@group(1) @binding(0)
+var atlas: texture_2d<f32>;
+@group(1) @binding(1)
+var atlas_sampler: sampler;
+
+struct GlyphInput {
+    // x, y, width, height in pixels
+    @location(0) rect: vec4<f32>,
+    // u0, v0, u1, v1 in the atlas
+    @location(1) uv: vec4<f32>,
+    @location(2) color: vec4<f32>,
+}
+
+struct VertexOutput {
+    @builtin(position) clip_position: vec4<f32>,
+    @location(0) uv: vec2<f32>,
+    @location(1) @interpolate(flat) color: vec4<f32>,
+}
+
+@vertex
+fn vs_main(@builtin(vertex_index) index: u32, in: GlyphInput) -> VertexOutput {
+    // Two triangles: 0-1-2, 2-1-3
+    var corners = array<u32, 6>(0u, 1u, 2u, 2u, 1u, 3u);
+    let c = corners[index];
+    let t = vec2<f32>(f32(c & 1u), f32(c >> 1u));
+    let position = in.rect.xy + in.rect.zw * t;
+
+    var out: VertexOutput;
+    out.clip_position = vec4<f32>(
+        position.x / globals.viewport.x * 2.0 - 1.0,
+        1.0 - position.y / globals.viewport.y * 2.0,
+        0.0,
+        1.0,
+    );
+    out.uv = mix(in.uv.xy, in.uv.zw, t);
+    out.color = to_target(in.color);
+
+    return out;
+}
+
+@fragment
+fn fs_main(in: VertexOutput) -> @location(0) vec4<f32> {
+    // The atlas stores coverage only
+    let a = in.color.a * textureSample(atlas, atlas_sampler, in.uv).r;
+    return vec4<f32>(in.color.rgb * a, a);
+}
@@ -1,11 +1,13 @@
 use logfather::*;
-mod core;
-use core::application;
+use kozy::core::application;
 
 fn main() {
     let _logger = Logger::new();
     
-    application::run("KoZy \\o/", 30.0);
+    if let Err(e) = application::run("KoZy \\o/", 30.0) {
+        error!("KoZy failed to start: {e}");
+        std::process::exit(1);
+    }
 }
 
 
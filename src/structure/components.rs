@@ -1,6 +1,20 @@
-use crate::application::color;
+use std::time::{Duration, Instant};
+use winit::event::{ElementState, MouseButton};
+use winit::keyboard::{Key, NamedKey};
+use crate::core::color;
+use crate::core::primitive::Primitive;
+use crate::core::renderer::Batch;
+use crate::structure::event::{Event, EventContext, Phase};
+use crate::structure::layout::{Rect, Size};
+use crate::structure::signal::{Binding, Signal};
+use crate::structure::style::{self, Element, States, Style};
+use crate::structure::widget::Widget;
+use crate::text::{FontId, Fonts, TextRun};
 
+pub use crate::structure::textbox::TextBox;
 
+
+#[allow(clippy::large_enum_variant)]
 pub enum Component {
     Button(Button),
     Label(Label),
@@ -30,51 +44,255 @@ pub enum Component {
     Command(Command),
     Overlay(Overlay),
 
-    Custom(Custom),
+    Custom(Box<dyn Widget>),
 }
 
 impl Component {
-    // pub fn render(&self) {
-    //     match *self {
-    //         Component::Button(c) => c.render(),
-    //         Component::Label(c) => c.render(),
-    //         Component::TextBox(c) => c.render(),
-    //         Component::Image(c) => c.render(),
-    //         Component::ToolTip(c) => c.render(),
-    //         Component::Toggle(c) => c.render(),
-    //         Component::Slider(c) => c.render(),
-    //         Component::DropdownList(c) => c.render(),
-    //         Component::ColorPicker(c) => c.render(),
-    //         Component::DatePicker(c) => c.render(),
-    //         Component::ValueInput(c) => c.render(),
-    //         Component::Table(c) => c.render(),
-    //         Component::Tree(c) => c.render(),
-    //         Component::List(c) => c.render(),
-    //         Component::Modal(c) => c.render(),
-    //         Component::Menu(c) => c.render(),
-    //         Component::DragAndDrop(c) => c.render(),
-    //         Component::ToolBar(c) => c.render(),
-    //         Component::MenuBar(c) => c.render(),
-    //         Component::ProgressBar(c) => c.render(),
-    //         Component::Spinner(c) => c.render(),
-    //         Component::Canvas(c) => c.render(),
-    //         Component::StatusBar(c) => c.render(),
-    //         Component::Accordion(c) => c.render(),
-    //         Component::Space(c) => c.render(),
-    //         Component::Command(c) => c.render(),
-    //         Component::Overlay(c) => c.render(),
-    //         Component::Custom(c) => c.render(),
-    //     }
-    // }
+    /// Wrap a widget from outside this crate
+    pub fn custom(widget: impl Widget + 'static) -> Self {
+        Component::Custom(Box::new(widget))
+    }
+
+    /// The component as a widget, whichever kind it is
+    pub fn widget(&self) -> &dyn Widget {
+        match self {
+            Component::Button(c) => c,
+            Component::Label(c) => c,
+            Component::TextBox(c) => c,
+            Component::Image(c) => c,
+            Component::ToolTip(c) => c,
+            Component::Toggle(c) => c,
+            Component::Slider(c) => c,
+            Component::DropdownList(c) => c,
+            Component::ColorPicker(c) => c,
+            Component::DatePicker(c) => c,
+            Component::ValueInput(c) => c,
+            Component::Table(c) => c,
+            Component::Tree(c) => c,
+            Component::List(c) => c,
+            Component::Modal(c) => c,
+            Component::Menu(c) => c,
+            Component::DragAndDrop(c) => c,
+            Component::ToolBar(c) => c,
+            Component::MenuBar(c) => c,
+            Component::ProgressBar(c) => c,
+            Component::Spinner(c) => c,
+            Component::Canvas(c) => c,
+            Component::StatusBar(c) => c,
+            Component::Accordion(c) => c,
+            Component::Space(c) => c,
+            Component::Command(c) => c,
+            Component::Overlay(c) => c,
+            Component::Custom(c) => c.as_ref(),
+        }
+    }
+
+    pub fn widget_mut(&mut self) -> &mut dyn Widget {
+        match self {
+            Component::Button(c) => c,
+            Component::Label(c) => c,
+            Component::TextBox(c) => c,
+            Component::Image(c) => c,
+            Component::ToolTip(c) => c,
+            Component::Toggle(c) => c,
+            Component::Slider(c) => c,
+            Component::DropdownList(c) => c,
+            Component::ColorPicker(c) => c,
+            Component::DatePicker(c) => c,
+            Component::ValueInput(c) => c,
+            Component::Table(c) => c,
+            Component::Tree(c) => c,
+            Component::List(c) => c,
+            Component::Modal(c) => c,
+            Component::Menu(c) => c,
+            Component::DragAndDrop(c) => c,
+            Component::ToolBar(c) => c,
+            Component::MenuBar(c) => c,
+            Component::ProgressBar(c) => c,
+            Component::Spinner(c) => c,
+            Component::Canvas(c) => c,
+            Component::StatusBar(c) => c,
+            Component::Accordion(c) => c,
+            Component::Space(c) => c,
+            Component::Command(c) => c,
+            Component::Overlay(c) => c,
+            Component::Custom(c) => c.as_mut(),
+        }
+    }
+}
+
+impl Widget for Component {
+    fn measure(&self, fonts: &Fonts) -> Size {
+        self.widget().measure(fonts)
+    }
+
+    fn layout(&mut self, rect: Rect, fonts: &Fonts) {
+        self.widget_mut().layout(rect, fonts)
+    }
+
+    fn sync(&mut self) -> bool {
+        self.widget_mut().sync()
+    }
+
+    fn bounds(&self) -> Rect {
+        self.widget().bounds()
+    }
+
+    fn paint(&self, batch: &mut Batch) {
+        self.widget().paint(batch)
+    }
+
+    fn handle_event(&mut self, event: &Event, ctx: &mut EventContext) {
+        self.widget_mut().handle_event(event, ctx)
+    }
+
+    fn focusable(&self) -> bool {
+        self.widget().focusable()
+    }
+
+    fn tab_index(&self) -> i32 {
+        self.widget().tab_index()
+    }
+
+    fn focus_scope(&self) -> bool {
+        self.widget().focus_scope()
+    }
+
+    fn overlay(&self) -> bool {
+        self.widget().overlay()
+    }
+
+    fn ime_area(&self) -> Option<Rect> {
+        self.widget().ime_area()
+    }
+
+    fn element(&self) -> Option<&Element> {
+        self.widget().element()
+    }
+
+    fn states(&self) -> States {
+        self.widget().states()
+    }
+
+    fn set_style(&mut self, style: Style) -> bool {
+        self.widget_mut().set_style(style)
+    }
+
+    fn children(&self) -> Vec<&dyn Widget> {
+        self.widget().children()
+    }
+
+    fn children_mut(&mut self) -> Vec<&mut dyn Widget> {
+        self.widget_mut().children_mut()
+    }
 }
 
 #[derive(Clone)]
 pub struct Label {
+    pub text: String,
+    pub font: FontId,
+    /// Pixels per em
+    pub size: f32,
+    pub color: color::Color,
+    pub x: f64,
+    pub y: f64,
+    /// Resolved by layout
+    pub width: f64,
+    pub height: f64,
+    pub element: Element,
+    binding: Option<Binding<String>>,
+    /// Resolved from the window's stylesheet
+    style: Style,
+}
+
+impl Label {
+    pub fn new(text: &str, font: FontId, size: f32, color: color::Color, x: f64, y: f64) -> Self {
+        Self {
+            text: text.to_owned(),
+            font,
+            size,
+            color,
+            x,
+            y,
+            width: 0.0,
+            height: 0.0,
+            element: Element::new("Label"),
+            binding: None,
+            style: Style::default(),
+        }
+    }
+
+    pub fn id(mut self, id: &str) -> Self {
+        self.element.id = Some(id.to_owned());
+        self
+    }
+
+    pub fn class(mut self, class: &str) -> Self {
+        self.element.classes.push(class.to_owned());
+        self
+    }
 
+    /// Font and pixels per em, as overridden by the stylesheet
+    fn text_style(&self) -> (FontId, f32) {
+        (self.style.font.unwrap_or(self.font), self.style.font_size.unwrap_or(self.size))
+    }
+
+    /// Show whatever `signal` holds from now on
+    pub fn bind(mut self, signal: Signal<String>) -> Self {
+        self.binding = Some(Binding::new(signal));
+        self
+    }
 }
 
-pub struct TextBox {
+impl Widget for Label {
+    fn sync(&mut self) -> bool {
+        match self.binding.as_mut().and_then(|b| b.changed()) {
+            Some(text) if text != self.text => {
+                self.text = text;
+                true
+            },
+            _ => false,
+        }
+    }
+
+    fn measure(&self, fonts: &Fonts) -> Size {
+        let (font, size) = self.text_style();
+        fonts.measure_text(font, &self.text, size)
+            .map(|m| Size::new(m.width as f64, m.height as f64))
+            .unwrap_or(Size::ZERO)
+    }
+
+    fn paint(&self, batch: &mut Batch) {
+        let (font, size) = self.text_style();
+        batch.text(TextRun {
+            font,
+            size,
+            position: [self.x as f32, self.y as f32],
+            color: self.style.foreground_or(style::rgba(&self.color)),
+            text: self.text.clone(),
+        });
+    }
 
+    fn layout(&mut self, rect: Rect, _fonts: &Fonts) {
+        self.x = rect.x;
+        self.y = rect.y;
+        self.width = rect.width;
+        self.height = rect.height;
+    }
+
+    fn bounds(&self) -> Rect {
+        Rect::new(self.x, self.y, self.width, self.height)
+    }
+
+    fn element(&self) -> Option<&Element> {
+        Some(&self.element)
+    }
+
+    fn set_style(&mut self, style: Style) -> bool {
+        let resized = style.resizes(&self.style);
+        self.style = style;
+        resized
+    }
 }
 
 pub struct Image {
@@ -85,12 +303,144 @@ pub struct ToolTip {
 
 }
 
+/// On/off switch
 pub struct Toggle {
+    pub on: bool,
+    pub x: f64,
+    pub y: f64,
+    pub width: f64,
+    pub height: f64,
+    /// Held down by the pointer - only a release that ends such a press flips the switch
+    pub pressed: bool,
+    pub focused: bool,
+    pub tab_index: i32,
+    pub element: Element,
+    binding: Option<Binding<bool>>,
+    /// Resolved from the window's stylesheet
+    style: Style,
+}
+
+impl Toggle {
+    pub fn new(on: bool) -> Self {
+        Self {
+            on,
+            x: 0.0,
+            y: 0.0,
+            width: 0.0,
+            height: 0.0,
+            pressed: false,
+            focused: false,
+            tab_index: 0,
+            element: Element::new("Toggle"),
+            binding: None,
+            style: Style::default(),
+        }
+    }
+
+    pub fn id(mut self, id: &str) -> Self {
+        self.element.id = Some(id.to_owned());
+        self
+    }
 
+    pub fn class(mut self, class: &str) -> Self {
+        self.element.classes.push(class.to_owned());
+        self
+    }
+
+    /// Follow `signal` and write the state back to it when the user flips the switch
+    pub fn bind(mut self, signal: Signal<bool>) -> Self {
+        self.binding = Some(Binding::new(signal));
+        self
+    }
+
+    pub fn set(&mut self, on: bool) {
+        self.on = on;
+        if let Some(binding) = &mut self.binding {
+            binding.set(on);
+        }
+    }
 }
 
+/// Picks a number between `min` and `max` by dragging a knob
 pub struct Slider {
+    pub value: f64,
+    pub min: f64,
+    pub max: f64,
+    pub x: f64,
+    pub y: f64,
+    pub width: f64,
+    pub height: f64,
+    pub focused: bool,
+    pub tab_index: i32,
+    pub element: Element,
+    dragging: bool,
+    binding: Option<Binding<f64>>,
+    /// Resolved from the window's stylesheet
+    style: Style,
+}
+
+impl Slider {
+    const KNOB: f64 = 8.0;
+
+    pub fn new(min: f64, max: f64, value: f64) -> Self {
+        Self {
+            value: value.clamp(min, max),
+            min,
+            max,
+            x: 0.0,
+            y: 0.0,
+            width: 0.0,
+            height: 0.0,
+            focused: false,
+            tab_index: 0,
+            element: Element::new("Slider"),
+            dragging: false,
+            binding: None,
+            style: Style::default(),
+        }
+    }
+
+    pub fn id(mut self, id: &str) -> Self {
+        self.element.id = Some(id.to_owned());
+        self
+    }
+
+    pub fn class(mut self, class: &str) -> Self {
+        self.element.classes.push(class.to_owned());
+        self
+    }
+
+    /// Follow `signal` and write the value back to it when the user moves the knob
+    pub fn bind(mut self, signal: Signal<f64>) -> Self {
+        self.binding = Some(Binding::new(signal));
+        self
+    }
+
+    pub fn set(&mut self, value: f64) {
+        let value = value.clamp(self.min, self.max);
+        if value == self.value {
+            return;
+        }
 
+        self.value = value;
+        if let Some(binding) = &mut self.binding {
+            binding.set(value);
+        }
+    }
+
+    /// Position of the value along the track, from 0 to 1
+    fn fraction(&self) -> f64 {
+        match self.max - self.min {
+            range if range > 0.0 => (self.value - self.min) / range,
+            _ => 0.0,
+        }
+    }
+
+    fn set_from_x(&mut self, x: f64) {
+        let track = (self.width - Self::KNOB * 2.0).max(1.0);
+        let fraction = ((x - self.x - Self::KNOB) / track).clamp(0.0, 1.0);
+        self.set(self.min + fraction * (self.max - self.min));
+    }
 }
 
 pub struct DropdownList {
@@ -121,8 +471,33 @@ pub struct List {
 
 }
 
+/// Dialog drawn above everything else while open - keeps focus inside until closed
 pub struct Modal {
+    pub open: bool,
+    pub content: Vec<Component>,
+    pub x: f64,
+    pub y: f64,
+    pub width: f64,
+    pub height: f64,
+    pub element: Element,
+    /// Resolved from the window's stylesheet
+    style: Style,
+}
+
+impl Modal {
+    pub fn new(content: Vec<Component>) -> Self {
+        Self { open: false, content, x: 0.0, y: 0.0, width: 0.0, height: 0.0, element: Element::new("Modal"), style: Style::default() }
+    }
+
+    pub fn id(mut self, id: &str) -> Self {
+        self.element.id = Some(id.to_owned());
+        self
+    }
 
+    pub fn class(mut self, class: &str) -> Self {
+        self.element.classes.push(class.to_owned());
+        self
+    }
 }
 
 pub struct Menu {
@@ -153,10 +528,6 @@ pub struct Canvas {
 
 }
 
-pub struct Custom {
-
-}
-
 pub struct StatusBar {
 
 }
@@ -177,39 +548,636 @@ pub struct Overlay {
 
 }
 
+/// Called with the number of clicks in a row - 2 for a double click, 3 for a triple click
+pub type ClickHandler = Box<dyn FnMut(u32)>;
+
 pub struct Button {
     pub label: String,
     pub color: color::Color,
+    pub font: FontId,
+    /// Pixels per em of the label
+    pub size: f32,
     pub x: f64,
     pub y: f64,
     pub width: f64,
     pub height: f64,
     pub hover: bool,
+    /// Held down by the pointer or Space
+    pub pressed: bool,
+    pub disabled: bool,
+    pub focused: bool,
+    pub tab_index: i32,
+    /// Longest gap between clicks that still counts them as a double or triple click
+    pub click_interval: Duration,
+    pub element: Element,
+    last_click: Option<(Instant, u32)>,
+    on_click: Vec<ClickHandler>,
+    /// Resolved by layout
+    label_size: Size,
+    /// Resolved from the window's stylesheet
+    style: Style,
 }
 
 impl Button {
+    const RADIUS: f32 = 4.0;
+
     pub fn new(label: &str, color: color::Color, x: f64, y: f64, width: f64, height: f64) -> Self {
         Self { 
             label: label.to_owned(), 
             color, 
+            font: FontId(0),
+            size: 14.0,
             x, 
             y, 
             width, 
             height, 
-            hover: false 
+            hover: false,
+            pressed: false,
+            disabled: false,
+            focused: false,
+            tab_index: 0,
+            click_interval: Duration::from_millis(500),
+            element: Element::new("Button"),
+            last_click: None,
+            on_click: Vec::new(),
+            label_size: Size::ZERO,
+            style: Style::default(),
+        }
+    }
+
+    /// A button placed by its container - `width` and `height` are its preferred size
+    pub fn with_size(label: &str, color: color::Color, width: f64, height: f64) -> Self {
+        Self::new(label, color, 0.0, 0.0, width, height)
+    }
+
+    pub fn font(mut self, font: FontId, size: f32) -> Self {
+        self.font = font;
+        self.size = size;
+        self
+    }
+
+    pub fn disabled(mut self, disabled: bool) -> Self {
+        self.disabled = disabled;
+        self
+    }
+
+    pub fn id(mut self, id: &str) -> Self {
+        self.element.id = Some(id.to_owned());
+        self
+    }
+
+    pub fn class(mut self, class: &str) -> Self {
+        self.element.classes.push(class.to_owned());
+        self
+    }
+
+    /// Run `handler` every time the button is clicked
+    pub fn on_click(mut self, handler: impl FnMut(u32) + 'static) -> Self {
+        self.on_click.push(Box::new(handler));
+        self
+    }
+
+    /// Fire the click handlers - pointer clicks in quick succession count up to a triple click
+    /// and start again, keyboard clicks always count once
+    pub fn click(&mut self, pointer: bool) {
+        let now = Instant::now();
+        let count = match self.last_click {
+            Some((time, count)) if pointer && now - time <= self.click_interval => count % 3 + 1,
+            _ => 1,
+        };
+        self.last_click = if pointer { Some((now, count)) } else { None };
+
+        for handler in &mut self.on_click {
+            handler(count);
+        }
+    }
+
+    /// Font and pixels per em of the label, as overridden by the stylesheet
+    fn text_style(&self) -> (FontId, f32) {
+        (self.style.font.unwrap_or(self.font), self.style.font_size.unwrap_or(self.size))
+    }
+
+    /// Fill color for the current state - a stylesheet background is used as is, since its
+    /// rules pick the color for each state
+    fn fill(&self) -> [f32; 4] {
+        if let Some(background) = &self.style.background {
+            return style::rgba(background);
+        }
+
+        let [r, g, b, _] = style::rgba(&self.color);
+
+        if self.disabled {
+            // Washed out towards gray
+            let gray = (r + g + b) / 3.0;
+            return [(r + gray) / 2.0, (g + gray) / 2.0, (b + gray) / 2.0, 0.5];
+        }
+        match (self.pressed, self.hover) {
+            (true, _) => [r * 0.75, g * 0.75, b * 0.75, 1.0],
+            (false, true) => [r + (1.0 - r) * 0.15, g + (1.0 - g) * 0.15, b + (1.0 - b) * 0.15, 1.0],
+            (false, false) => [r, g, b, 1.0],
+        }
+    }
+}
+
+impl Widget for Button {
+    /// The preferred size, grown to fit the label inside the stylesheet padding
+    fn measure(&self, fonts: &Fonts) -> Size {
+        let Some(padding) = self.style.padding else { return Size::new(self.width, self.height) };
+        let (font, size) = self.text_style();
+        let label = fonts.measure_text(font, &self.label, size)
+            .map(|m| Size::new(m.width as f64, m.height as f64))
+            .unwrap_or(Size::ZERO);
+
+        Size::new(
+            self.width.max(label.width + padding.left + padding.right),
+            self.height.max(label.height + padding.top + padding.bottom),
+        )
+    }
+
+    fn layout(&mut self, rect: Rect, fonts: &Fonts) {
+        self.x = rect.x;
+        self.y = rect.y;
+        self.width = rect.width;
+        self.height = rect.height;
+        let (font, size) = self.text_style();
+        self.label_size = fonts.measure_text(font, &self.label, size)
+            .map(|m| Size::new(m.width as f64, m.height as f64))
+            .unwrap_or(Size::ZERO);
+    }
+
+    fn paint(&self, batch: &mut Batch) {
+        let mut button = Primitive::rect(self.x as f32, self.y as f32, self.width as f32, self.height as f32, self.fill())
+            .radius(self.style.radius.unwrap_or(Self::RADIUS));
+        let (width, color) = self.style.border_or(0.0, [0.0; 4]);
+        if width > 0.0 {
+            button = button.border(width, color);
+        }
+        batch.primitive(button);
+
+        if !self.label.is_empty() {
+            let [r, g, b, a] = self.style.foreground_or([1.0; 4]);
+            let alpha = if self.disabled { 0.5 } else { 1.0 };
+            let (font, size) = self.text_style();
+            batch.text(TextRun {
+                font,
+                size,
+                position: [
+                    (self.x + (self.width - self.label_size.width) / 2.0) as f32,
+                    (self.y + (self.height - self.label_size.height) / 2.0) as f32,
+                ],
+                color: [r, g, b, a * alpha],
+                text: self.label.clone(),
+            });
+        }
+    }
+
+    fn bounds(&self) -> Rect {
+        Rect::new(self.x, self.y, self.width, self.height)
+    }
+
+    fn focusable(&self) -> bool {
+        !self.disabled
+    }
+
+    fn element(&self) -> Option<&Element> {
+        Some(&self.element)
+    }
+
+    fn states(&self) -> States {
+        States { hover: self.hover, pressed: self.pressed, focus: self.focused, disabled: self.disabled, checked: false }
+    }
+
+    fn set_style(&mut self, style: Style) -> bool {
+        let resized = style.resizes(&self.style);
+        self.style = style;
+        resized
+    }
+
+    fn tab_index(&self) -> i32 {
+        self.tab_index
+    }
+
+    fn handle_event(&mut self, event: &Event, ctx: &mut EventContext) {
+        match event {
+            Event::PointerEntered => self.hover = true,
+            Event::PointerLeft => self.hover = false,
+            Event::Focus(focused) => {
+                self.focused = *focused;
+                // Losing focus while Space is held cancels the press
+                if !focused {
+                    self.pressed = false;
+                }
+            },
+            _ if self.disabled || ctx.phase() != Phase::Target => {},
+            Event::PointerPressed { button: MouseButton::Left, .. } => {
+                self.pressed = true;
+                ctx.stop_propagation();
+            },
+            // Releases arrive here even off the button, but only count as a click inside it
+            Event::PointerReleased { x, y, button: MouseButton::Left } if self.pressed => {
+                self.pressed = false;
+                if self.bounds().contains(*x, *y) {
+                    self.click(true);
+                }
+                ctx.stop_propagation();
+            },
+            Event::Key(key) if !key.repeat => match (&key.logical_key, key.state) {
+                (Key::Named(NamedKey::Space), ElementState::Pressed) => {
+                    self.pressed = true;
+                    ctx.stop_propagation();
+                },
+                (Key::Named(NamedKey::Space), ElementState::Released) if self.pressed => {
+                    self.pressed = false;
+                    self.click(false);
+                    ctx.stop_propagation();
+                },
+                (Key::Named(NamedKey::Enter), ElementState::Pressed) => {
+                    self.click(false);
+                    ctx.stop_propagation();
+                },
+                _ => {},
+            },
+            _ => {},
+        }
+    }
+}
+
+impl Widget for Image {}
+impl Widget for ToolTip {}
+impl Widget for DropdownList {}
+impl Widget for ColorPicker {}
+impl Widget for DatePicker {}
+impl Widget for ValueInput {}
+impl Widget for Table {}
+impl Widget for Tree {}
+impl Widget for List {}
+impl Widget for Menu {}
+impl Widget for DragAndDrop {}
+impl Widget for ToolBar {}
+impl Widget for MenuBar {}
+impl Widget for ProgressBar {}
+impl Widget for Spinner {}
+impl Widget for Canvas {}
+impl Widget for StatusBar {}
+impl Widget for Accordion {}
+impl Widget for Space {}
+impl Widget for Command {}
+impl Widget for Overlay {}
+
+impl Widget for Modal {
+    fn measure(&self, fonts: &Fonts) -> Size {
+        self.content.iter().map(|c| c.measure(fonts)).fold(Size::ZERO, |a, b| {
+            Size::new(a.width.max(b.width), a.height + b.height)
+        })
+    }
+
+    /// Content is stacked top to bottom
+    fn layout(&mut self, rect: Rect, fonts: &Fonts) {
+        self.x = rect.x;
+        self.y = rect.y;
+        self.width = rect.width;
+        self.height = rect.height;
+
+        let mut y = rect.y;
+        for c in &mut self.content {
+            let size = c.measure(fonts);
+            c.layout(Rect::new(rect.x, y, rect.width, size.height), fonts);
+            y += size.height;
+        }
+    }
+
+    fn bounds(&self) -> Rect {
+        match self.open {
+            true => Rect::new(self.x, self.y, self.width, self.height),
+            false => Rect::default(),
+        }
+    }
+
+    fn paint(&self, batch: &mut Batch) {
+        if !self.open {
+            return;
+        }
+
+        let background = self.style.background_or([0.15, 0.15, 0.18, 1.0]);
+        let (border, border_color) = self.style.border_or(0.0, [0.0; 4]);
+        batch.primitive(
+            Primitive::rect(self.x as f32, self.y as f32, self.width as f32, self.height as f32, background)
+                .radius(self.style.radius.unwrap_or(6.0))
+                .border(border, border_color)
+                .shadow([0.0, 4.0], 16.0, [0.0, 0.0, 0.0, 0.5]),
+        );
+        self.content.iter().for_each(|c| c.paint(batch));
+    }
+
+    fn element(&self) -> Option<&Element> {
+        Some(&self.element)
+    }
+
+    fn set_style(&mut self, style: Style) -> bool {
+        self.style = style;
+        false
+    }
+
+    fn focus_scope(&self) -> bool {
+        self.open
+    }
+
+    fn overlay(&self) -> bool {
+        self.open
+    }
+
+    fn children(&self) -> Vec<&dyn Widget> {
+        match self.open {
+            true => self.content.iter().map(|c| c as &dyn Widget).collect(),
+            false => Vec::new(),
+        }
+    }
+
+    fn children_mut(&mut self) -> Vec<&mut dyn Widget> {
+        match self.open {
+            true => self.content.iter_mut().map(|c| c as &mut dyn Widget).collect(),
+            false => Vec::new(),
+        }
+    }
+}
+
+impl Widget for Toggle {
+    fn sync(&mut self) -> bool {
+        match self.binding.as_mut().and_then(|b| b.changed()) {
+            Some(on) if on != self.on => {
+                self.on = on;
+                true
+            },
+            _ => false,
+        }
+    }
+
+    fn measure(&self, _fonts: &Fonts) -> Size {
+        Size::new(40.0, 22.0)
+    }
+
+    fn layout(&mut self, rect: Rect, _fonts: &Fonts) {
+        self.x = rect.x;
+        self.y = rect.y;
+        self.width = rect.width;
+        self.height = rect.height;
+    }
+
+    fn bounds(&self) -> Rect {
+        Rect::new(self.x, self.y, self.width, self.height)
+    }
+
+    fn paint(&self, batch: &mut Batch) {
+        let (x, y, w, h) = (self.x as f32, self.y as f32, self.width as f32, self.height as f32);
+        let track = self.style.background_or(if self.on { [0.25, 0.55, 0.95, 1.0] } else { [0.3, 0.3, 0.33, 1.0] });
+        let (border, border_color) = self.style.border_or(0.0, [0.0; 4]);
+        batch.primitive(Primitive::rect(x, y, w, h, track).radius(self.style.radius.unwrap_or(h / 2.0)).border(border, border_color));
+
+        let knob = h - 4.0;
+        let knob_x = if self.on { x + w - knob - 2.0 } else { x + 2.0 };
+        let color = self.style.foreground_or([0.95, 0.95, 0.95, 1.0]);
+        batch.primitive(Primitive::rect(knob_x, y + 2.0, knob, knob, color).radius(knob / 2.0));
+    }
+
+    fn focusable(&self) -> bool {
+        true
+    }
+
+    fn tab_index(&self) -> i32 {
+        self.tab_index
+    }
+
+    fn element(&self) -> Option<&Element> {
+        Some(&self.element)
+    }
+
+    fn states(&self) -> States {
+        States { pressed: self.pressed, focus: self.focused, checked: self.on, ..Default::default() }
+    }
+
+    fn set_style(&mut self, style: Style) -> bool {
+        self.style = style;
+        false
+    }
+
+    fn handle_event(&mut self, event: &Event, ctx: &mut EventContext) {
+        if ctx.phase() != Phase::Target {
+            return;
+        }
+
+        match event {
+            Event::Focus(focused) => self.focused = *focused,
+            Event::PointerPressed { button: MouseButton::Left, .. } => {
+                self.pressed = true;
+                ctx.stop_propagation();
+            },
+            // Releases arrive here even off the switch, but only flip it inside
+            Event::PointerReleased { x, y, button: MouseButton::Left } if self.pressed => {
+                self.pressed = false;
+                if self.bounds().contains(*x, *y) {
+                    self.set(!self.on);
+                }
+                ctx.stop_propagation();
+            },
+            Event::Key(key) if key.state.is_pressed() && !key.repeat => {
+                if let Key::Named(NamedKey::Space | NamedKey::Enter) = key.logical_key {
+                    self.set(!self.on);
+                    ctx.stop_propagation();
+                }
+            },
+            _ => {},
         }
     }
+}
 
-    pub fn inside(&mut self, x: f64, y: f64) -> bool {
-        if x >= self.x && x <= self.x + self.width && y >= self.y && y <= self.y + self.height {
-            self.hover = true;
-            return true;
+impl Widget for Slider {
+    fn sync(&mut self) -> bool {
+        // Clamped first, so an out of range value that clamps to the current one changes nothing
+        match self.binding.as_mut().and_then(|b| b.changed()).map(|v| v.clamp(self.min, self.max)) {
+            Some(value) if value != self.value => {
+                self.value = value;
+                true
+            },
+            _ => false,
         }
-        self.hover = false;
-        return false;
     }
 
-    pub fn draw(&self) {
-        todo!()
+    fn measure(&self, _fonts: &Fonts) -> Size {
+        Size::new(160.0, Self::KNOB * 2.0 + 4.0)
+    }
+
+    fn layout(&mut self, rect: Rect, _fonts: &Fonts) {
+        self.x = rect.x;
+        self.y = rect.y;
+        self.width = rect.width;
+        self.height = rect.height;
+    }
+
+    fn bounds(&self) -> Rect {
+        Rect::new(self.x, self.y, self.width, self.height)
+    }
+
+    fn paint(&self, batch: &mut Batch) {
+        let knob = Self::KNOB as f32;
+        let (x, w) = (self.x as f32 + knob, (self.width as f32 - knob * 2.0).max(0.0));
+        let middle = (self.y + self.height / 2.0) as f32;
+        let filled = w * self.fraction() as f32;
+
+        let radius = self.style.radius.unwrap_or(2.0);
+        batch.primitive(Primitive::rect(x, middle - 2.0, w, 4.0, self.style.background_or([0.3, 0.3, 0.33, 1.0])).radius(radius));
+        batch.primitive(Primitive::rect(x, middle - 2.0, filled, 4.0, self.style.foreground_or([0.25, 0.55, 0.95, 1.0])).radius(radius));
+        batch.primitive(
+            Primitive::rect(x + filled - knob, middle - knob, knob * 2.0, knob * 2.0, [0.95, 0.95, 0.95, 1.0])
+                .radius(knob),
+        );
+    }
+
+    fn focusable(&self) -> bool {
+        true
+    }
+
+    fn tab_index(&self) -> i32 {
+        self.tab_index
+    }
+
+    fn element(&self) -> Option<&Element> {
+        Some(&self.element)
+    }
+
+    fn states(&self) -> States {
+        States { focus: self.focused, pressed: self.dragging, ..Default::default() }
     }
-}
\ No newline at end of file
+
+    fn set_style(&mut self, style: Style) -> bool {
+        self.style = style;
+        false
+    }
+
+    fn handle_event(&mut self, event: &Event, ctx: &mut EventContext) {
+        if ctx.phase() != Phase::Target {
+            return;
+        }
+
+        match event {
+            Event::Focus(focused) => self.focused = *focused,
+            Event::PointerPressed { x, button: MouseButton::Left, .. } => {
+                self.dragging = true;
+                self.set_from_x(*x);
+                ctx.stop_propagation();
+            },
+            Event::PointerMoved { x, .. } if self.dragging => {
+                self.set_from_x(*x);
+                ctx.stop_propagation();
+            },
+            Event::PointerReleased { button: MouseButton::Left, .. } => self.dragging = false,
+            Event::Key(key) if key.state.is_pressed() => {
+                let step = (self.max - self.min) / 100.0;
+                match key.logical_key {
+                    Key::Named(NamedKey::ArrowLeft | NamedKey::ArrowDown) => self.set(self.value - step),
+                    Key::Named(NamedKey::ArrowRight | NamedKey::ArrowUp) => self.set(self.value + step),
+                    Key::Named(NamedKey::Home) => self.set(self.min),
+                    Key::Named(NamedKey::End) => self.set(self.max),
+                    _ => return,
+                }
+                ctx.stop_propagation();
+            },
+            _ => {},
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::cell::RefCell;
+    use std::rc::Rc;
+    use winit::keyboard::ModifiersState;
+    use super::*;
+
+    fn send(widget: &mut dyn Widget, event: Event) -> bool {
+        let mut ctx = EventContext::new(Phase::Target, ModifiersState::empty());
+        widget.handle_event(&event, &mut ctx);
+        ctx.is_stopped()
+    }
+
+    fn pressed(x: f64, y: f64) -> Event {
+        Event::PointerPressed { x, y, button: MouseButton::Left }
+    }
+
+    fn released(x: f64, y: f64) -> Event {
+        Event::PointerReleased { x, y, button: MouseButton::Left }
+    }
+
+    fn toggle() -> Toggle {
+        let mut toggle = Toggle::new(false);
+        toggle.layout(Rect::new(0.0, 0.0, 40.0, 22.0), &Fonts::new());
+        toggle
+    }
+
+    #[test]
+    fn toggle_flips_only_for_presses_that_started_on_it() {
+        let mut toggle = toggle();
+        assert!(!send(&mut toggle, released(10.0, 10.0)));
+        assert!(!toggle.on);
+
+        send(&mut toggle, pressed(10.0, 10.0));
+        assert!(toggle.states().pressed);
+        send(&mut toggle, released(10.0, 10.0));
+        assert!(toggle.on);
+
+        send(&mut toggle, pressed(10.0, 10.0));
+        assert!(send(&mut toggle, released(100.0, 10.0)));
+        assert!(toggle.on);
+        assert!(!toggle.pressed);
+    }
+
+    #[test]
+    fn toggle_writes_back_to_its_signal() {
+        let signal = Signal::new(false);
+        let mut toggle = Toggle::new(false).bind(signal.clone());
+        toggle.layout(Rect::new(0.0, 0.0, 40.0, 22.0), &Fonts::new());
+
+        send(&mut toggle, pressed(10.0, 10.0));
+        send(&mut toggle, released(10.0, 10.0));
+        assert!(signal.get());
+    }
+
+    #[test]
+    fn slider_only_syncs_values_that_clamp_to_a_change() {
+        let signal = Signal::new(1.0);
+        let mut slider = Slider::new(0.0, 1.0, 1.0).bind(signal.clone());
+        assert!(!slider.sync());
+
+        signal.set(5.0);
+        assert!(!slider.sync());
+        signal.set(-2.0);
+        assert!(slider.sync());
+        assert_eq!(slider.value, 0.0);
+    }
+
+    #[test]
+    fn button_counts_clicks_released_inside() {
+        let clicks = Rc::new(RefCell::new(Vec::new()));
+        let sink = clicks.clone();
+        let mut button = Button::new("Ok", color::Color::Blue, 0.0, 0.0, 80.0, 30.0)
+            .on_click(move |count| sink.borrow_mut().push(count));
+
+        for _ in 0..2 {
+            send(&mut button, pressed(10.0, 10.0));
+            send(&mut button, released(10.0, 10.0));
+        }
+        send(&mut button, pressed(10.0, 10.0));
+        send(&mut button, released(200.0, 10.0));
+        // A release without a press is not a click
+        send(&mut button, released(10.0, 10.0));
+
+        assert_eq!(*clicks.borrow(), [1, 2]);
+    }
+
+    #[test]
+    fn disabled_buttons_ignore_the_pointer() {
+        let mut button = Button::new("Ok", color::Color::Blue, 0.0, 0.0, 80.0, 30.0).disabled(true);
+        assert!(!send(&mut button, pressed(10.0, 10.0)));
+        assert!(!button.pressed);
+        assert!(!button.focusable());
+    }
+}
@@ -0,0 +1,603 @@
+use winit::event::{ElementState, MouseButton, WindowEvent};
+use winit::keyboard::{Key, ModifiersState, NamedKey};
+use crate::structure::event::{Event, EventContext, Phase};
+use crate::structure::focus::Focus;
+use crate::structure::layout::Rect;
+use crate::structure::style::States;
+use crate::structure::widget::Widget;
+use crate::structure::window::{Container, ContainerType, Window};
+
+/// Position of a widget in the tree - child indices in layout order from the window down,
+/// continuing through `Widget::children` below components
+pub type Path = Vec<usize>;
+
+/// Turns winit window events into KoZy events and delivers them to the widgets they concern
+#[derive(Clone, Debug, Default)]
+pub struct Dispatcher {
+    cursor: (f64, f64),
+    modifiers: ModifiersState,
+    /// Widgets under the pointer, outermost first
+    hovered: Path,
+    /// Where the last button press landed - receives moves and the release until then
+    pressed: Option<Path>,
+    focus: Focus,
+}
+
+impl Dispatcher {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    pub fn cursor(&self) -> (f64, f64) {
+        self.cursor
+    }
+
+    pub fn modifiers(&self) -> ModifiersState {
+        self.modifiers
+    }
+
+    pub fn focus(&self) -> &Focus {
+        &self.focus
+    }
+
+    pub fn focus_mut(&mut self) -> &mut Focus {
+        &mut self.focus
+    }
+
+    /// Handle a window event - returns true when a widget or built-in behaviour used it. Widgets
+    /// that use an event are marked dirty, and the whole window when scrolling or tabs moved
+    pub fn handle(&mut self, window: &mut Window, event: &WindowEvent) -> bool {
+        self.resolve(window);
+        let (x, y) = self.cursor;
+
+        match event {
+            WindowEvent::ModifiersChanged(modifiers) => {
+                self.modifiers = modifiers.state();
+                false
+            },
+            WindowEvent::CursorMoved { position, .. } => {
+                let (x, y) = (position.x, position.y);
+                self.cursor = (x, y);
+                let dragged = window.drag_to(x, y);
+                if dragged {
+                    window.mark_dirty(Path::new());
+                }
+
+                let path = self.hit_test(window, x, y).unwrap_or_default();
+                self.hover(window, path.clone());
+                let target = self.pressed.clone().unwrap_or(path);
+                self.deliver(window, &target, &Event::PointerMoved { x, y }) || dragged
+            },
+            WindowEvent::CursorLeft { .. } => {
+                self.hover(window, Path::new());
+                false
+            },
+            WindowEvent::MouseInput { state: ElementState::Pressed, button, .. } => {
+                // Scrollbars and tab strips sit above the content, but below an open focus scope
+                if *button == MouseButton::Left && window.focus_scope().is_none() && window.press(x, y) {
+                    self.resolve(window);
+                    window.mark_dirty(Path::new());
+                    return true;
+                }
+
+                let Some(path) = self.hit_test(window, x, y) else { return false };
+                self.pressed = Some(path.clone());
+                self.focus.click(window, &path, self.modifiers);
+                self.deliver(window, &path, &Event::PointerPressed { x, y, button: *button })
+            },
+            WindowEvent::MouseInput { state: ElementState::Released, button, .. } => {
+                if *button == MouseButton::Left {
+                    window.release();
+                }
+
+                let Some(path) = self.pressed.take().or_else(|| self.hit_test(window, x, y)) else { return false };
+                self.deliver(window, &path, &Event::PointerReleased { x, y, button: *button })
+            },
+            WindowEvent::MouseWheel { delta, phase, .. } => {
+                let Some(path) = self.hit_test(window, x, y) else { return false };
+                if self.deliver(window, &path, &Event::Wheel { x, y, delta: *delta, phase: *phase }) {
+                    return true;
+                }
+
+                let scrolled = window.focus_scope().is_none() && window.wheel(x, y, *delta, *phase);
+                if scrolled {
+                    window.mark_dirty(Path::new());
+                }
+                scrolled
+            },
+            WindowEvent::KeyboardInput { event, .. } => {
+                let path = self.focus.path().map(|p| p.to_vec()).unwrap_or_default();
+                if self.deliver(window, &path, &Event::Key(event.clone())) {
+                    return true;
+                }
+
+                match event.logical_key {
+                    Key::Named(NamedKey::Tab) if event.state.is_pressed() && self.modifiers.control_key() => {
+                        // The tab container holding the focused widget, or the first one when
+                        // nothing has focus
+                        let path = self.focus.path().map(|p| p.to_vec()).or_else(|| window.first_tabs());
+                        let Some(tabs) = path.and_then(|p| window.cycle_tabs(&p, !self.modifiers.shift_key())) else {
+                            return false;
+                        };
+
+                        // Focus stays behind on the page that was just hidden
+                        if self.focus.path().is_some_and(|p| p.starts_with(&tabs)) {
+                            self.focus.clear(window, self.modifiers);
+                        }
+                        window.mark_dirty(Path::new());
+                        true
+                    },
+                    Key::Named(NamedKey::Tab) if event.state.is_pressed() => {
+                        self.focus.advance(window, !self.modifiers.shift_key(), self.modifiers)
+                    },
+                    _ => false,
+                }
+            },
+            WindowEvent::Ime(ime) => {
+                let path = self.focus.path().map(|p| p.to_vec()).unwrap_or_default();
+                self.deliver(window, &path, &Event::Ime(ime.clone()))
+            },
+            WindowEvent::Focused(false) => {
+                self.focus.clear(window, self.modifiers);
+                false
+            },
+            _ => false,
+        }
+    }
+
+    /// Widgets under `x`, `y` that may receive pointer events - `None` when an open focus scope,
+    /// such as a modal, blocks the pointer from reaching them
+    fn hit_test(&self, window: &Window, x: f64, y: f64) -> Option<Path> {
+        let path = window.hit_test(x, y);
+        match window.focus_scope() {
+            Some(scope) if !path.starts_with(&scope) => None,
+            _ => Some(path),
+        }
+    }
+
+    /// Re-resolve the paths held on to after children were removed from the tree, or after the
+    /// tree was swapped out - paths to widgets that are gone are dropped
+    fn resolve(&mut self, window: &mut Window) {
+        for removed in window.take_removed() {
+            if !shift(&mut self.hovered, &removed) {
+                self.hovered.truncate(removed.len() - 1);
+            }
+            if self.pressed.as_mut().is_some_and(|p| !shift(p, &removed)) {
+                self.pressed = None;
+            }
+            self.focus.removed(&removed);
+        }
+
+        if self.pressed.as_ref().is_some_and(|p| !window.exists(p)) {
+            self.pressed = None;
+        }
+        if !window.exists(&self.hovered) {
+            self.hovered.clear();
+        }
+        self.focus.resolve(window);
+    }
+
+    /// Caret area of the focused widget when it takes text input - `None` means the input
+    /// method should be off
+    pub fn ime_area(&self, window: &Window) -> Option<Rect> {
+        self.focus.path().and_then(|p| window.widget(p)).and_then(|w| w.ime_area())
+    }
+
+    /// Deliver `event` along `path` - capture from the outermost widget down, then the target,
+    /// then bubble back up. Returns true when a widget stopped propagation, marking that widget
+    /// dirty. The window is only restyled when a widget's states changed
+    pub fn deliver(&self, window: &mut Window, path: &[usize], event: &Event) -> bool {
+        // Containers along the path are not widgets and never see the event
+        let depths: Vec<usize> = (1..=path.len()).filter(|&d| window.widget_mut(&path[..d]).is_some()).collect();
+        let Some((&target, ancestors)) = depths.split_last() else { return false };
+        let before = states(window, &[path]);
+
+        let mut stopped = false;
+        let mut ctx = EventContext::new(Phase::Capture, self.modifiers);
+        let order = ancestors.iter()
+            .map(|&d| (Phase::Capture, d))
+            .chain(std::iter::once((Phase::Target, target)))
+            .chain(ancestors.iter().rev().map(|&d| (Phase::Bubble, d)));
+
+        for (phase, depth) in order {
+            ctx.phase = phase;
+            if let Some(widget) = window.widget_mut(&path[..depth]) {
+                widget.handle_event(event, &mut ctx);
+            }
+            if ctx.is_stopped() {
+                window.mark_dirty(path[..depth].to_vec());
+                stopped = true;
+                break;
+            }
+        }
+
+        if states(window, &[path]) != before {
+            window.restyle_pending = true;
+        }
+        stopped
+    }
+
+    /// Send leave and enter events to widgets the pointer moved off and onto
+    fn hover(&mut self, window: &mut Window, path: Path) {
+        if path == self.hovered {
+            return;
+        }
+
+        let before = states(window, &[&self.hovered, &path]);
+        let common = self.hovered.iter().zip(&path).take_while(|(a, b)| a == b).count();
+        let mut ctx = EventContext::new(Phase::Target, self.modifiers);
+
+        for depth in (common + 1..=self.hovered.len()).rev() {
+            if let Some(widget) = window.widget_mut(&self.hovered[..depth]) {
+                widget.handle_event(&Event::PointerLeft, &mut ctx);
+            }
+        }
+        for depth in common + 1..=path.len() {
+            if let Some(widget) = window.widget_mut(&path[..depth]) {
+                widget.handle_event(&Event::PointerEntered, &mut ctx);
+            }
+        }
+
+        if states(window, &[&self.hovered, &path]) != before {
+            window.restyle_pending = true;
+        }
+        self.hovered = path;
+    }
+}
+
+/// States of every widget along `paths` - compared before and after delivering an event, since
+/// only a change of state can change which selectors match
+fn states(window: &Window, paths: &[&[usize]]) -> Vec<States> {
+    paths.iter()
+        .flat_map(|path| (1..=path.len()).filter_map(|d| window.widget(&path[..d]).map(|w| w.states())))
+        .collect()
+}
+
+impl Window {
+    /// Path to the topmost widget or container at `x`, `y` - overlays come first, then later
+    /// children are drawn above earlier ones. Content outside a scrollable's viewport or on hidden
+    /// tabs is skipped
+    pub fn hit_test(&self, x: f64, y: f64) -> Path {
+        let offset = self.components.len();
+        let components = |overlay: bool| self.components.iter().enumerate().rev()
+            .filter(move |(_, c)| c.overlay() == overlay)
+            .find_map(|(i, c)| hit_widget(c, x, y).map(|p| prepend(i, p)));
+
+        components(true)
+            .or_else(|| self.containers.iter().enumerate().rev()
+                .find_map(|(i, c)| hit_container(c, x, y).map(|p| prepend(offset + i, p))))
+            .or_else(|| components(false))
+            .unwrap_or_default()
+    }
+
+    /// Whether `path` still leads to a widget or container
+    pub fn exists(&self, path: &[usize]) -> bool {
+        let Some((&first, rest)) = path.split_first() else { return true };
+
+        match self.components.get(first) {
+            Some(c) => find_widget(c, rest).is_some(),
+            None => self.containers.get(first - self.components.len()).is_some_and(|c| container_exists(c, rest)),
+        }
+    }
+
+    /// The widget at `path`, `None` when the path ends at a container or no longer exists
+    pub fn widget(&self, path: &[usize]) -> Option<&dyn Widget> {
+        let (&first, rest) = path.split_first()?;
+        let offset = self.components.len();
+
+        match self.components.get(first) {
+            Some(c) => find_widget(c, rest),
+            None => find_container(self.containers.get(first - offset)?, rest),
+        }
+    }
+
+    pub fn widget_mut(&mut self, path: &[usize]) -> Option<&mut dyn Widget> {
+        let (&first, rest) = path.split_first()?;
+        let offset = self.components.len();
+
+        match self.components.get_mut(first) {
+            Some(c) => descend_widget(c, rest),
+            None => descend_container(self.containers.get_mut(first - offset)?, rest),
+        }
+    }
+}
+
+pub(crate) fn prepend(index: usize, mut path: Path) -> Path {
+    path.insert(0, index);
+    path
+}
+
+/// Fix up `path` after the child at `removed` left the tree, moving it past the gap - returns
+/// false when `path` was inside the removed child
+pub(crate) fn shift(path: &mut Path, removed: &[usize]) -> bool {
+    if path.starts_with(removed) {
+        return false;
+    }
+
+    let (&index, parent) = removed.split_last().expect("removed paths are never empty");
+    if path.len() > parent.len() && path.starts_with(parent) && path[parent.len()] > index {
+        path[parent.len()] -= 1;
+    }
+    true
+}
+
+fn hit_container(container: &Container, x: f64, y: f64) -> Option<Path> {
+    if !container.rect.contains(x, y) {
+        return None;
+    }
+
+    if let ContainerType::Scrollable(scroll) = &container.layout {
+        if !scroll.viewport.contains(x, y) {
+            return Some(Path::new());
+        }
+    }
+
+    let offset = container.components.len();
+    let hit = container.containers.iter().enumerate().rev()
+        .filter(|(i, _)| container.shows(offset + i))
+        .find_map(|(i, c)| hit_container(c, x, y).map(|p| prepend(offset + i, p)))
+        .or_else(|| container.components.iter().enumerate().rev()
+            .filter(|(i, _)| container.shows(*i))
+            .find_map(|(i, c)| hit_widget(c, x, y).map(|p| prepend(i, p))));
+
+    Some(hit.unwrap_or_default())
+}
+
+fn hit_widget(widget: &dyn Widget, x: f64, y: f64) -> Option<Path> {
+    if !widget.bounds().contains(x, y) {
+        return None;
+    }
+
+    let hit = widget.children().into_iter().enumerate().rev()
+        .find_map(|(i, c)| hit_widget(c, x, y).map(|p| prepend(i, p)));
+
+    Some(hit.unwrap_or_default())
+}
+
+fn container_exists(container: &Container, path: &[usize]) -> bool {
+    let Some((&first, rest)) = path.split_first() else { return true };
+
+    match container.components.get(first) {
+        Some(c) => find_widget(c, rest).is_some(),
+        None => container.containers.get(first - container.components.len()).is_some_and(|c| container_exists(c, rest)),
+    }
+}
+
+fn find_container<'a>(container: &'a Container, path: &[usize]) -> Option<&'a dyn Widget> {
+    let (&first, rest) = path.split_first()?;
+    let offset = container.components.len();
+
+    match container.components.get(first) {
+        Some(c) => find_widget(c, rest),
+        None => find_container(container.containers.get(first - offset)?, rest),
+    }
+}
+
+fn find_widget<'a>(widget: &'a dyn Widget, path: &[usize]) -> Option<&'a dyn Widget> {
+    match path.split_first() {
+        None => Some(widget),
+        Some((&first, rest)) => find_widget(widget.children().into_iter().nth(first)?, rest),
+    }
+}
+
+fn descend_container<'a>(container: &'a mut Container, path: &[usize]) -> Option<&'a mut dyn Widget> {
+    let (&first, rest) = path.split_first()?;
+    let offset = container.components.len();
+
+    match container.components.get_mut(first) {
+        Some(c) => descend_widget(c, rest),
+        None => descend_container(container.containers.get_mut(first - offset)?, rest),
+    }
+}
+
+fn descend_widget<'a>(widget: &'a mut dyn Widget, path: &[usize]) -> Option<&'a mut dyn Widget> {
+    match path.split_first() {
+        None => Some(widget),
+        Some((&first, rest)) => descend_widget(widget.children_mut().into_iter().nth(first)?, rest),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::cell::RefCell;
+    use std::rc::Rc;
+    use winit::event::DeviceId;
+    use super::*;
+    use crate::core::color::Color;
+    use crate::structure::components::{Button, Component, Modal};
+    use crate::structure::event::EventContext;
+    use crate::structure::window::{Container, ContainerType, Window};
+    use crate::text::Fonts;
+
+    fn button(x: f64, y: f64, width: f64, height: f64) -> Component {
+        Component::Button(Button::new("", Color::Blue, x, y, width, height))
+    }
+
+    /// A column filled by one large button, and a modal holding a small one above it
+    fn window(open: bool) -> Window {
+        let mut modal = Modal::new(vec![button(0.0, 0.0, 100.0, 40.0)]);
+        (modal.open, modal.x, modal.y) = (open, 20.0, 20.0);
+
+        let mut window = Window::new(300.0, 300.0)
+            .component(Component::Modal(modal))
+            .container(Container::new(ContainerType::Column).component(button(0.0, 0.0, 300.0, 300.0)));
+        window.layout(&Fonts::new());
+        window
+    }
+
+    fn press(dispatcher: &mut Dispatcher, window: &mut Window, x: f64, y: f64) -> bool {
+        dispatcher.cursor = (x, y);
+        // SAFETY: the dummy id is only compared, never passed to the platform
+        let device_id = unsafe { DeviceId::dummy() };
+        dispatcher.handle(window, &WindowEvent::MouseInput { device_id, state: ElementState::Pressed, button: MouseButton::Left })
+    }
+
+    fn pressed(window: &Window, path: &[usize]) -> bool {
+        window.widget(path).is_some_and(|w| w.states().pressed)
+    }
+
+    fn move_to(dispatcher: &mut Dispatcher, window: &mut Window, x: f64, y: f64) {
+        // SAFETY: the dummy id is only compared, never passed to the platform
+        let device_id = unsafe { DeviceId::dummy() };
+        dispatcher.handle(window, &WindowEvent::CursorMoved { device_id, position: (x, y).into() });
+    }
+
+    type Log = Rc<RefCell<Vec<(&'static str, Phase)>>>;
+
+    /// Records every phase it sees an event in, stopping propagation in `stop`
+    struct Recorder {
+        name: &'static str,
+        rect: Rect,
+        stop: Option<Phase>,
+        log: Log,
+        child: Option<Box<Recorder>>,
+    }
+
+    impl Widget for Recorder {
+        fn bounds(&self) -> Rect {
+            self.rect
+        }
+
+        fn handle_event(&mut self, _event: &Event, ctx: &mut EventContext) {
+            self.log.borrow_mut().push((self.name, ctx.phase()));
+            if self.stop == Some(ctx.phase()) {
+                ctx.stop_propagation();
+            }
+        }
+
+        fn children(&self) -> Vec<&dyn Widget> {
+            self.child.iter().map(|c| c.as_ref() as &dyn Widget).collect()
+        }
+
+        fn children_mut(&mut self) -> Vec<&mut dyn Widget> {
+            self.child.iter_mut().map(|c| c.as_mut() as &mut dyn Widget).collect()
+        }
+    }
+
+    /// An outer recorder covering the window with an inner one in its top left corner
+    fn recorders(stop: Option<(&'static str, Phase)>) -> (Window, Log) {
+        let log = Log::default();
+        let recorder = |name: &'static str, rect, child| Recorder {
+            name,
+            rect,
+            stop: stop.filter(|(n, _)| *n == name).map(|(_, phase)| phase),
+            log: log.clone(),
+            child,
+        };
+        let inner = recorder("inner", Rect::new(0.0, 0.0, 10.0, 10.0), None);
+        let outer = recorder("outer", Rect::new(0.0, 0.0, 300.0, 300.0), Some(Box::new(inner)));
+
+        (Window::new(300.0, 300.0).component(Component::custom(outer)), log)
+    }
+
+    #[test]
+    fn open_overlays_are_hit_first() {
+        assert_eq!(window(true).hit_test(30.0, 30.0), [0, 0]);
+        assert_eq!(window(false).hit_test(30.0, 30.0), [1, 0]);
+    }
+
+    #[test]
+    fn events_capture_down_then_bubble_up() {
+        let (mut window, log) = recorders(None);
+        assert_eq!(window.hit_test(5.0, 5.0), [0, 0]);
+        assert_eq!(window.hit_test(50.0, 50.0), [0]);
+
+        assert!(!press(&mut Dispatcher::new(), &mut window, 5.0, 5.0));
+        assert_eq!(*log.borrow(), [("outer", Phase::Capture), ("inner", Phase::Target), ("outer", Phase::Bubble)]);
+
+        log.borrow_mut().clear();
+        assert!(!press(&mut Dispatcher::new(), &mut window, 50.0, 50.0));
+        assert_eq!(*log.borrow(), [("outer", Phase::Target)]);
+    }
+
+    #[test]
+    fn stopped_events_reach_no_further_widgets() {
+        let (mut window, log) = recorders(Some(("outer", Phase::Capture)));
+        window.take_dirty();
+        assert!(press(&mut Dispatcher::new(), &mut window, 5.0, 5.0));
+        assert_eq!(*log.borrow(), [("outer", Phase::Capture)]);
+        assert_eq!(window.take_dirty(), [vec![0]]);
+
+        let (mut window, log) = recorders(Some(("inner", Phase::Target)));
+        assert!(press(&mut Dispatcher::new(), &mut window, 5.0, 5.0));
+        assert_eq!(*log.borrow(), [("outer", Phase::Capture), ("inner", Phase::Target)]);
+    }
+
+    #[test]
+    fn only_changed_states_restyle_the_window() {
+        let mut window = window(false);
+        let mut dispatcher = Dispatcher::new();
+        window.restyle_pending = false;
+
+        // Entering the button hovers it, moving within it changes nothing
+        move_to(&mut dispatcher, &mut window, 150.0, 150.0);
+        assert!(std::mem::take(&mut window.restyle_pending));
+        move_to(&mut dispatcher, &mut window, 160.0, 160.0);
+        assert!(!window.restyle_pending);
+
+        assert!(press(&mut dispatcher, &mut window, 160.0, 160.0));
+        assert!(window.restyle_pending);
+    }
+
+    #[test]
+    fn focus_scope_blocks_the_pointer_outside_it() {
+        let mut window = window(true);
+        let mut dispatcher = Dispatcher::new();
+
+        assert!(!press(&mut dispatcher, &mut window, 200.0, 200.0));
+        assert!(!pressed(&window, &[1, 0]));
+        assert_eq!(dispatcher.pressed, None);
+
+        assert!(press(&mut dispatcher, &mut window, 30.0, 30.0));
+        assert!(pressed(&window, &[0, 0]));
+    }
+
+    #[test]
+    fn only_events_that_change_something_mark_the_window_dirty() {
+        let mut window = window(false);
+        let mut dispatcher = Dispatcher::new();
+        window.take_dirty();
+
+        // SAFETY: the dummy id is only compared, never passed to the platform
+        let device_id = unsafe { DeviceId::dummy() };
+        let moved = WindowEvent::CursorMoved { device_id, position: (400.0, 400.0).into() };
+        dispatcher.handle(&mut window, &moved);
+        assert!(!window.is_dirty());
+
+        assert!(press(&mut dispatcher, &mut window, 150.0, 150.0));
+        assert!(window.take_dirty().contains(&vec![1, 0]));
+    }
+
+    #[test]
+    fn removed_children_shift_held_paths() {
+        let row = Container::new(ContainerType::Row)
+            .component(button(0.0, 0.0, 10.0, 10.0))
+            .component(button(0.0, 0.0, 10.0, 10.0))
+            .component(button(0.0, 0.0, 10.0, 10.0));
+        let mut window = Window::new(300.0, 300.0).container(row);
+        let mut dispatcher = Dispatcher::new();
+        dispatcher.focus.set(&mut window, Some(vec![0, 2]), ModifiersState::empty());
+        dispatcher.pressed = Some(vec![0, 2]);
+
+        window.containers[0].remove_child(0);
+        dispatcher.resolve(&mut window);
+        assert_eq!(dispatcher.focus.path(), Some(&[0, 1][..]));
+        assert_eq!(dispatcher.pressed, Some(vec![0, 1]));
+
+        window.containers[0].remove_child(1);
+        dispatcher.resolve(&mut window);
+        assert_eq!(dispatcher.focus.path(), None);
+        assert_eq!(dispatcher.pressed, None);
+    }
+
+    #[test]
+    fn replaced_trees_drop_missing_paths() {
+        let mut window = window(false);
+        let mut dispatcher = Dispatcher::new();
+        dispatcher.focus.set(&mut window, Some(vec![1, 0]), ModifiersState::empty());
+
+        let mut replaced = Window::new(300.0, 300.0);
+        dispatcher.resolve(&mut replaced);
+        assert_eq!(dispatcher.focus.path(), None);
+    }
+}
@@ -0,0 +1,62 @@
+use winit::event::{Ime, KeyEvent, MouseButton, MouseScrollDelta, TouchPhase};
+use winit::keyboard::ModifiersState;
+
+/// Input delivered to widgets, in window coordinates
+#[derive(Clone, Debug, PartialEq)]
+pub enum Event {
+    PointerMoved { x: f64, y: f64 },
+    /// The pointer moved onto the widget or one of its children
+    PointerEntered,
+    /// The pointer moved off the widget and all of its children
+    PointerLeft,
+    PointerPressed { x: f64, y: f64, button: MouseButton },
+    PointerReleased { x: f64, y: f64, button: MouseButton },
+    Wheel { x: f64, y: f64, delta: MouseScrollDelta, phase: TouchPhase },
+    Key(KeyEvent),
+    /// Input method composition - sent to the focused widget
+    Ime(Ime),
+    /// Keyboard focus arrived at or left the widget
+    Focus(bool),
+}
+
+/// Stage of delivery - ancestors see an event on the way down to its target, then again on the
+/// way back up
+#[derive(Copy, Clone, Debug, PartialEq, Eq)]
+pub enum Phase {
+    Capture,
+    Target,
+    Bubble,
+}
+
+/// Delivery state shared by every widget an event passes through
+#[derive(Clone, Debug)]
+pub struct EventContext {
+    pub(crate) phase: Phase,
+    modifiers: ModifiersState,
+    stopped: bool,
+}
+
+impl EventContext {
+    pub fn new(phase: Phase, modifiers: ModifiersState) -> Self {
+        Self { phase, modifiers, stopped: false }
+    }
+
+    pub fn phase(&self) -> Phase {
+        self.phase
+    }
+
+    /// Keyboard modifiers held when the event happened
+    pub fn modifiers(&self) -> ModifiersState {
+        self.modifiers
+    }
+
+    /// Keep the event from reaching any further widgets, and from built-in behaviour such as
+    /// scrolling
+    pub fn stop_propagation(&mut self) {
+        self.stopped = true;
+    }
+
+    pub fn is_stopped(&self) -> bool {
+        self.stopped
+    }
+}
@@ -0,0 +1,348 @@
+use winit::keyboard::ModifiersState;
+use crate::core::primitive::Primitive;
+use crate::core::renderer::Batch;
+use crate::structure::dispatch::{shift, Path};
+use crate::structure::event::{Event, EventContext, Phase};
+use crate::structure::layout::Rect;
+use crate::structure::widget::Widget;
+use crate::structure::window::{Container, ContainerType, Window};
+
+/// Which widget receives keyboard input
+#[derive(Clone, Debug, Default)]
+pub struct Focus {
+    path: Option<Path>,
+}
+
+impl Focus {
+    const RING_WIDTH: f32 = 2.0;
+    const RING_COLOR: [f32; 4] = [0.3, 0.6, 1.0, 1.0];
+
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    pub fn path(&self) -> Option<&[usize]> {
+        self.path.as_deref()
+    }
+
+    /// Move focus to the widget at `path`, telling the old and new widget and scrolling the new
+    /// one into view - `None` clears it
+    pub fn set(&mut self, window: &mut Window, path: Option<Path>, modifiers: ModifiersState) {
+        if self.path == path {
+            return;
+        }
+
+        let mut ctx = EventContext::new(Phase::Target, modifiers);
+        if let Some(widget) = self.path.as_deref().and_then(|p| window.widget_mut(p)) {
+            widget.handle_event(&Event::Focus(false), &mut ctx);
+        }
+        if let Some(widget) = path.as_deref().and_then(|p| window.widget_mut(p)) {
+            widget.handle_event(&Event::Focus(true), &mut ctx);
+            let bounds = widget.bounds();
+            if window.scroll_into_view(bounds) {
+                window.mark_dirty(Path::new());
+            }
+        }
+
+        // Both widgets may show focus differently, such as a caret or composed text
+        for changed in self.path.iter().chain(&path) {
+            window.mark_dirty(changed.clone());
+        }
+        window.restyle_pending = true;
+        self.path = path;
+    }
+
+    pub fn clear(&mut self, window: &mut Window, modifiers: ModifiersState) {
+        self.set(window, None, modifiers);
+    }
+
+    /// Fix the focused path after the child at `removed` left the tree - focus inside it is lost
+    pub(crate) fn removed(&mut self, removed: &[usize]) {
+        if self.path.as_mut().is_some_and(|p| !shift(p, removed)) {
+            self.path = None;
+        }
+    }
+
+    /// Drop focus held by a widget that is no longer in the tree
+    pub(crate) fn resolve(&mut self, window: &Window) {
+        if self.path.as_deref().is_some_and(|p| window.widget(p).is_none()) {
+            self.path = None;
+        }
+    }
+
+    /// Focus the deepest focusable widget along a clicked `path` - clicks outside an open focus
+    /// scope leave focus alone
+    pub fn click(&mut self, window: &mut Window, path: &[usize], modifiers: ModifiersState) {
+        if let Some(scope) = window.focus_scope() {
+            if !path.starts_with(&scope) {
+                return;
+            }
+        }
+
+        let target = (1..=path.len()).rev()
+            .find(|&d| window.widget(&path[..d]).is_some_and(|w| w.focusable()))
+            .map(|d| path[..d].to_vec());
+        self.set(window, target, modifiers);
+    }
+
+    /// Tab and Shift+Tab - move to the next or previous widget in tab order, wrapping around and
+    /// staying inside the open focus scope
+    pub fn advance(&mut self, window: &mut Window, forward: bool, modifiers: ModifiersState) -> bool {
+        let order = window.tab_order();
+        if order.is_empty() {
+            return false;
+        }
+
+        let current = self.path.as_ref().and_then(|p| order.iter().position(|o| o == p));
+        let next = match (current, forward) {
+            (Some(i), true) => (i + 1) % order.len(),
+            (Some(i), false) => (i + order.len() - 1) % order.len(),
+            (None, true) => 0,
+            (None, false) => order.len() - 1,
+        };
+
+        self.set(window, Some(order[next].clone()), modifiers);
+        true
+    }
+
+    /// Outline the focused widget, clipped like the widget itself - nothing is drawn while it
+    /// sits on a hidden tab page
+    pub fn draw(&self, window: &Window, batch: &mut Batch) {
+        let Some(path) = self.path.as_deref() else { return };
+        let (Some(widget), Some(viewports)) = (window.widget(path), window.viewports(path)) else { return };
+        let r = widget.bounds();
+        let w = Self::RING_WIDTH;
+
+        for v in &viewports {
+            batch.push_clip([v.x as f32, v.y as f32, v.width as f32, v.height as f32]);
+        }
+        batch.primitive(
+            Primitive::rect(r.x as f32 - w, r.y as f32 - w, r.width as f32 + w * 2.0, r.height as f32 + w * 2.0, [0.0; 4])
+                .radius(w * 2.0)
+                .border(w, Self::RING_COLOR),
+        );
+        viewports.iter().for_each(|_| batch.pop_clip());
+    }
+}
+
+impl Window {
+    /// Paths of every widget reachable with Tab - positive tab indices first in ascending order,
+    /// then the rest in layout order. Limited to the open focus scope, if any
+    pub fn tab_order(&self) -> Vec<Path> {
+        let mut found = Vec::new();
+        match self.focus_scope() {
+            Some(scope) => {
+                if let Some(widget) = self.widget(&scope) {
+                    collect_widget(widget, scope, &mut found);
+                }
+            },
+            None => {
+                let offset = self.components.len();
+                for (i, c) in self.components.iter().enumerate() {
+                    collect_widget(c, vec![i], &mut found);
+                }
+                for (i, c) in self.containers.iter().enumerate() {
+                    collect_container(c, vec![offset + i], &mut found);
+                }
+            },
+        }
+
+        // Stable sort keeps layout order among equal indices
+        found.sort_by_key(|(index, _)| if *index > 0 { (0, *index) } else { (1, 0) });
+        found.into_iter().map(|(_, path)| path).collect()
+    }
+
+    /// Viewports of the scrollables enclosing `path`, outermost first - `None` when a tab
+    /// container along the way does not show it
+    pub(crate) fn viewports(&self, path: &[usize]) -> Option<Vec<Rect>> {
+        let mut viewports = Vec::new();
+        let Some((&first, mut rest)) = path.split_first() else { return Some(viewports) };
+        let Some(mut container) = first.checked_sub(self.components.len()).and_then(|i| self.containers.get(i)) else {
+            return Some(viewports);
+        };
+
+        loop {
+            if let ContainerType::Scrollable(scroll) = &container.layout {
+                viewports.push(scroll.viewport);
+            }
+            let Some((&index, next)) = rest.split_first() else { break };
+            if !container.shows(index) {
+                return None;
+            }
+            match index.checked_sub(container.components.len()).and_then(|i| container.containers.get(i)) {
+                Some(c) => (container, rest) = (c, next),
+                // Widgets draw their own children without clipping them
+                None => break,
+            }
+        }
+
+        Some(viewports)
+    }
+
+    /// The topmost widget currently trapping focus, such as an open modal
+    pub fn focus_scope(&self) -> Option<Path> {
+        let mut scope = None;
+        let offset = self.components.len();
+        for (i, c) in self.components.iter().enumerate() {
+            find_scope(c, vec![i], &mut scope);
+        }
+        for (i, c) in self.containers.iter().enumerate() {
+            find_scope_container(c, vec![offset + i], &mut scope);
+        }
+        scope
+    }
+}
+
+fn child(path: &[usize], index: usize) -> Path {
+    let mut path = path.to_vec();
+    path.push(index);
+    path
+}
+
+fn collect_widget(widget: &dyn Widget, path: Path, found: &mut Vec<(i32, Path)>) {
+    if widget.focusable() && widget.tab_index() >= 0 {
+        found.push((widget.tab_index(), path.clone()));
+    }
+    for (i, c) in widget.children().into_iter().enumerate() {
+        collect_widget(c, child(&path, i), found);
+    }
+}
+
+fn collect_container(container: &Container, path: Path, found: &mut Vec<(i32, Path)>) {
+    let offset = container.components.len();
+    for (i, c) in container.components.iter().enumerate().filter(|(i, _)| container.shows(*i)) {
+        collect_widget(c, child(&path, i), found);
+    }
+    for (i, c) in container.containers.iter().enumerate().filter(|(i, _)| container.shows(offset + i)) {
+        collect_container(c, child(&path, offset + i), found);
+    }
+}
+
+fn find_scope(widget: &dyn Widget, path: Path, scope: &mut Option<Path>) {
+    if widget.focus_scope() {
+        *scope = Some(path.clone());
+    }
+    for (i, c) in widget.children().into_iter().enumerate() {
+        find_scope(c, child(&path, i), scope);
+    }
+}
+
+fn find_scope_container(container: &Container, path: Path, scope: &mut Option<Path>) {
+    let offset = container.components.len();
+    for (i, c) in container.components.iter().enumerate().filter(|(i, _)| container.shows(*i)) {
+        find_scope(c, child(&path, i), scope);
+    }
+    for (i, c) in container.containers.iter().enumerate().filter(|(i, _)| container.shows(offset + i)) {
+        find_scope_container(c, child(&path, offset + i), scope);
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::core::color::Color;
+    use crate::structure::components::{Button, Component, Modal};
+    use crate::structure::scroll::Scroll;
+    use crate::structure::window::ContainerType;
+    use crate::text::Fonts;
+
+    fn button(tab_index: i32) -> Button {
+        let mut button = Button::with_size("", Color::Blue, 10.0, 10.0);
+        button.tab_index = tab_index;
+        button
+    }
+
+    fn window(modal: bool) -> Window {
+        let mut dialog = Modal::new(vec![Component::Button(button(0)), Component::Button(button(0))]);
+        dialog.open = modal;
+
+        Window::new(100.0, 100.0)
+            .component(Component::Modal(dialog))
+            .container(Container::new(ContainerType::Column)
+                .component(Component::Button(button(0)))
+                .component(Component::Button(button(2)))
+                .component(Component::Button(button(-1)))
+                .component(Component::Button(button(1))))
+    }
+
+    #[test]
+    fn positive_tab_indices_come_first() {
+        assert_eq!(window(false).tab_order(), [vec![1, 3], vec![1, 1], vec![1, 0]]);
+    }
+
+    #[test]
+    fn advance_wraps_in_both_directions() {
+        let mut window = window(false);
+        let mut focus = Focus::new();
+        let modifiers = ModifiersState::empty();
+
+        focus.advance(&mut window, false, modifiers);
+        assert_eq!(focus.path(), Some(&[1, 0][..]));
+        focus.advance(&mut window, true, modifiers);
+        assert_eq!(focus.path(), Some(&[1, 3][..]));
+        assert!(window.widget(&[1, 3]).is_some_and(|w| w.states().focus));
+        assert!(!window.widget(&[1, 0]).is_some_and(|w| w.states().focus));
+    }
+
+    #[test]
+    fn open_modals_trap_focus() {
+        let mut window = window(true);
+        let mut focus = Focus::new();
+        let modifiers = ModifiersState::empty();
+
+        assert_eq!(window.focus_scope(), Some(vec![0]));
+        assert_eq!(window.tab_order(), [vec![0, 0], vec![0, 1]]);
+
+        focus.click(&mut window, &[1, 1], modifiers);
+        assert_eq!(focus.path(), None);
+        focus.click(&mut window, &[0, 1], modifiers);
+        assert_eq!(focus.path(), Some(&[0, 1][..]));
+    }
+
+    #[test]
+    fn focus_ring_follows_scroll_clipping_and_hidden_tabs() {
+        use crate::structure::tabs::Tabs;
+
+        let scroll = Container::new(ContainerType::Scrollable(Scroll::default()))
+            .component(Component::Button(button(0)));
+        let tabs = Container::new(ContainerType::Tab(Tabs::new().tab("A").tab("B")))
+            .component(Component::Button(button(0)))
+            .container(scroll);
+        let mut window = Window::new(100.0, 100.0).component(Component::Button(button(0))).container(tabs);
+        window.layout(&Fonts::new());
+
+        assert_eq!(window.viewports(&[0]), Some(Vec::new()));
+        assert_eq!(window.viewports(&[1, 0]), Some(Vec::new()));
+        assert_eq!(window.viewports(&[1, 1, 0]), None);
+
+        let mut focus = Focus::new();
+        focus.path = Some(vec![1, 1, 0]);
+        let mut batch = Batch::new();
+        focus.draw(&window, &mut batch);
+        assert!(batch.is_empty());
+
+        let ContainerType::Tab(tabs) = &mut window.containers[0].layout else { unreachable!() };
+        tabs.select(1);
+        window.layout(&Fonts::new());
+        let ContainerType::Scrollable(scroll) = &window.containers[0].containers[0].layout else { unreachable!() };
+        assert_eq!(window.viewports(&[1, 1, 0]), Some(vec![scroll.viewport]));
+        assert_eq!(window.viewports(&[1, 0]), None);
+        focus.draw(&window, &mut batch);
+        assert_eq!(batch.primitives.len(), 1);
+    }
+
+    #[test]
+    fn focusing_a_hidden_widget_scrolls_it_into_view() {
+        let buttons = (0..10).fold(Container::new(ContainerType::Scrollable(Scroll::default())), |c, _| {
+            c.component(Component::Button(Button::with_size("", Color::Blue, 10.0, 30.0)))
+        });
+        let mut window = Window::new(100.0, 100.0).container(buttons);
+        window.layout(&Fonts::new());
+
+        let mut focus = Focus::new();
+        focus.set(&mut window, Some(vec![0, 5]), ModifiersState::empty());
+        let ContainerType::Scrollable(scroll) = &window.containers[0].layout else { unreachable!() };
+        assert_eq!(scroll.offset, [0.0, 80.0]);
+        assert!(window.take_dirty().contains(&Path::new()));
+    }
+}
@@ -0,0 +1,426 @@
+use serde::Deserialize;
+use crate::structure::layout::{Rect, Size};
+
+/// Sizing of a single grid row or column
+#[derive(Clone, Debug, PartialEq, Deserialize)]
+pub enum Track {
+    /// Exactly this many pixels
+    Fixed(f64),
+    /// Share of the space left after every other track is sized
+    Fraction(f64),
+    /// As large as the largest item in the track
+    Auto,
+    /// Between a minimum and maximum sizing - a fraction as the minimum counts as 0
+    MinMax(Box<Track>, Box<Track>),
+}
+
+impl Track {
+    pub fn minmax(min: Track, max: Track) -> Self {
+        Track::MinMax(Box::new(min), Box::new(max))
+    }
+
+    /// Smallest size of the track for items of `content` size
+    fn base(&self, content: f64) -> f64 {
+        match self {
+            Track::Fixed(size) => *size,
+            Track::Fraction(_) => 0.0,
+            Track::Auto => content,
+            Track::MinMax(min, _) => min.base(content),
+        }
+    }
+
+    /// Largest size the track grows to, `Err` holding the fraction for flexible tracks
+    fn limit(&self, content: f64) -> Result<f64, f64> {
+        match self {
+            Track::Fixed(size) => Ok(*size),
+            Track::Fraction(fraction) => Err(*fraction),
+            Track::Auto => Ok(content),
+            Track::MinMax(_, max) => max.limit(content),
+        }
+    }
+
+    fn is_auto(&self) -> bool {
+        match self {
+            Track::Auto => true,
+            Track::MinMax(min, _) => min.is_auto(),
+            _ => false,
+        }
+    }
+}
+
+/// Where a child sits in a grid - unset lines are filled by auto placement
+#[derive(Copy, Clone, Debug, PartialEq, Eq, Deserialize)]
+#[serde(default)]
+pub struct GridItem {
+    pub column: Option<usize>,
+    pub row: Option<usize>,
+    pub column_span: usize,
+    pub row_span: usize,
+}
+
+impl Default for GridItem {
+    fn default() -> Self {
+        Self { column: None, row: None, column_span: 1, row_span: 1 }
+    }
+}
+
+impl GridItem {
+    pub fn at(column: usize, row: usize) -> Self {
+        Self { column: Some(column), row: Some(row), ..Default::default() }
+    }
+
+    pub fn span(mut self, columns: usize, rows: usize) -> Self {
+        self.column_span = columns.max(1);
+        self.row_span = rows.max(1);
+        self
+    }
+}
+
+/// Parameters of a grid container
+#[derive(Clone, Debug, PartialEq, Deserialize)]
+#[serde(default)]
+pub struct Grid {
+    /// An empty list lays children out in equal columns, as square as possible
+    pub columns: Vec<Track>,
+    pub rows: Vec<Track>,
+    /// Sizing of rows created by auto placement past the end of `rows`
+    pub auto_rows: Track,
+    pub column_gap: f64,
+    pub row_gap: f64,
+    /// Placement of each child in layout order (components, then containers) - children past
+    /// the end of the list are auto placed
+    pub items: Vec<GridItem>,
+}
+
+impl Default for Grid {
+    fn default() -> Self {
+        Self {
+            columns: Vec::new(),
+            rows: Vec::new(),
+            auto_rows: Track::Auto,
+            column_gap: 0.0,
+            row_gap: 0.0,
+            items: Vec::new(),
+        }
+    }
+}
+
+/// A child resolved to grid lines
+#[derive(Copy, Clone, Debug, PartialEq)]
+struct Placed {
+    column: usize,
+    row: usize,
+    columns: usize,
+    rows: usize,
+}
+
+impl Grid {
+    pub fn new(columns: Vec<Track>, rows: Vec<Track>) -> Self {
+        Self { columns, rows, ..Default::default() }
+    }
+
+    pub fn gap(mut self, column_gap: f64, row_gap: f64) -> Self {
+        self.column_gap = column_gap;
+        self.row_gap = row_gap;
+        self
+    }
+
+    pub fn item(mut self, item: GridItem) -> Self {
+        self.items.push(item);
+        self
+    }
+
+    fn column_tracks(&self, count: usize) -> Vec<Track> {
+        if self.columns.is_empty() {
+            let columns = (count as f64).sqrt().ceil().max(1.0) as usize;
+            vec![Track::Fraction(1.0); columns]
+        } else {
+            self.columns.clone()
+        }
+    }
+
+    fn row_tracks(&self, rows: usize) -> Vec<Track> {
+        let auto = if self.columns.is_empty() { Track::Fraction(1.0) } else { self.auto_rows.clone() };
+        (0..rows.max(self.rows.len()))
+            .map(|i| self.rows.get(i).cloned().unwrap_or_else(|| auto.clone()))
+            .collect()
+    }
+
+    /// Resolve every child to grid lines - explicitly placed children first, then the rest in
+    /// order, row by row
+    fn place(&self, count: usize, columns: usize) -> Vec<Placed> {
+        let mut occupied: Vec<Vec<bool>> = Vec::new();
+        let free = |occupied: &Vec<Vec<bool>>, p: &Placed| {
+            (p.row..p.row + p.rows).all(|r| {
+                (p.column..p.column + p.columns).all(|c| !occupied.get(r).is_some_and(|row| row[c]))
+            })
+        };
+        let mark = |occupied: &mut Vec<Vec<bool>>, p: &Placed| {
+            while occupied.len() < p.row + p.rows {
+                occupied.push(vec![false; columns]);
+            }
+            for row in &mut occupied[p.row..p.row + p.rows] {
+                row[p.column..p.column + p.columns].iter_mut().for_each(|c| *c = true);
+            }
+        };
+
+        // Spans are public and deserialized, so 0 can reach here
+        let items: Vec<GridItem> = (0..count)
+            .map(|i| self.items.get(i).copied().unwrap_or_default())
+            .map(|item| GridItem { column_span: item.column_span.max(1), row_span: item.row_span.max(1), ..item })
+            .collect();
+        let mut placed: Vec<Option<Placed>> = vec![None; count];
+
+        for (i, item) in items.iter().enumerate() {
+            if let (Some(column), Some(row)) = (item.column, item.row) {
+                let column = column.min(columns - 1);
+                let p = Placed { column, row, columns: item.column_span.min(columns - column), rows: item.row_span };
+                mark(&mut occupied, &p);
+                placed[i] = Some(p);
+            }
+        }
+
+        let mut cursor = (0, 0);
+        for (i, item) in items.iter().enumerate() {
+            if placed[i].is_some() {
+                continue;
+            }
+
+            let span = item.column_span.min(columns);
+            let mut p = Placed { column: 0, row: 0, columns: span, rows: item.row_span };
+            match (item.column, item.row) {
+                (Some(column), None) => {
+                    p.column = column.min(columns - 1);
+                    p.columns = span.min(columns - p.column);
+                    while !free(&occupied, &p) {
+                        p.row += 1;
+                    }
+                },
+                (None, Some(row)) => {
+                    p.row = row;
+                    loop {
+                        if p.column + span > columns {
+                            // The row is full - continue below it
+                            p.column = 0;
+                            p.row += 1;
+                        }
+                        if free(&occupied, &p) {
+                            break;
+                        }
+                        p.column += 1;
+                    }
+                },
+                _ => {
+                    (p.row, p.column) = cursor;
+                    loop {
+                        if p.column + span > columns {
+                            p.column = 0;
+                            p.row += 1;
+                        }
+                        if free(&occupied, &p) {
+                            break;
+                        }
+                        p.column += 1;
+                    }
+                    cursor = (p.row, p.column + span);
+                },
+            }
+
+            mark(&mut occupied, &p);
+            placed[i] = Some(p);
+        }
+
+        placed.into_iter().flatten().collect()
+    }
+
+    /// Size every track - `items` holds the first track, span and size of each child. Without
+    /// `available` space flexible tracks take their content size
+    fn size_tracks(tracks: &[Track], items: &[(usize, usize, f64)], gap: f64, available: Option<f64>) -> Vec<f64> {
+        let mut content = vec![0.0f64; tracks.len()];
+        for &(start, span, size) in items {
+            if span == 1 {
+                content[start] = content[start].max(size);
+            }
+        }
+
+        let mut sizes: Vec<f64> = tracks.iter().zip(&content).map(|(t, c)| t.base(*c)).collect();
+
+        // Spanning items grow the auto tracks they cross when they do not fit
+        for &(start, span, size) in items.iter().filter(|(_, span, _)| *span > 1) {
+            let range = start..start + span;
+            let current: f64 = sizes[range.clone()].iter().sum::<f64>() + gap * (span - 1) as f64;
+            let auto: Vec<usize> = range.filter(|i| tracks[*i].is_auto()).collect();
+            if size > current && !auto.is_empty() {
+                let extra = (size - current) / auto.len() as f64;
+                for i in auto {
+                    sizes[i] += extra;
+                    content[i] = content[i].max(sizes[i]);
+                }
+            }
+        }
+
+        let gaps = gap * tracks.len().saturating_sub(1) as f64;
+        let Some(available) = available else {
+            // Natural size - every track at its content or fixed maximum
+            return tracks.iter().zip(&content).zip(&sizes)
+                .map(|((t, c), s)| t.limit(*c).unwrap_or(*c).max(*s))
+                .collect();
+        };
+
+        // Grow tracks with a fixed maximum, sharing free space equally - every pass either uses
+        // up the free space or caps a track, so there is at most one pass per track
+        for _ in 0..=tracks.len() {
+            let free = available - gaps - sizes.iter().sum::<f64>();
+            let growable: Vec<usize> = (0..tracks.len())
+                .filter(|i| matches!(tracks[*i].limit(content[*i]), Ok(max) if max > sizes[*i]))
+                .collect();
+            // Sub-pixel leftovers from rounding are not worth another pass
+            if free <= 1e-6 || growable.is_empty() {
+                break;
+            }
+
+            let share = free / growable.len() as f64;
+            for i in growable {
+                let max = tracks[i].limit(content[i]).unwrap_or(f64::INFINITY);
+                sizes[i] = (sizes[i] + share).min(max);
+            }
+        }
+
+        // Flexible tracks split what remains, but never drop below their minimum
+        let mut flexible: Vec<usize> = (0..tracks.len()).filter(|i| tracks[*i].limit(content[*i]).is_err()).collect();
+        loop {
+            let fixed: f64 = (0..tracks.len()).filter(|i| !flexible.contains(i)).map(|i| sizes[i]).sum();
+            let fractions: f64 = flexible.iter().map(|i| tracks[*i].limit(content[*i]).unwrap_err()).sum();
+            if flexible.is_empty() || fractions <= 0.0 {
+                break;
+            }
+
+            let unit = (available - gaps - fixed).max(0.0) / fractions;
+            let (fit, below): (Vec<usize>, Vec<usize>) = flexible.iter()
+                .partition(|i| unit * tracks[**i].limit(content[**i]).unwrap_err() >= tracks[**i].base(content[**i]));
+
+            if below.is_empty() {
+                for i in fit {
+                    sizes[i] = unit * tracks[i].limit(content[i]).unwrap_err();
+                }
+                break;
+            }
+
+            for i in below {
+                sizes[i] = tracks[i].base(content[i]);
+            }
+            flexible = fit;
+        }
+
+        sizes
+    }
+
+    /// Natural size of the grid's content for children of `sizes`
+    pub fn measure(&self, sizes: &[Size]) -> Size {
+        let (columns, rows, _) = self.tracks(sizes, None);
+        let total = |tracks: &[f64], gap: f64| tracks.iter().sum::<f64>() + gap * tracks.len().saturating_sub(1) as f64;
+
+        Size::new(total(&columns, self.column_gap), total(&rows, self.row_gap))
+    }
+
+    /// Rectangles of each child when the grid fills `content`
+    pub fn layout(&self, content: Rect, sizes: &[Size]) -> Vec<Rect> {
+        let (columns, rows, placed) = self.tracks(sizes, Some(content.size()));
+        let offsets = |tracks: &[f64], start: f64, gap: f64| {
+            tracks.iter().scan(start, |position, size| {
+                let offset = *position;
+                *position += size + gap;
+                Some(offset)
+            }).collect::<Vec<f64>>()
+        };
+        let x = offsets(&columns, content.x, self.column_gap);
+        let y = offsets(&rows, content.y, self.row_gap);
+        let extent = |tracks: &[f64], start: usize, span: usize, gap: f64| {
+            tracks[start..start + span].iter().sum::<f64>() + gap * (span - 1) as f64
+        };
+
+        placed.iter()
+            .map(|p| Rect::new(
+                x[p.column],
+                y[p.row],
+                extent(&columns, p.column, p.columns, self.column_gap),
+                extent(&rows, p.row, p.rows, self.row_gap),
+            ))
+            .collect()
+    }
+
+    fn tracks(&self, sizes: &[Size], available: Option<Size>) -> (Vec<f64>, Vec<f64>, Vec<Placed>) {
+        let column_tracks = self.column_tracks(sizes.len());
+        let placed = self.place(sizes.len(), column_tracks.len());
+        let row_count = placed.iter().map(|p| p.row + p.rows).max().unwrap_or(0);
+        let row_tracks = self.row_tracks(row_count);
+
+        let column_items: Vec<_> = placed.iter().zip(sizes).map(|(p, s)| (p.column, p.columns, s.width)).collect();
+        let row_items: Vec<_> = placed.iter().zip(sizes).map(|(p, s)| (p.row, p.rows, s.height)).collect();
+
+        let columns = Self::size_tracks(&column_tracks, &column_items, self.column_gap, available.map(|s| s.width));
+        let rows = Self::size_tracks(&row_tracks, &row_items, self.row_gap, available.map(|s| s.height));
+
+        (columns, rows, placed)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn placed(grid: &Grid, count: usize) -> Vec<(usize, usize, usize, usize)> {
+        let columns = grid.column_tracks(count).len();
+        grid.place(count, columns).iter().map(|p| (p.column, p.row, p.columns, p.rows)).collect()
+    }
+
+    #[test]
+    fn auto_placement_flows_around_placed_and_spanning_items() {
+        let grid = Grid::new(vec![Track::Auto; 3], Vec::new())
+            .item(GridItem::default())
+            .item(GridItem::at(1, 0).span(2, 1))
+            .item(GridItem::default().span(2, 1));
+
+        assert_eq!(placed(&grid, 4), [(0, 0, 1, 1), (1, 0, 2, 1), (0, 1, 2, 1), (2, 1, 1, 1)]);
+    }
+
+    #[test]
+    fn out_of_range_spans_and_lines_are_clamped() {
+        let grid = Grid::new(vec![Track::Auto; 2], Vec::new())
+            .item(GridItem { column: Some(5), row: Some(0), column_span: 3, row_span: 0 })
+            .item(GridItem::default().span(9, 1));
+
+        assert_eq!(placed(&grid, 2), [(1, 0, 1, 1), (0, 1, 2, 1)]);
+    }
+
+    #[test]
+    fn tracks_share_space_between_fixed_auto_and_fractions() {
+        let grid = Grid::new(
+            vec![Track::Fixed(50.0), Track::Auto, Track::Fraction(1.0), Track::Fraction(3.0)],
+            vec![Track::Auto],
+        ).gap(10.0, 0.0);
+        let sizes = [Size::new(10.0, 20.0), Size::new(40.0, 30.0), Size::new(10.0, 10.0), Size::new(10.0, 10.0)];
+
+        let rects = grid.layout(Rect::new(0.0, 0.0, 300.0, 100.0), &sizes);
+        let columns: Vec<_> = rects.iter().map(|r| (r.x, r.width)).collect();
+        assert_eq!(columns, [(0.0, 50.0), (60.0, 40.0), (110.0, 45.0), (165.0, 135.0)]);
+        // Without space to share, fractions take their content size
+        assert_eq!(grid.measure(&sizes), Size::new(140.0, 30.0));
+    }
+
+    #[test]
+    fn fractions_never_drop_below_their_minimum() {
+        let grid = Grid::new(vec![Track::minmax(Track::Fixed(80.0), Track::Fraction(1.0)), Track::Fraction(1.0)], Vec::new());
+        let rects = grid.layout(Rect::new(0.0, 0.0, 100.0, 10.0), &[Size::ZERO, Size::ZERO]);
+
+        assert_eq!((rects[0].width, rects[1].width), (80.0, 20.0));
+    }
+
+    #[test]
+    fn spanning_items_grow_the_auto_tracks_they_cross() {
+        let tracks = [Track::Fixed(20.0), Track::Auto, Track::Auto];
+        let widths = Grid::size_tracks(&tracks, &[(0, 3, 100.0)], 0.0, None);
+
+        assert_eq!(widths, [20.0, 40.0, 40.0]);
+    }
+}
@@ -0,0 +1,176 @@
+use winit::event::{MouseScrollDelta, TouchPhase};
+use crate::structure::dispatch::{prepend, Path};
+use crate::structure::layout::Rect;
+use crate::structure::scroll::Scroll;
+use crate::structure::tabs::TabPress;
+use crate::structure::window::{Container, ContainerType, Window};
+
+impl Container {
+    /// Route a wheel event at `x`, `y` to the innermost scrollable that can still move
+    pub fn wheel(&mut self, x: f64, y: f64, delta: MouseScrollDelta, phase: TouchPhase) -> bool {
+        if !self.rect.contains(x, y) {
+            return false;
+        }
+        if self.visible_containers_mut().any(|c| c.wheel(x, y, delta, phase)) {
+            return true;
+        }
+
+        match &mut self.layout {
+            ContainerType::Scrollable(scroll) => scroll.wheel(delta, phase),
+            ContainerType::Tab(tabs) if tabs.strip.contains(x, y) => {
+                let (dx, dy) = match delta {
+                    MouseScrollDelta::LineDelta(x, y) => (x as f64 * Scroll::LINE, y as f64 * Scroll::LINE),
+                    MouseScrollDelta::PixelDelta(p) => (p.x, p.y),
+                };
+                // Plain wheels only scroll vertically, so either direction moves the strip
+                tabs.scroll_by(-(dx + dy))
+            },
+            _ => false,
+        }
+    }
+
+    /// Press the left button at `x`, `y` - grabs scrollbars and tabs
+    pub fn press(&mut self, x: f64, y: f64) -> bool {
+        if !self.rect.contains(x, y) {
+            return false;
+        }
+
+        // Scrollbars and tab strips sit above the content
+        let grabbed = match &mut self.layout {
+            ContainerType::Scrollable(scroll) => scroll.press(x, y),
+            ContainerType::Tab(tabs) => match tabs.press(x, y) {
+                TabPress::Selected => true,
+                TabPress::Close(index) => {
+                    if let Some(page) = tabs.close(index) {
+                        self.remove_child(page);
+                    }
+                    true
+                },
+                TabPress::Missed => false,
+            },
+            _ => false,
+        };
+
+        grabbed || self.visible_containers_mut().any(|c| c.press(x, y))
+    }
+
+    pub fn drag_to(&mut self, x: f64, y: f64) -> bool {
+        let moved = match &mut self.layout {
+            ContainerType::Scrollable(scroll) => scroll.drag_to(x, y),
+            ContainerType::Tab(tabs) => tabs.drag_to(x),
+            _ => false,
+        };
+
+        self.containers.iter_mut().fold(moved, |moved, c| c.drag_to(x, y) | moved)
+    }
+
+    pub fn release(&mut self) {
+        match &mut self.layout {
+            ContainerType::Scrollable(scroll) => scroll.release(),
+            ContainerType::Tab(tabs) => tabs.release(),
+            _ => {}
+        }
+        self.containers.iter_mut().for_each(|c| c.release());
+    }
+
+    /// Advance momentum scrolling by `dt` seconds - returns true while anything is moving
+    pub fn animate(&mut self, dt: f64) -> bool {
+        let moving = match &mut self.layout {
+            ContainerType::Scrollable(scroll) => scroll.animate(dt),
+            _ => false,
+        };
+
+        self.containers.iter_mut().fold(moving, |moving, c| c.animate(dt) | moving)
+    }
+
+    /// Scroll every scrollable around `rect` so it becomes visible - innermost first, taking
+    /// effect at the next layout
+    pub fn scroll_into_view(&mut self, rect: Rect) -> bool {
+        let mut moved = self.visible_containers_mut().fold(false, |moved, c| c.scroll_into_view(rect) | moved);
+
+        if let ContainerType::Scrollable(scroll) = &mut self.layout {
+            let content = Rect::new(
+                scroll.viewport.x - scroll.offset[0],
+                scroll.viewport.y - scroll.offset[1],
+                scroll.content.width,
+                scroll.content.height,
+            );
+            if content.contains(rect.x, rect.y) {
+                moved |= scroll.scroll_into_view(rect);
+            }
+        }
+
+        moved
+    }
+
+    /// Switch the innermost tab container along `path` to its next or previous tab - returns
+    /// the path to it
+    pub fn cycle_tabs(&mut self, path: &[usize], forward: bool) -> Option<Path> {
+        if let Some((&first, rest)) = path.split_first() {
+            let offset = self.components.len();
+            if let Some(child) = first.checked_sub(offset).and_then(|i| self.containers.get_mut(i)) {
+                if let Some(inner) = child.cycle_tabs(rest, forward) {
+                    return Some(prepend(first, inner));
+                }
+            }
+        }
+
+        match &mut self.layout {
+            ContainerType::Tab(tabs) if forward => tabs.next(),
+            ContainerType::Tab(tabs) => tabs.previous(),
+            _ => return None,
+        }
+        Some(Path::new())
+    }
+
+    fn first_tabs(&self) -> Option<Path> {
+        if let ContainerType::Tab(_) = self.layout {
+            return Some(Path::new());
+        }
+
+        let offset = self.components.len();
+        self.containers.iter().enumerate()
+            .filter(|(i, _)| self.shows(offset + i))
+            .find_map(|(i, c)| c.first_tabs().map(|p| prepend(offset + i, p)))
+    }
+}
+
+impl Window {
+    pub fn wheel(&mut self, x: f64, y: f64, delta: MouseScrollDelta, phase: TouchPhase) -> bool {
+        self.containers.iter_mut().rev().any(|c| c.wheel(x, y, delta, phase))
+    }
+
+    pub fn press(&mut self, x: f64, y: f64) -> bool {
+        self.containers.iter_mut().rev().any(|c| c.press(x, y))
+    }
+
+    pub fn drag_to(&mut self, x: f64, y: f64) -> bool {
+        self.containers.iter_mut().fold(false, |moved, c| c.drag_to(x, y) | moved)
+    }
+
+    pub fn release(&mut self) {
+        self.containers.iter_mut().for_each(|c| c.release());
+    }
+
+    pub fn animate(&mut self, dt: f64) -> bool {
+        self.containers.iter_mut().fold(false, |moving, c| c.animate(dt) | moving)
+    }
+
+    pub fn scroll_into_view(&mut self, rect: Rect) -> bool {
+        self.containers.iter_mut().fold(false, |moved, c| c.scroll_into_view(rect) | moved)
+    }
+
+    /// Ctrl+Tab - cycles the innermost tab container holding `path`, normally the focused widget
+    pub fn cycle_tabs(&mut self, path: &[usize], forward: bool) -> Option<Path> {
+        let (&first, rest) = path.split_first()?;
+        let container = self.containers.get_mut(first.checked_sub(self.components.len())?)?;
+        Some(prepend(first, container.cycle_tabs(rest, forward)?))
+    }
+
+    /// Path to the first tab container shown, in layout order
+    pub fn first_tabs(&self) -> Option<Path> {
+        let offset = self.components.len();
+        self.containers.iter().enumerate()
+            .find_map(|(i, c)| c.first_tabs().map(|p| prepend(offset + i, p)))
+    }
+}
@@ -0,0 +1,702 @@
+use crate::structure::components::Component;
+use crate::structure::dispatch::Path;
+use crate::structure::widget::Widget;
+use crate::structure::window::{Container, ContainerType, Window};
+use crate::text::Fonts;
+use serde::Deserialize;
+
+#[derive(Copy, Clone, Debug, Default, PartialEq, Deserialize)]
+pub struct Size {
+    pub width: f64,
+    pub height: f64,
+}
+
+impl Size {
+    pub const ZERO: Size = Size { width: 0.0, height: 0.0 };
+    pub const INFINITE: Size = Size { width: f64::INFINITY, height: f64::INFINITY };
+
+    pub fn new(width: f64, height: f64) -> Self {
+        Self { width, height }
+    }
+
+    pub fn clamp(self, min: Size, max: Size) -> Self {
+        Self {
+            width: self.width.min(max.width).max(min.width),
+            height: self.height.min(max.height).max(min.height),
+        }
+    }
+
+    fn main(&self, axis: Axis) -> f64 {
+        match axis {
+            Axis::Horizontal => self.width,
+            Axis::Vertical => self.height,
+        }
+    }
+
+    fn cross(&self, axis: Axis) -> f64 {
+        self.main(axis.flip())
+    }
+
+    fn from_axis(axis: Axis, main: f64, cross: f64) -> Self {
+        match axis {
+            Axis::Horizontal => Self::new(main, cross),
+            Axis::Vertical => Self::new(cross, main),
+        }
+    }
+}
+
+#[derive(Copy, Clone, Debug, Default, PartialEq)]
+pub struct Rect {
+    pub x: f64,
+    pub y: f64,
+    pub width: f64,
+    pub height: f64,
+}
+
+impl Rect {
+    pub fn new(x: f64, y: f64, width: f64, height: f64) -> Self {
+        Self { x, y, width, height }
+    }
+
+    pub fn size(&self) -> Size {
+        Size::new(self.width, self.height)
+    }
+
+    pub fn contains(&self, x: f64, y: f64) -> bool {
+        x >= self.x && x <= self.x + self.width && y >= self.y && y <= self.y + self.height
+    }
+
+    /// Shrink by `edges`, never below zero size
+    pub fn inset(&self, edges: Edges) -> Self {
+        Self {
+            x: self.x + edges.left,
+            y: self.y + edges.top,
+            width: (self.width - edges.left - edges.right).max(0.0),
+            height: (self.height - edges.top - edges.bottom).max(0.0),
+        }
+    }
+
+    fn from_axis(axis: Axis, main: f64, cross: f64, size: Size) -> Self {
+        match axis {
+            Axis::Horizontal => Self::new(main, cross, size.width, size.height),
+            Axis::Vertical => Self::new(cross, main, size.width, size.height),
+        }
+    }
+
+    fn main_start(&self, axis: Axis) -> f64 {
+        match axis {
+            Axis::Horizontal => self.x,
+            Axis::Vertical => self.y,
+        }
+    }
+
+    fn cross_start(&self, axis: Axis) -> f64 {
+        self.main_start(axis.flip())
+    }
+}
+
+/// Space around the content of a container
+#[derive(Copy, Clone, Debug, Default, PartialEq, Deserialize)]
+#[serde(default)]
+pub struct Edges {
+    pub top: f64,
+    pub right: f64,
+    pub bottom: f64,
+    pub left: f64,
+}
+
+impl Edges {
+    pub fn all(value: f64) -> Self {
+        Self { top: value, right: value, bottom: value, left: value }
+    }
+
+    pub fn symmetric(vertical: f64, horizontal: f64) -> Self {
+        Self { top: vertical, right: horizontal, bottom: vertical, left: horizontal }
+    }
+
+    fn horizontal(&self) -> f64 {
+        self.left + self.right
+    }
+
+    fn vertical(&self) -> f64 {
+        self.top + self.bottom
+    }
+}
+
+/// Placement of children across the stacking direction of a container
+#[derive(Copy, Clone, Debug, Default, PartialEq, Eq, Deserialize)]
+pub enum Align {
+    #[default]
+    Start,
+    Center,
+    End,
+    /// Fill the container, limited by the child's maximum size
+    Stretch,
+}
+
+impl Align {
+    /// Offset of an item of `size` within `available` space
+    fn offset(&self, available: f64, size: f64) -> f64 {
+        match self {
+            Align::Start | Align::Stretch => 0.0,
+            Align::Center => (available - size) / 2.0,
+            Align::End => available - size,
+        }
+    }
+}
+
+/// Distribution of leftover space along the stacking direction of a row or column
+#[derive(Copy, Clone, Debug, Default, PartialEq, Eq, Deserialize)]
+pub enum Justify {
+    #[default]
+    Start,
+    Center,
+    End,
+    /// No space before the first or after the last child
+    SpaceBetween,
+    /// Half sized space before the first and after the last child
+    SpaceAround,
+    /// Equal space before, between and after children
+    SpaceEvenly,
+}
+
+impl Justify {
+    /// Space before the first item and extra space between items
+    fn distribute(&self, free: f64, count: usize) -> (f64, f64) {
+        let free = free.max(0.0);
+        let count = count as f64;
+        match self {
+            Justify::Start => (0.0, 0.0),
+            Justify::Center => (free / 2.0, 0.0),
+            Justify::End => (free, 0.0),
+            Justify::SpaceBetween if count > 1.0 => (0.0, free / (count - 1.0)),
+            Justify::SpaceBetween => (0.0, 0.0),
+            Justify::SpaceAround => (free / count / 2.0, free / count),
+            Justify::SpaceEvenly => (free / (count + 1.0), free / (count + 1.0)),
+        }
+    }
+}
+
+/// How a container is sized by a parent row or column
+#[derive(Copy, Clone, Debug, PartialEq, Deserialize)]
+#[serde(default)]
+pub struct Flex {
+    /// Share of positive free space taken, 0 never grows
+    pub grow: f64,
+    /// Share of negative free space given up, weighted by the basis - 0 never shrinks
+    pub shrink: f64,
+    /// Initial main size, the measured size when `None`
+    pub basis: Option<f64>,
+}
+
+impl Default for Flex {
+    fn default() -> Self {
+        Self { grow: 0.0, shrink: 1.0, basis: None }
+    }
+}
+
+impl Flex {
+    pub fn grow(grow: f64) -> Self {
+        Self { grow, ..Default::default() }
+    }
+}
+
+/// A child sized along the main axis of a flex line
+struct Item {
+    basis: f64,
+    main: f64,
+    cross: f64,
+    flex: Flex,
+    min: Size,
+    max: Size,
+}
+
+/// Resolve the main size of each item on a line holding `available` space
+fn resolve(items: &mut [Item], available: f64, axis: Axis) {
+    let mut frozen = vec![false; items.len()];
+    for item in items.iter_mut() {
+        item.main = item.basis;
+    }
+
+    // Items clamped by their min or max size are frozen and the rest redistributed
+    loop {
+        let used: f64 = items.iter().zip(&frozen).map(|(i, f)| if *f { i.main } else { i.basis }).sum();
+        let free = available - used;
+        let growing = free > 0.0;
+        let weight = |i: &Item| if growing { i.flex.grow } else { i.flex.shrink * i.basis };
+
+        let total: f64 = items.iter().zip(&frozen).filter(|(_, f)| !**f).map(|(i, _)| weight(i)).sum();
+        if total <= 0.0 || free == 0.0 {
+            return;
+        }
+
+        let mut violated = false;
+        for (item, frozen) in items.iter_mut().zip(frozen.iter_mut()).filter(|(_, f)| !**f) {
+            let target = item.basis + free * weight(item) / total;
+            let clamped = target.min(item.max.main(axis)).max(item.min.main(axis)).max(0.0);
+            item.main = clamped;
+            if clamped != target {
+                *frozen = true;
+                violated = true;
+            }
+        }
+
+        if !violated {
+            return;
+        }
+    }
+}
+
+#[derive(Copy, Clone, Debug, PartialEq, Eq)]
+enum Axis {
+    Horizontal,
+    Vertical,
+}
+
+impl Axis {
+    fn flip(self) -> Self {
+        match self {
+            Axis::Horizontal => Axis::Vertical,
+            Axis::Vertical => Axis::Horizontal,
+        }
+    }
+}
+
+/// A child of a container during layout - components first, then containers, matching draw order
+enum Child<'a> {
+    Component(&'a mut Component),
+    Container(&'a mut Container),
+}
+
+impl Child<'_> {
+    fn layout(&mut self, rect: Rect, fonts: &Fonts) {
+        match self {
+            Child::Component(c) => c.layout(rect, fonts),
+            Child::Container(c) => c.layout(rect, fonts),
+        }
+    }
+}
+
+impl Container {
+    fn children(&mut self) -> Vec<Child<'_>> {
+        self.components.iter_mut().map(Child::Component)
+            .chain(self.containers.iter_mut().map(Child::Container))
+            .collect()
+    }
+
+    /// Flex, min and max size of every child in layout order - components never flex
+    fn child_items(&self) -> Vec<(Flex, Size, Size)> {
+        self.components.iter().map(|_| (Flex { grow: 0.0, shrink: 0.0, basis: None }, Size::ZERO, Size::INFINITE))
+            .chain(self.containers.iter().map(|c| (c.flex, c.min_size, c.max_size)))
+            .collect()
+    }
+
+    fn child_sizes(&self, fonts: &Fonts) -> Vec<Size> {
+        self.components.iter().map(|c| c.measure(fonts))
+            .chain(self.containers.iter().map(|c| c.measure(fonts)))
+            .collect()
+    }
+
+    /// Preferred size of the container, including padding and clamped to its min and max size
+    pub fn measure(&self, fonts: &Fonts) -> Size {
+        let sizes = self.child_sizes(fonts);
+        let gaps = self.spacing * sizes.len().saturating_sub(1) as f64;
+
+        let content = match self.layout {
+            ContainerType::Column | ContainerType::Scrollable(_) => Size::new(
+                sizes.iter().map(|s| s.width).fold(0.0, f64::max),
+                sizes.iter().map(|s| s.height).sum::<f64>() + gaps,
+            ),
+            ContainerType::Row => Size::new(
+                sizes.iter().map(|s| s.width).sum::<f64>() + gaps,
+                sizes.iter().map(|s| s.height).fold(0.0, f64::max),
+            ),
+            ContainerType::Tab(ref tabs) => {
+                let strip = tabs.measure(fonts);
+                Size::new(
+                    sizes.iter().map(|s| s.width).fold(0.0, f64::max),
+                    sizes.iter().map(|s| s.height).fold(0.0, f64::max) + strip.height,
+                )
+            },
+            ContainerType::Grid(ref grid) => grid.measure(&sizes),
+        };
+
+        let padding = self.inner_padding();
+        Size::new(content.width + padding.horizontal(), content.height + padding.vertical())
+            .clamp(self.min_size, self.max_size)
+    }
+
+    /// Position the container at `rect` and lay out its children inside it
+    pub fn layout(&mut self, rect: Rect, fonts: &Fonts) {
+        self.rect = rect;
+        let content = rect.inset(self.inner_padding());
+        let sizes = self.child_sizes(fonts);
+        self.sizes.clone_from(&sizes);
+
+        match self.layout {
+            ContainerType::Column => self.stack(Axis::Vertical, content, &sizes, fonts, true),
+            ContainerType::Scrollable(ref mut scroll) => {
+                // Scrolled content keeps its natural size, but fills the viewport when smaller
+                let gaps = self.spacing * sizes.len().saturating_sub(1) as f64;
+                let natural = Size::new(
+                    sizes.iter().map(|s| s.width).fold(0.0, f64::max),
+                    sizes.iter().map(|s| s.height).sum::<f64>() + gaps,
+                );
+                scroll.viewport = content;
+                scroll.content = Size::new(
+                    if scroll.horizontal { natural.width.max(content.width) } else { content.width },
+                    if scroll.vertical { natural.height.max(content.height) } else { content.height },
+                );
+                scroll.clamp();
+
+                let scrolled = Rect::new(
+                    content.x - scroll.offset[0],
+                    content.y - scroll.offset[1],
+                    scroll.content.width,
+                    scroll.content.height,
+                );
+                self.stack(Axis::Vertical, scrolled, &sizes, fonts, false)
+            },
+            ContainerType::Row => self.stack(Axis::Horizontal, content, &sizes, fonts, true),
+            ContainerType::Tab(ref mut tabs) => {
+                // Hidden pages keep their last layout until they are shown again
+                let page = tabs.layout(content, fonts);
+                if let Some(mut child) = tabs.page().and_then(|p| self.children().into_iter().nth(p)) {
+                    child.layout(page, fonts);
+                }
+            },
+            ContainerType::Grid(ref grid) => {
+                let rects = grid.layout(content, &sizes);
+                for (mut child, rect) in self.children().into_iter().zip(rects) {
+                    child.layout(rect, fonts);
+                }
+            },
+        }
+    }
+
+    /// Place children one after another along `axis` - when `flexible` children grow and shrink
+    /// to fill the line, and wrap onto new lines if the container wraps
+    fn stack(&mut self, axis: Axis, content: Rect, sizes: &[Size], fonts: &Fonts, flexible: bool) {
+        let (align, justify, spacing) = (self.align, self.justify, self.spacing);
+        let available = content.size().main(axis);
+
+        let mut items: Vec<Item> = self.child_items().into_iter().zip(sizes)
+            .map(|((flex, min, max), size)| {
+                let basis = flex.basis.unwrap_or(size.main(axis)).min(max.main(axis)).max(min.main(axis));
+                Item { basis, main: basis, cross: size.cross(axis), flex, min, max }
+            })
+            .collect();
+
+        // Break into lines - every line holds at least one item
+        let mut lines: Vec<std::ops::Range<usize>> = Vec::new();
+        lines.push(0..0);
+        for (i, item) in items.iter().enumerate() {
+            let line = lines.last_mut().unwrap();
+            let used: f64 = items[line.clone()].iter().map(|i| i.basis + spacing).sum();
+            if self.wrap && line.start != line.end && used + item.basis > available {
+                lines.push(i..i + 1);
+            } else {
+                line.end = i + 1;
+            }
+        }
+
+        // A single line fills the container across, wrapped lines are as tall as their tallest item
+        let single = lines.len() == 1;
+        let mut cross_cursor = content.cross_start(axis);
+        let mut rects = Vec::with_capacity(items.len());
+
+        for line in lines {
+            let items = &mut items[line];
+            let gaps = spacing * items.len().saturating_sub(1) as f64;
+            if flexible {
+                resolve(items, available - gaps, axis);
+            }
+
+            let line_cross = if single {
+                content.size().cross(axis)
+            } else {
+                items.iter().map(|i| i.cross).fold(0.0, f64::max)
+            };
+
+            let used: f64 = items.iter().map(|i| i.main).sum::<f64>() + gaps;
+            let (leading, between) = justify.distribute(available - used, items.len());
+            let mut cursor = content.main_start(axis) + leading;
+
+            for item in items.iter() {
+                let cross = match align {
+                    Align::Stretch => line_cross.min(item.max.cross(axis)).max(item.min.cross(axis)),
+                    _ => item.cross,
+                };
+                let offset = align.offset(line_cross, cross);
+
+                rects.push(Rect::from_axis(axis, cursor, cross_cursor + offset, Size::from_axis(axis, item.main, cross)));
+                cursor += item.main + spacing + between;
+            }
+
+            cross_cursor += line_cross + spacing;
+        }
+
+        for (mut child, rect) in self.children().into_iter().zip(rects) {
+            child.layout(rect, fonts);
+        }
+    }
+}
+
+impl Container {
+    /// Lay out again what a change at `path` below this container affects - a child that keeps
+    /// its preferred size is placed again where it is. Returns true when the child's size changed,
+    /// so the whole container needs laying out again by the caller, who knows whether the
+    /// container's own size changed in turn
+    fn relayout(&mut self, path: &[usize], fonts: &Fonts) -> bool {
+        let Some((&index, rest)) = path.split_first() else { return true };
+        let offset = self.components.len();
+
+        let size = match self.components.get(index) {
+            Some(component) => component.measure(fonts),
+            None => {
+                let Some(container) = self.containers.get_mut(index - offset) else { return false };
+                if !container.relayout(rest, fonts) {
+                    return false;
+                }
+                container.measure(fonts)
+            },
+        };
+        if self.sizes.get(index) != Some(&size) {
+            return true;
+        }
+
+        // Hidden tab pages are laid out when they are shown
+        if self.shows(index) {
+            match self.children().into_iter().nth(index) {
+                Some(Child::Component(c)) => {
+                    let rect = c.bounds();
+                    c.layout(rect, fonts);
+                },
+                Some(Child::Container(c)) => c.layout(c.rect, fonts),
+                None => {},
+            }
+        }
+        false
+    }
+}
+
+impl Window {
+    /// Resolve the rectangles of every container in the window - each top level container fills
+    /// the window, while top level components keep their own position at their preferred size
+    pub fn layout(&mut self, fonts: &Fonts) {
+        for i in 0..self.components.len() {
+            self.layout_component(i, fonts);
+        }
+
+        let rect = Rect::new(0.0, 0.0, self.width, self.height);
+        for container in &mut self.containers {
+            container.layout(rect, fonts);
+        }
+    }
+
+    fn layout_component(&mut self, index: usize, fonts: &Fonts) {
+        let component = &mut self.components[index];
+        let (position, size) = (component.bounds(), component.measure(fonts));
+        component.layout(Rect::new(position.x, position.y, size.width, size.height), fonts);
+    }
+
+    /// Lay out only what changed at the `dirty` paths - widgets that keep their preferred size
+    /// are placed again where they are, and containers only when a child's size changed. An
+    /// empty path lays out the whole window
+    pub fn relayout(&mut self, dirty: &[Path], fonts: &Fonts) {
+        if dirty.iter().any(|p| p.is_empty()) {
+            return self.layout(fonts);
+        }
+
+        let rect = Rect::new(0.0, 0.0, self.width, self.height);
+        let offset = self.components.len();
+        for path in dirty {
+            let index = path[0];
+            if index < offset {
+                self.layout_component(index, fonts);
+            } else if let Some(container) = self.containers.get_mut(index - offset) {
+                if container.relayout(&path[1..], fonts) {
+                    container.layout(rect, fonts);
+                }
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::cell::Cell;
+    use std::rc::Rc;
+    use super::*;
+
+    /// Widget with a preferred size set from outside, recording where and how often it is placed
+    #[derive(Clone, Default)]
+    struct Probe {
+        size: Rc<Cell<Size>>,
+        rect: Rc<Cell<Rect>>,
+        layouts: Rc<Cell<u32>>,
+    }
+
+    impl Probe {
+        fn new(width: f64, height: f64) -> Self {
+            let probe = Self::default();
+            probe.size.set(Size::new(width, height));
+            probe
+        }
+
+        fn component(&self) -> Component {
+            Component::custom(self.clone())
+        }
+    }
+
+    impl Widget for Probe {
+        fn measure(&self, _fonts: &Fonts) -> Size {
+            self.size.get()
+        }
+
+        fn layout(&mut self, rect: Rect, _fonts: &Fonts) {
+            self.rect.set(rect);
+            self.layouts.set(self.layouts.get() + 1);
+        }
+
+        fn bounds(&self) -> Rect {
+            self.rect.get()
+        }
+    }
+
+    fn column(probes: &[&Probe]) -> Window {
+        let column = probes.iter().fold(Container::new(ContainerType::Column), |c, p| c.component(p.component()));
+        let mut window = Window::new(200.0, 200.0).container(column);
+        window.layout(&Fonts::new());
+        window
+    }
+
+    #[test]
+    fn relayout_places_a_widget_that_kept_its_size_in_place() {
+        let (a, b) = (Probe::new(50.0, 20.0), Probe::new(50.0, 20.0));
+        let mut window = column(&[&a, &b]);
+
+        window.relayout(&[vec![0, 0]], &Fonts::new());
+        assert_eq!((a.layouts.get(), b.layouts.get()), (2, 1));
+        assert_eq!(a.rect.get(), Rect::new(0.0, 0.0, 50.0, 20.0));
+    }
+
+    #[test]
+    fn relayout_moves_siblings_when_a_size_changed() {
+        let (a, b) = (Probe::new(50.0, 20.0), Probe::new(50.0, 20.0));
+        let mut window = column(&[&a, &b]);
+
+        a.size.set(Size::new(50.0, 60.0));
+        window.relayout(&[vec![0, 0]], &Fonts::new());
+        assert_eq!((a.layouts.get(), b.layouts.get()), (2, 2));
+        assert_eq!(b.rect.get().y, 60.0);
+    }
+
+    #[test]
+    fn relayout_stops_at_containers_that_kept_their_size() {
+        let (a, b, c) = (Probe::new(50.0, 20.0), Probe::new(50.0, 20.0), Probe::new(50.0, 20.0));
+        // The inner column is fixed in size, so changes inside it never reach `c`
+        let inner = Container::new(ContainerType::Column)
+            .component(a.component())
+            .component(b.component())
+            .min_size(Size::new(100.0, 100.0))
+            .max_size(Size::new(100.0, 100.0));
+        let outer = Container::new(ContainerType::Column).component(c.component()).container(inner);
+        let mut window = Window::new(200.0, 200.0).container(outer);
+        window.layout(&Fonts::new());
+
+        a.size.set(Size::new(50.0, 40.0));
+        window.relayout(&[vec![0, 1, 0]], &Fonts::new());
+        assert_eq!((a.layouts.get(), b.layouts.get(), c.layouts.get()), (2, 2, 1));
+        assert_eq!(b.rect.get().y, 60.0);
+    }
+
+    #[test]
+    fn an_empty_dirty_path_lays_out_everything() {
+        let (a, b) = (Probe::new(50.0, 20.0), Probe::new(50.0, 20.0));
+        let mut window = column(&[&a, &b]);
+
+        window.relayout(&[vec![0, 1], vec![]], &Fonts::new());
+        assert_eq!((a.layouts.get(), b.layouts.get()), (2, 2));
+    }
+
+    /// An empty container of a fixed size, which unlike components can flex
+    fn block(width: f64, height: f64) -> Container {
+        Container::new(ContainerType::Column).min_size(Size::new(width, height))
+    }
+
+    fn laid_out(container: Container) -> Container {
+        let mut window = Window::new(300.0, 200.0).container(container);
+        window.layout(&Fonts::new());
+        window.containers.remove(0)
+    }
+
+    #[test]
+    fn columns_stack_inside_padding_with_spacing() {
+        let (a, b) = (Probe::new(50.0, 20.0), Probe::new(80.0, 30.0));
+        let column = Container::new(ContainerType::Column)
+            .padding(Edges::symmetric(10.0, 5.0))
+            .spacing(4.0)
+            .component(a.component())
+            .component(b.component());
+        assert_eq!(column.measure(&Fonts::new()), Size::new(90.0, 74.0));
+
+        laid_out(column);
+        assert_eq!(a.rect.get(), Rect::new(5.0, 10.0, 50.0, 20.0));
+        assert_eq!(b.rect.get(), Rect::new(5.0, 34.0, 80.0, 30.0));
+    }
+
+    #[test]
+    fn rows_share_free_space_by_grow_and_respect_max_size() {
+        let row = laid_out(Container::new(ContainerType::Row)
+            .container(block(50.0, 10.0).flex(Flex::grow(1.0)))
+            .container(block(50.0, 10.0).flex(Flex::grow(3.0)).max_size(Size::new(80.0, f64::INFINITY)))
+            .container(block(50.0, 10.0).flex(Flex::grow(1.0))));
+
+        // The second block is frozen at its max size and the rest is split between the others
+        let widths: Vec<_> = row.containers.iter().map(|c| c.rect.width).collect();
+        assert_eq!(widths, [110.0, 80.0, 110.0]);
+        assert_eq!(row.containers[2].rect.x, 190.0);
+    }
+
+    #[test]
+    fn rows_shrink_by_basis_when_overfull() {
+        let row = laid_out(Container::new(ContainerType::Row)
+            .container(block(200.0, 10.0).flex(Flex { basis: Some(200.0), ..Flex::default() }))
+            .container(block(0.0, 10.0).flex(Flex { basis: Some(200.0), ..Flex::default() })));
+
+        let widths: Vec<_> = row.containers.iter().map(|c| c.rect.width).collect();
+        assert_eq!(widths, [200.0, 100.0]);
+    }
+
+    #[test]
+    fn justify_and_align_place_leftover_space() {
+        let (a, b) = (Probe::new(50.0, 20.0), Probe::new(50.0, 40.0));
+        laid_out(Container::new(ContainerType::Row)
+            .justify(Justify::SpaceBetween)
+            .align(Align::Center)
+            .component(a.component())
+            .component(b.component()));
+        assert_eq!(a.rect.get(), Rect::new(0.0, 90.0, 50.0, 20.0));
+        assert_eq!(b.rect.get(), Rect::new(250.0, 80.0, 50.0, 40.0));
+
+        let (a, b) = (Probe::new(50.0, 20.0), Probe::new(50.0, 40.0));
+        laid_out(Container::new(ContainerType::Row)
+            .justify(Justify::Center)
+            .align(Align::Stretch)
+            .component(a.component())
+            .component(b.component()));
+        assert_eq!(a.rect.get(), Rect::new(100.0, 0.0, 50.0, 200.0));
+        assert_eq!(b.rect.get(), Rect::new(150.0, 0.0, 50.0, 200.0));
+    }
+
+    #[test]
+    fn wrapping_rows_break_onto_lines_as_tall_as_their_tallest_item() {
+        let probes: Vec<_> = [(120.0, 20.0), (120.0, 30.0), (120.0, 10.0)].iter().map(|&(w, h)| Probe::new(w, h)).collect();
+        laid_out(probes.iter().fold(Container::new(ContainerType::Row).wrap(true).spacing(10.0), |row, p| row.component(p.component())));
+
+        assert_eq!(probes[0].rect.get(), Rect::new(0.0, 0.0, 120.0, 20.0));
+        assert_eq!(probes[1].rect.get(), Rect::new(130.0, 0.0, 120.0, 30.0));
+        assert_eq!(probes[2].rect.get(), Rect::new(0.0, 40.0, 120.0, 10.0));
+    }
+}
@@ -0,0 +1,154 @@
+/// Build a window, container or component tree declaratively
+///
+/// Containers take an optional layout configuration in brackets, optional attributes in
+/// parentheses that call the `Container` builder methods of the same name, and their children in
+/// braces. Components take their constructor arguments in parentheses, followed by any builder
+/// methods of the component:
+///
+/// ```
+/// use kozy::kozy;
+/// use kozy::structure::layout::Edges;
+/// use kozy::structure::signal::Signal;
+/// use kozy::structure::tabs::Tabs;
+///
+/// let name = Signal::new(String::from("Hello"));
+/// let window = kozy! {
+///     Window(800, 600) {
+///         Column(padding: Edges::all(8.0), spacing: 4.0) {
+///             Label("Hello").bind(name.clone()),
+///             Row {
+///                 Button("Ok").on_click(|_| println!("ok")),
+///                 Button("Cancel").disabled(true),
+///             },
+///             Tab[Tabs::new().tab("One").tab("Two")] {
+///                 TextBox("first"),
+///                 Slider(0.0, 1.0, 0.5),
+///             },
+///         }
+///     }
+/// };
+///
+/// // The label is the column's only component, the row and the tabs its containers
+/// assert_eq!(window.containers[0].components.len(), 1);
+/// assert_eq!(window.containers[0].containers.len(), 2);
+/// ```
+///
+/// Known containers are `Column`, `Row`, `Scrollable`, `Grid` and `Tab`. Components are `Button`,
+/// `Label`, `TextBox`, `Password`, `Toggle`, `Slider`, and `Custom(widget)` for any `Widget`
+/// - anything else is a compile error naming what was expected:
+///
+/// ```compile_fail
+/// let window = kozy::kozy! {
+///     Window(800, 600) {
+///         Stack { Button("Ok") }
+///     }
+/// };
+/// ```
+///
+/// ```compile_fail
+/// let window = kozy::kozy! {
+///     Window(800, 600) {
+///         Column { Checkbox(true) }
+///     }
+/// };
+/// ```
+#[macro_export]
+macro_rules! kozy {
+    (Window ($width:expr, $height:expr) { $($children:tt)* }) => {
+        $crate::kozy!(@children $crate::structure::window::Window::new($width as f64, $height as f64); $($children)*)
+    };
+
+    // Children are added one at a time, containers first so `Button(..)` is never mistaken for one
+    (@children $parent:expr;) => { $parent };
+    (@children $parent:expr;
+        $kind:ident $([$config:expr])? $(($($key:ident : $value:expr),* $(,)?))? { $($inner:tt)* }
+        $(, $($rest:tt)*)?
+    ) => {
+        $crate::kozy!(@children
+            $parent.container($crate::kozy!($kind $([$config])? $(($($key: $value),*))? { $($inner)* }));
+            $($($rest)*)?
+        )
+    };
+    (@children $parent:expr;
+        $kind:ident ($($args:tt)*) $(.$method:ident ($($margs:tt)*))*
+        $(, $($rest:tt)*)?
+    ) => {
+        $crate::kozy!(@children
+            $parent.component($crate::kozy!($kind ($($args)*) $(.$method ($($margs)*))*));
+            $($($rest)*)?
+        )
+    };
+    (@children $parent:expr; $($unexpected:tt)+) => {
+        compile_error!(concat!(
+            "kozy!: expected `Name(..)` or `Name { .. }`, found `", stringify!($($unexpected)+), "`"
+        ))
+    };
+
+    // Layout configuration
+    (@layout Column) => { $crate::structure::window::ContainerType::Column };
+    (@layout Row) => { $crate::structure::window::ContainerType::Row };
+    (@layout Scrollable) => { $crate::structure::window::ContainerType::Scrollable(::std::default::Default::default()) };
+    (@layout Scrollable $config:expr) => { $crate::structure::window::ContainerType::Scrollable($config) };
+    (@layout Grid) => { $crate::structure::window::ContainerType::Grid($crate::structure::grid::Grid::new(Vec::new(), Vec::new())) };
+    (@layout Grid $config:expr) => { $crate::structure::window::ContainerType::Grid($config) };
+    (@layout Tab) => { $crate::structure::window::ContainerType::Tab($crate::structure::tabs::Tabs::new()) };
+    (@layout Tab $config:expr) => { $crate::structure::window::ContainerType::Tab($config) };
+    (@layout $kind:ident $($config:expr)?) => {
+        compile_error!(concat!(
+            "kozy!: unknown container `", stringify!($kind), "` - expected Column, Row, Scrollable, Grid or Tab"
+        ))
+    };
+
+    // Components
+    (@component Button ($label:expr)) => {
+        $crate::structure::components::Component::Button
+    };
+    (@new Button ($label:expr)) => {
+        $crate::structure::components::Button::with_size($label, $crate::core::color::Color::Korple, 100.0, 32.0)
+    };
+    (@component Label ($text:expr)) => { $crate::structure::components::Component::Label };
+    (@new Label ($text:expr)) => {
+        $crate::structure::components::Label::new(
+            $text, $crate::text::FontId(0), 14.0, $crate::core::color::Color::White, 0.0, 0.0,
+        )
+    };
+    (@component TextBox ($text:expr)) => { $crate::structure::components::Component::TextBox };
+    (@new TextBox ($text:expr)) => {
+        $crate::structure::components::TextBox::new($text, $crate::text::FontId(0), 14.0)
+    };
+    (@component Password ($text:expr)) => { $crate::structure::components::Component::TextBox };
+    (@new Password ($text:expr)) => {
+        $crate::structure::components::TextBox::new($text, $crate::text::FontId(0), 14.0).password(true)
+    };
+    (@component Toggle ($on:expr)) => { $crate::structure::components::Component::Toggle };
+    (@new Toggle ($on:expr)) => { $crate::structure::components::Toggle::new($on) };
+    (@component Slider ($min:expr, $max:expr, $value:expr)) => { $crate::structure::components::Component::Slider };
+    (@new Slider ($min:expr, $max:expr, $value:expr)) => {
+        $crate::structure::components::Slider::new($min, $max, $value)
+    };
+    (@component $kind:ident ($($args:tt)*)) => {
+        compile_error!(concat!(
+            "kozy!: unknown component `", stringify!($kind), "(", stringify!($($args)*), ")` - expected Button(label), ",
+            "Label(text), TextBox(text), Password(text), Toggle(on), Slider(min, max, value) or Custom(widget)"
+        ))
+    };
+    // Already reported by `@component`
+    (@new $kind:ident ($($args:tt)*)) => { () };
+
+    // Entry points for a single container or component
+    (Custom ($widget:expr) $(.$method:ident ($($margs:tt)*))*) => {
+        $crate::structure::components::Component::custom($widget $(.$method($($margs)*))*)
+    };
+    ($kind:ident $([$config:expr])? $(($($key:ident : $value:expr),* $(,)?))? { $($children:tt)* }) => {
+        $crate::kozy!(@children
+            $crate::structure::window::Container::new($crate::kozy!(@layout $kind $($config)?))
+                $($(.$key($value))*)?;
+            $($children)*
+        )
+    };
+    ($kind:ident ($($args:tt)*) $(.$method:ident ($($margs:tt)*))*) => {
+        $crate::kozy!(@component $kind ($($args)*))(
+            $crate::kozy!(@new $kind ($($args)*)) $(.$method($($margs)*))*
+        )
+    };
+}
@@ -0,0 +1,518 @@
+use std::collections::HashMap;
+use std::path::{Path as FilePath, PathBuf};
+use std::time::{Duration, Instant, SystemTime};
+use ron::extensions::Extensions;
+use serde::Deserialize;
+use crate::core::color::Color;
+use crate::structure::components::{Button, Component, Label, Slider, TextBox, Toggle};
+use crate::structure::dispatch::Path;
+use crate::structure::grid::Grid;
+use crate::structure::layout::{Align, Edges, Flex, Justify, Size};
+use crate::structure::scroll::Scroll;
+use crate::structure::style::Element;
+use crate::structure::tabs::Tabs;
+use crate::structure::window::{Container, ContainerType, Window};
+use crate::text::FontId;
+
+/// Root of a layout file
+///
+/// Containers keep their components ahead of their child containers, so a component listed after
+/// a container is laid out - and given a path - before it. Wrap components in a `Column` or `Row`
+/// of their own to place them between containers
+///
+/// ```ignore
+/// Window(
+///     width: 800, height: 600,
+///     children: [
+///         Column(padding: (top: 8, right: 8, bottom: 8, left: 8), spacing: 4, children: [
+///             Label(text: "Name"),
+///             TextBox(id: "name"),
+///             Button(id: "ok", label: "Ok"),
+///         ]),
+///     ],
+/// )
+/// ```
+#[derive(Clone, Debug, Deserialize)]
+#[serde(rename = "Window")]
+pub struct WindowNode {
+    #[serde(default)]
+    pub width: f64,
+    #[serde(default)]
+    pub height: f64,
+    /// Components are laid out before containers, whatever their order here
+    #[serde(default)]
+    pub children: Vec<Node>,
+}
+
+/// A container or component in a layout file
+#[derive(Clone, Debug, Deserialize)]
+pub enum Node {
+    Column(ContainerNode),
+    Row(ContainerNode),
+    Scrollable(ContainerNode),
+    Grid(ContainerNode),
+    Tab(ContainerNode),
+    Button(ButtonNode),
+    Label(LabelNode),
+    TextBox(TextBoxNode),
+    Toggle(ToggleNode),
+    Slider(SliderNode),
+}
+
+#[derive(Clone, Debug, Deserialize)]
+#[serde(default)]
+pub struct ContainerNode {
+    /// Name the container is found by from code
+    pub id: Option<String>,
+    /// Classes matched by stylesheet selectors
+    pub classes: Vec<String>,
+    pub padding: Edges,
+    pub spacing: f64,
+    pub align: Align,
+    pub justify: Justify,
+    pub wrap: bool,
+    pub flex: Flex,
+    pub min_size: Size,
+    /// Unbounded when left out
+    pub max_size: Option<Size>,
+    /// Scrollbars of a `Scrollable`
+    pub scroll: Scroll,
+    /// Tracks and placement of a `Grid`
+    pub grid: Grid,
+    /// Titles of a `Tab` container, one per child
+    pub tabs: Vec<TabNode>,
+    /// Components are laid out before containers, whatever their order here
+    pub children: Vec<Node>,
+}
+
+impl Default for ContainerNode {
+    fn default() -> Self {
+        Self {
+            id: None,
+            classes: Vec::new(),
+            padding: Edges::default(),
+            spacing: 0.0,
+            align: Align::default(),
+            justify: Justify::default(),
+            wrap: false,
+            flex: Flex::default(),
+            min_size: Size::ZERO,
+            max_size: None,
+            scroll: Scroll::default(),
+            grid: Grid::default(),
+            tabs: Vec::new(),
+            children: Vec::new(),
+        }
+    }
+}
+
+#[derive(Clone, Debug, Deserialize)]
+pub struct TabNode {
+    pub title: String,
+    #[serde(default)]
+    pub closable: bool,
+}
+
+#[derive(Clone, Debug, Deserialize)]
+#[serde(default)]
+pub struct ButtonNode {
+    pub id: Option<String>,
+    pub classes: Vec<String>,
+    pub label: String,
+    pub width: f64,
+    pub height: f64,
+    pub font: usize,
+    pub size: f32,
+    pub disabled: bool,
+    pub tab_index: i32,
+}
+
+impl Default for ButtonNode {
+    fn default() -> Self {
+        Self { id: None, classes: Vec::new(), label: String::new(), width: 100.0, height: 32.0, font: 0, size: 14.0, disabled: false, tab_index: 0 }
+    }
+}
+
+/// Text coloured by the stylesheet's `foreground`, which the built-in themes set on the window
+/// and labels inherit - white without one. Select the label by id or class to colour it
+#[derive(Clone, Debug, Deserialize)]
+#[serde(default)]
+pub struct LabelNode {
+    pub id: Option<String>,
+    pub classes: Vec<String>,
+    pub text: String,
+    pub font: usize,
+    pub size: f32,
+}
+
+impl Default for LabelNode {
+    fn default() -> Self {
+        Self { id: None, classes: Vec::new(), text: String::new(), font: 0, size: 14.0 }
+    }
+}
+
+#[derive(Clone, Debug, Deserialize)]
+#[serde(default)]
+pub struct TextBoxNode {
+    pub id: Option<String>,
+    pub classes: Vec<String>,
+    pub text: String,
+    pub font: usize,
+    pub size: f32,
+    pub multiline: bool,
+    pub password: bool,
+    pub tab_index: i32,
+}
+
+impl Default for TextBoxNode {
+    fn default() -> Self {
+        Self { id: None, classes: Vec::new(), text: String::new(), font: 0, size: 14.0, multiline: false, password: false, tab_index: 0 }
+    }
+}
+
+#[derive(Clone, Debug, Default, Deserialize)]
+#[serde(default)]
+pub struct ToggleNode {
+    pub id: Option<String>,
+    pub classes: Vec<String>,
+    pub on: bool,
+    pub tab_index: i32,
+}
+
+#[derive(Clone, Debug, Deserialize)]
+#[serde(default)]
+pub struct SliderNode {
+    pub id: Option<String>,
+    pub classes: Vec<String>,
+    pub min: f64,
+    pub max: f64,
+    pub value: f64,
+    pub tab_index: i32,
+}
+
+impl Default for SliderNode {
+    fn default() -> Self {
+        Self { id: None, classes: Vec::new(), min: 0.0, max: 1.0, value: 0.0, tab_index: 0 }
+    }
+}
+
+#[derive(Debug)]
+pub enum MarkupError {
+    Io(std::io::Error),
+    Parse(ron::error::SpannedError),
+    /// Two widgets in the same layout share an id
+    DuplicateId(String),
+}
+
+impl std::fmt::Display for MarkupError {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            MarkupError::Io(e) => write!(f, "could not read layout: {e}"),
+            MarkupError::Parse(e) => write!(f, "invalid layout: {e}"),
+            MarkupError::DuplicateId(id) => write!(f, "id `{id}` is used more than once"),
+        }
+    }
+}
+
+impl std::error::Error for MarkupError {
+    fn source(&self) -> Option<&(dyn std::error::Error + 'static)> {
+        match self {
+            MarkupError::Io(e) => Some(e),
+            MarkupError::Parse(e) => Some(e),
+            MarkupError::DuplicateId(_) => None,
+        }
+    }
+}
+
+impl From<std::io::Error> for MarkupError {
+    fn from(e: std::io::Error) -> Self {
+        MarkupError::Io(e)
+    }
+}
+
+impl From<ron::error::SpannedError> for MarkupError {
+    fn from(e: ron::error::SpannedError) -> Self {
+        MarkupError::Parse(e)
+    }
+}
+
+impl WindowNode {
+    /// Build the widget tree, recording the path of every node with an id
+    pub fn build(self) -> Result<Window, MarkupError> {
+        let mut window = Window::new(self.width, self.height);
+        let mut ids = HashMap::new();
+        let (components, containers) = build_children(self.children, &[], &mut ids)?;
+        window.components = components;
+        window.containers = containers;
+        window.ids = ids;
+        Ok(window)
+    }
+}
+
+impl ContainerNode {
+    fn build(self, layout: ContainerType, path: &[usize], ids: &mut HashMap<String, Path>) -> Result<Container, MarkupError> {
+        let (components, containers) = build_children(self.children, path, ids)?;
+        let mut container = Container::new(layout)
+            .padding(self.padding)
+            .spacing(self.spacing)
+            .align(self.align)
+            .justify(self.justify)
+            .wrap(self.wrap)
+            .flex(self.flex)
+            .min_size(self.min_size)
+            .max_size(self.max_size.unwrap_or(Size::INFINITE));
+        container.element.id = self.id;
+        container.element.classes = self.classes;
+        container.components = components;
+        container.containers = containers;
+        Ok(container)
+    }
+}
+
+impl Node {
+    fn id(&self) -> Option<&str> {
+        match self {
+            Node::Column(n) | Node::Row(n) | Node::Scrollable(n) | Node::Grid(n) | Node::Tab(n) => n.id.as_deref(),
+            Node::Button(n) => n.id.as_deref(),
+            Node::Label(n) => n.id.as_deref(),
+            Node::TextBox(n) => n.id.as_deref(),
+            Node::Toggle(n) => n.id.as_deref(),
+            Node::Slider(n) => n.id.as_deref(),
+        }
+    }
+
+    fn is_container(&self) -> bool {
+        matches!(self, Node::Column(_) | Node::Row(_) | Node::Scrollable(_) | Node::Grid(_) | Node::Tab(_))
+    }
+
+    fn build(self, path: &[usize], ids: &mut HashMap<String, Path>) -> Result<Built, MarkupError> {
+        if let Some(id) = self.id() {
+            if ids.insert(id.to_owned(), path.to_vec()).is_some() {
+                return Err(MarkupError::DuplicateId(id.to_owned()));
+            }
+        }
+
+        Ok(match self {
+            Node::Column(n) => Built::Container(n.build(ContainerType::Column, path, ids)?),
+            Node::Row(n) => Built::Container(n.build(ContainerType::Row, path, ids)?),
+            Node::Scrollable(mut n) => {
+                let scroll = std::mem::take(&mut n.scroll);
+                Built::Container(n.build(ContainerType::Scrollable(scroll), path, ids)?)
+            },
+            Node::Grid(mut n) => {
+                let grid = std::mem::take(&mut n.grid);
+                Built::Container(n.build(ContainerType::Grid(grid), path, ids)?)
+            },
+            Node::Tab(mut n) => {
+                let tabs = std::mem::take(&mut n.tabs).into_iter().fold(Tabs::new(), |tabs, t| match t.closable {
+                    true => tabs.closable(&t.title),
+                    false => tabs.tab(&t.title),
+                });
+                Built::Container(n.build(ContainerType::Tab(tabs), path, ids)?)
+            },
+            Node::Button(n) => {
+                let mut button = Button::with_size(&n.label, Color::Korple, n.width, n.height)
+                    .font(FontId(n.font), n.size)
+                    .disabled(n.disabled);
+                button.tab_index = n.tab_index;
+                button.element = Element { id: n.id, classes: n.classes, ..button.element };
+                Built::Component(Component::Button(button))
+            },
+            Node::Label(n) => {
+                // Only shows without a stylesheet foreground
+                let mut label = Label::new(&n.text, FontId(n.font), n.size, Color::White, 0.0, 0.0);
+                label.element = Element { id: n.id, classes: n.classes, ..label.element };
+                Built::Component(Component::Label(label))
+            },
+            Node::TextBox(n) => {
+                let mut textbox = match n.multiline {
+                    true => TextBox::multiline(&n.text, FontId(n.font), n.size),
+                    false => TextBox::new(&n.text, FontId(n.font), n.size),
+                }.password(n.password);
+                textbox.tab_index = n.tab_index;
+                textbox.element = Element { id: n.id, classes: n.classes, ..textbox.element };
+                Built::Component(Component::TextBox(textbox))
+            },
+            Node::Toggle(n) => {
+                let mut toggle = Toggle::new(n.on);
+                toggle.tab_index = n.tab_index;
+                toggle.element = Element { id: n.id, classes: n.classes, ..toggle.element };
+                Built::Component(Component::Toggle(toggle))
+            },
+            Node::Slider(n) => {
+                let mut slider = Slider::new(n.min, n.max, n.value);
+                slider.tab_index = n.tab_index;
+                slider.element = Element { id: n.id, classes: n.classes, ..slider.element };
+                Built::Component(Component::Slider(slider))
+            },
+        })
+    }
+}
+
+#[allow(clippy::large_enum_variant)]
+enum Built {
+    Component(Component),
+    Container(Container),
+}
+
+/// Split `nodes` into components and containers - components come first in layout order, so
+/// paths are assigned accordingly and a component listed after a container moves ahead of it
+fn build_children(nodes: Vec<Node>, parent: &[usize], ids: &mut HashMap<String, Path>) -> Result<(Vec<Component>, Vec<Container>), MarkupError> {
+    let offset = nodes.iter().filter(|n| !n.is_container()).count();
+    let mut components = Vec::with_capacity(offset);
+    let mut containers = Vec::new();
+
+    for node in nodes {
+        let index = if node.is_container() { offset + containers.len() } else { components.len() };
+        let mut path = parent.to_vec();
+        path.push(index);
+
+        match node.build(&path, ids)? {
+            Built::Component(c) => components.push(c),
+            Built::Container(c) => containers.push(c),
+        }
+    }
+
+    Ok((components, containers))
+}
+
+impl Window {
+    /// Build a window from a layout in RON
+    pub fn from_ron(source: &str) -> Result<Self, MarkupError> {
+        // Lets nodes be written `Label(id: "name")` rather than `Label(LabelNode(id: Some("name")))`
+        let options = ron::Options::default()
+            .with_default_extension(Extensions::UNWRAP_VARIANT_NEWTYPES | Extensions::IMPLICIT_SOME);
+        options.from_str::<WindowNode>(source)?.build()
+    }
+
+    /// Read and build a layout file
+    pub fn load(path: impl AsRef<FilePath>) -> Result<Self, MarkupError> {
+        Self::from_ron(&std::fs::read_to_string(path)?)
+    }
+
+    /// Path of the widget or container given `id` in its layout file
+    pub fn path_of(&self, id: &str) -> Option<&[usize]> {
+        self.ids.get(id).map(|p| p.as_slice())
+    }
+
+    /// Component given `id` in its layout file
+    pub fn component_mut(&mut self, id: &str) -> Option<&mut Component> {
+        let path = self.ids.get(id)?.clone();
+        match locate(&mut self.components, &mut self.containers, &path)? {
+            Located::Component(c) => Some(c),
+            Located::Container(_) => None,
+        }
+    }
+
+    /// Container given `id` in its layout file
+    pub fn container_mut(&mut self, id: &str) -> Option<&mut Container> {
+        let path = self.ids.get(id)?.clone();
+        match locate(&mut self.components, &mut self.containers, &path)? {
+            Located::Container(c) => Some(c),
+            Located::Component(_) => None,
+        }
+    }
+}
+
+enum Located<'a> {
+    Component(&'a mut Component),
+    Container(&'a mut Container),
+}
+
+fn locate<'a>(components: &'a mut [Component], containers: &'a mut [Container], path: &[usize]) -> Option<Located<'a>> {
+    let (&first, rest) = path.split_first()?;
+    let offset = components.len();
+
+    if first < offset {
+        return rest.is_empty().then(|| Located::Component(&mut components[first]));
+    }
+
+    let container = containers.get_mut(first - offset)?;
+    match rest.is_empty() {
+        true => Some(Located::Container(container)),
+        false => locate(&mut container.components, &mut container.containers, rest),
+    }
+}
+
+/// Watches a layout file for changes so it can be reloaded while the application runs
+pub struct LayoutWatcher {
+    path: PathBuf,
+    modified: Option<SystemTime>,
+    last_check: Instant,
+}
+
+impl LayoutWatcher {
+    /// Least time between two looks at the file
+    const INTERVAL: Duration = Duration::from_millis(250);
+
+    pub fn new(path: impl Into<PathBuf>) -> Self {
+        let path = path.into();
+        let modified = Self::modified(&path);
+        Self { path, modified, last_check: Instant::now() }
+    }
+
+    pub fn path(&self) -> &FilePath {
+        &self.path
+    }
+
+    fn modified(path: &FilePath) -> Option<SystemTime> {
+        std::fs::metadata(path).and_then(|m| m.modified()).ok()
+    }
+
+    /// The rebuilt window when the file changed since the last call - throttled, so it can be
+    /// called every frame
+    pub fn poll(&mut self) -> Option<Result<Window, MarkupError>> {
+        if self.last_check.elapsed() < Self::INTERVAL {
+            return None;
+        }
+        self.last_check = Instant::now();
+
+        let modified = Self::modified(&self.path);
+        if modified.is_none() || modified == self.modified {
+            return None;
+        }
+
+        self.modified = modified;
+        Some(Window::load(&self.path))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::core::renderer::Batch;
+    use crate::structure::style::{rgba, Stylesheet};
+    use crate::structure::widget::Widget;
+    use crate::structure::theme::Theme;
+
+    #[test]
+    fn components_are_placed_ahead_of_containers() {
+        let window = Window::from_ron(r#"Window(children: [
+            Column(id: "column", children: [Label(id: "inner")]),
+            Label(id: "outer"),
+        ])"#).unwrap();
+
+        assert_eq!(window.path_of("outer"), Some(&[0][..]));
+        assert_eq!(window.path_of("column"), Some(&[1][..]));
+        assert_eq!(window.path_of("inner"), Some(&[1, 0][..]));
+    }
+
+    #[test]
+    fn duplicate_ids_are_rejected() {
+        let result = Window::from_ron(r#"Window(children: [Label(id: "a"), Button(id: "a")])"#);
+        assert!(matches!(result, Err(MarkupError::DuplicateId(id)) if id == "a"));
+    }
+
+    #[test]
+    fn labels_take_the_stylesheet_foreground() {
+        let mut window = Window::from_ron(r#"Window(children: [Label(id: "title", text: "Hi")])"#)
+            .unwrap()
+            .theme(Theme::Light)
+            .stylesheet(Stylesheet::parse("#title { foreground: red; }").unwrap());
+        window.restyle();
+
+        let mut batch = Batch::new();
+        window.component_mut("title").unwrap().paint(&mut batch);
+        assert_eq!(batch.text[0].color, rgba(&Color::Red));
+    }
+}
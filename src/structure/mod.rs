@@ -1,2 +1,17 @@
 pub mod components;
+pub mod dispatch;
+pub mod event;
+pub mod focus;
+pub mod grid;
+pub mod input;
+pub mod layout;
+pub mod macros;
+pub mod markup;
+pub mod scroll;
+pub mod signal;
+pub mod style;
+pub mod tabs;
+pub mod textbox;
+pub mod theme;
+pub mod widget;
 pub mod window;
\ No newline at end of file
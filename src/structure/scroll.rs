@@ -0,0 +1,272 @@
+use serde::Deserialize;
+use winit::event::{MouseScrollDelta, TouchPhase};
+use crate::core::primitive::Primitive;
+use crate::core::renderer::Batch;
+use crate::structure::layout::{Rect, Size};
+use crate::structure::style::{Element, Style};
+
+/// Scroll position and scrollbar state of a scrollable container
+#[derive(Clone, Debug, PartialEq, Deserialize)]
+#[serde(default)]
+pub struct Scroll {
+    pub horizontal: bool,
+    pub vertical: bool,
+    /// Keep moving after a touchpad gesture ends, slowing down over time
+    pub momentum: bool,
+    /// Distance scrolled from the top left of the content
+    #[serde(skip)]
+    pub offset: [f64; 2],
+    /// Visible area - resolved by layout
+    #[serde(skip)]
+    pub viewport: Rect,
+    /// Size of everything inside the container - resolved by layout
+    #[serde(skip)]
+    pub content: Size,
+    /// Pixels per second while coasting
+    #[serde(skip)]
+    velocity: [f64; 2],
+    #[serde(skip)]
+    last_gesture: Option<std::time::Instant>,
+    /// Scrollbar being dragged and where on the thumb it was grabbed
+    #[serde(skip)]
+    drag: Option<(usize, f64)>,
+    /// Matched by `Scrollbar` selectors - the thumb is drawn in the background color, that of
+    /// `Scrollbar:pressed` while dragged
+    #[serde(skip)]
+    pub element: Element,
+    /// Resolved from the window's stylesheet
+    #[serde(skip)]
+    pub(crate) style: Style,
+    #[serde(skip)]
+    pub(crate) pressed_style: Style,
+}
+
+impl Default for Scroll {
+    fn default() -> Self {
+        Self {
+            horizontal: false,
+            vertical: true,
+            momentum: false,
+            offset: [0.0; 2],
+            viewport: Rect::default(),
+            content: Size::ZERO,
+            velocity: [0.0; 2],
+            last_gesture: None,
+            drag: None,
+            element: Element::new("Scrollbar"),
+            style: Style::default(),
+            pressed_style: Style::default(),
+        }
+    }
+}
+
+impl Scroll {
+    /// Pixels moved by one line of a mouse wheel
+    pub const LINE: f64 = 40.0;
+    pub const SCROLLBAR_WIDTH: f64 = 8.0;
+    const MIN_THUMB: f64 = 20.0;
+    /// Fraction of velocity lost per second while coasting
+    const FRICTION: f64 = 0.95;
+    /// Coasting stops below this speed in pixels per second
+    const MIN_VELOCITY: f64 = 10.0;
+
+    pub fn new(horizontal: bool, vertical: bool) -> Self {
+        Self { horizontal, vertical, ..Default::default() }
+    }
+
+    pub fn momentum(mut self, momentum: bool) -> Self {
+        self.momentum = momentum;
+        self
+    }
+
+    fn enabled(&self, axis: usize) -> bool {
+        [self.horizontal, self.vertical][axis]
+    }
+
+    pub fn max_offset(&self) -> [f64; 2] {
+        [
+            (self.content.width - self.viewport.width).max(0.0),
+            (self.content.height - self.viewport.height).max(0.0),
+        ]
+    }
+
+    /// Keep the offset within the content
+    pub fn clamp(&mut self) {
+        let max = self.max_offset();
+        for (axis, max) in max.into_iter().enumerate() {
+            self.offset[axis] = if self.enabled(axis) { self.offset[axis].clamp(0.0, max) } else { 0.0 };
+        }
+    }
+
+    /// Move the content by `dx`, `dy` pixels - returns false when already at the limit
+    pub fn scroll_by(&mut self, dx: f64, dy: f64) -> bool {
+        let before = self.offset;
+        self.offset[0] += dx;
+        self.offset[1] += dy;
+        self.clamp();
+        self.offset != before
+    }
+
+    /// Apply a mouse wheel or touchpad event - returns false when nothing moved, so the event can
+    /// be passed on to an outer scrollable
+    pub fn wheel(&mut self, delta: MouseScrollDelta, phase: TouchPhase) -> bool {
+        let (dx, dy) = match delta {
+            MouseScrollDelta::LineDelta(x, y) => (x as f64 * Self::LINE, y as f64 * Self::LINE),
+            MouseScrollDelta::PixelDelta(p) => (p.x, p.y),
+        };
+        // Shift+wheel reports a horizontal delta, which only vertical scrollables would ignore
+        let (dx, dy) = if !self.vertical && dx == 0.0 { (dy, 0.0) } else { (dx, dy) };
+
+        let now = std::time::Instant::now();
+        match phase {
+            // A wheel notch interrupts coasting
+            TouchPhase::Started | TouchPhase::Moved if matches!(delta, MouseScrollDelta::LineDelta(..)) => {
+                self.velocity = [0.0; 2]
+            },
+            TouchPhase::Started => self.velocity = [0.0; 2],
+            TouchPhase::Moved if self.momentum && matches!(delta, MouseScrollDelta::PixelDelta(_)) => {
+                if let Some(last) = self.last_gesture {
+                    let dt = now.duration_since(last).as_secs_f64().max(1.0 / 240.0);
+                    self.velocity = [-dx / dt, -dy / dt];
+                }
+            },
+            TouchPhase::Ended | TouchPhase::Cancelled if !self.momentum => self.velocity = [0.0; 2],
+            _ => {}
+        }
+        self.last_gesture = match (phase, delta) {
+            (TouchPhase::Ended | TouchPhase::Cancelled, _) | (_, MouseScrollDelta::LineDelta(..)) => None,
+            _ => Some(now),
+        };
+
+        // Wheel deltas point the way the content moves, offsets the opposite
+        self.scroll_by(-dx, -dy)
+    }
+
+    /// Advance coasting by `dt` seconds - returns true while still moving
+    pub fn animate(&mut self, dt: f64) -> bool {
+        if self.last_gesture.is_some() || self.velocity == [0.0; 2] {
+            return false;
+        }
+
+        let moved = self.scroll_by(self.velocity[0] * dt, self.velocity[1] * dt);
+        let decay = (1.0 - Self::FRICTION).powf(dt);
+        self.velocity = self.velocity.map(|v| v * decay);
+
+        if !moved || self.velocity.iter().all(|v| v.abs() < Self::MIN_VELOCITY) {
+            self.velocity = [0.0; 2];
+        }
+
+        moved
+    }
+
+    /// Scrollbar track along `axis` (0 horizontal, 1 vertical) - inside the viewport edge
+    pub fn track(&self, axis: usize) -> Rect {
+        let v = self.viewport;
+        match axis {
+            0 => Rect::new(v.x, v.y + v.height - Self::SCROLLBAR_WIDTH, v.width, Self::SCROLLBAR_WIDTH),
+            _ => Rect::new(v.x + v.width - Self::SCROLLBAR_WIDTH, v.y, Self::SCROLLBAR_WIDTH, v.height),
+        }
+    }
+
+    /// Scrollbar thumb along `axis`, `None` when the content fits
+    pub fn thumb(&self, axis: usize) -> Option<Rect> {
+        let max = self.max_offset()[axis];
+        if !self.enabled(axis) || max <= 0.0 {
+            return None;
+        }
+
+        let track = self.track(axis);
+        let (length, content) = match axis {
+            0 => (track.width, self.content.width),
+            _ => (track.height, self.content.height),
+        };
+        let size = (length * length / content).max(Self::MIN_THUMB).min(length);
+        let start = (length - size) * self.offset[axis] / max;
+
+        Some(match axis {
+            0 => Rect::new(track.x + start, track.y, size, track.height),
+            _ => Rect::new(track.x, track.y + start, track.width, size),
+        })
+    }
+
+    /// Start dragging a scrollbar at `x`, `y` - clicking the track outside the thumb jumps there
+    pub fn press(&mut self, x: f64, y: f64) -> bool {
+        for axis in 0..2 {
+            let Some(thumb) = self.thumb(axis) else { continue };
+            if !self.track(axis).contains(x, y) {
+                continue;
+            }
+
+            let position = [x, y][axis];
+            let (start, size) = match axis {
+                0 => (thumb.x, thumb.width),
+                _ => (thumb.y, thumb.height),
+            };
+            let grab = if thumb.contains(x, y) { position - start } else { size / 2.0 };
+
+            self.velocity = [0.0; 2];
+            self.drag = Some((axis, grab));
+            self.drag_to(x, y);
+            return true;
+        }
+
+        false
+    }
+
+    /// Move a dragged thumb to follow the pointer
+    pub fn drag_to(&mut self, x: f64, y: f64) -> bool {
+        let Some((axis, grab)) = self.drag else { return false };
+        let (Some(thumb), track) = (self.thumb(axis), self.track(axis)) else { return false };
+
+        let (track_start, track_length, thumb_length) = match axis {
+            0 => (track.x, track.width, thumb.width),
+            _ => (track.y, track.height, thumb.height),
+        };
+        let travel = (track_length - thumb_length).max(1.0);
+        let fraction = ([x, y][axis] - grab - track_start) / travel;
+
+        let mut offset = self.offset;
+        offset[axis] = fraction * self.max_offset()[axis];
+        self.scroll_by(offset[0] - self.offset[0], offset[1] - self.offset[1])
+    }
+
+    pub fn release(&mut self) {
+        self.drag = None;
+    }
+
+    pub fn is_dragging(&self) -> bool {
+        self.drag.is_some()
+    }
+
+    /// Scroll the least distance that makes `rect` visible - returns whether anything moved
+    pub fn scroll_into_view(&mut self, rect: Rect) -> bool {
+        let v = self.viewport;
+        let delta = |start: f64, length: f64, view_start: f64, view_length: f64| {
+            if start < view_start || length > view_length {
+                start - view_start
+            } else if start + length > view_start + view_length {
+                start + length - (view_start + view_length)
+            } else {
+                0.0
+            }
+        };
+
+        self.scroll_by(delta(rect.x, rect.width, v.x, v.width), delta(rect.y, rect.height, v.y, v.height))
+    }
+
+    pub fn draw(&self, batch: &mut Batch) {
+        for axis in 0..2 {
+            if let Some(thumb) = self.thumb(axis) {
+                let (style, alpha) = match self.drag.is_some_and(|(a, _)| a == axis) {
+                    true => (&self.pressed_style, 0.7),
+                    false => (&self.style, 0.4),
+                };
+                let radius = style.radius.unwrap_or(Self::SCROLLBAR_WIDTH as f32 / 2.0);
+                batch.primitive(
+                    Primitive::rect(thumb.x as f32, thumb.y as f32, thumb.width as f32, thumb.height as f32, style.background_or([0.5, 0.5, 0.5, alpha]))
+                        .radius(radius),
+                );
+            }
+        }
+    }
+}
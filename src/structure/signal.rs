@@ -0,0 +1,217 @@
+use std::cell::{Cell, RefCell};
+use std::rc::Rc;
+use crate::structure::dispatch::Path;
+use crate::structure::widget::Widget;
+use crate::structure::window::{Container, Window};
+
+thread_local! {
+    /// Bumped whenever any signal changes or gains a binding, so windows can skip syncing when
+    /// nothing did
+    static CHANGES: Cell<u64> = const { Cell::new(0) };
+}
+
+fn changed() {
+    CHANGES.with(|c| c.set(c.get() + 1));
+}
+
+struct Shared<T> {
+    value: T,
+    /// Bumped on every change so bindings can tell whether they are behind
+    version: u64,
+}
+
+/// A value shared between the model and any number of widgets - cloning shares the same value
+pub struct Signal<T> {
+    shared: Rc<RefCell<Shared<T>>>,
+}
+
+impl<T> Clone for Signal<T> {
+    fn clone(&self) -> Self {
+        Self { shared: self.shared.clone() }
+    }
+}
+
+impl<T: std::fmt::Debug> std::fmt::Debug for Signal<T> {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        let shared = self.shared.borrow();
+        f.debug_struct("Signal").field("value", &shared.value).field("version", &shared.version).finish()
+    }
+}
+
+impl<T: Clone> Signal<T> {
+    pub fn new(value: T) -> Self {
+        Self { shared: Rc::new(RefCell::new(Shared { value, version: 0 })) }
+    }
+
+    pub fn get(&self) -> T {
+        self.shared.borrow().value.clone()
+    }
+
+    pub fn set(&self, value: T) {
+        let mut shared = self.shared.borrow_mut();
+        shared.value = value;
+        shared.version += 1;
+        changed();
+    }
+
+    /// Change the value in place
+    pub fn update(&self, f: impl FnOnce(&mut T)) {
+        let mut shared = self.shared.borrow_mut();
+        f(&mut shared.value);
+        shared.version += 1;
+        changed();
+    }
+
+    pub fn version(&self) -> u64 {
+        self.shared.borrow().version
+    }
+}
+
+/// A widget's view of a signal - remembers which version it last showed
+#[derive(Clone, Debug)]
+pub struct Binding<T> {
+    signal: Signal<T>,
+    seen: u64,
+}
+
+impl<T: Clone> Binding<T> {
+    /// Bind to `signal` - the first `changed` call reports its current value
+    pub fn new(signal: Signal<T>) -> Self {
+        let seen = signal.version().wrapping_sub(1);
+        changed();
+        Self { signal, seen }
+    }
+
+    /// The new value if the signal changed since the last call
+    pub fn changed(&mut self) -> Option<T> {
+        let version = self.signal.version();
+        if version == self.seen {
+            return None;
+        }
+
+        self.seen = version;
+        Some(self.signal.get())
+    }
+
+    /// Write a value from the widget back to the model without reporting it as a change
+    pub fn set(&mut self, value: T) {
+        self.signal.set(value);
+        self.seen = self.signal.version();
+    }
+
+    pub fn signal(&self) -> &Signal<T> {
+        &self.signal
+    }
+}
+
+impl Window {
+    /// Pull bound values into every widget, hidden ones included, and remember which changed -
+    /// returns true when any did. Skips the walk while no signal changed since the last call
+    pub fn sync(&mut self) -> bool {
+        let changes = CHANGES.with(Cell::get);
+        if self.synced == Some(changes) {
+            return false;
+        }
+        self.synced = Some(changes);
+
+        let mut changed = Vec::new();
+        let offset = self.components.len();
+        for (i, c) in self.components.iter_mut().enumerate() {
+            sync_widget(c, vec![i], &mut changed);
+        }
+        for (i, c) in self.containers.iter_mut().enumerate() {
+            sync_container(c, vec![offset + i], &mut changed);
+        }
+
+        let any = !changed.is_empty();
+        self.restyle_pending |= any;
+        self.dirty.extend(changed);
+        any
+    }
+
+    /// Note that the widget or container at `path` needs layout again - an empty path stands for
+    /// the whole window
+    pub fn mark_dirty(&mut self, path: Path) {
+        self.dirty.push(path);
+    }
+
+    /// Widgets changed since the last call
+    pub fn take_dirty(&mut self) -> Vec<Path> {
+        std::mem::take(&mut self.dirty)
+    }
+
+    pub fn is_dirty(&self) -> bool {
+        !self.dirty.is_empty()
+    }
+}
+
+fn sync_widget(widget: &mut dyn Widget, path: Path, changed: &mut Vec<Path>) {
+    if widget.sync() {
+        changed.push(path.clone());
+    }
+    for (i, c) in widget.children_mut().into_iter().enumerate() {
+        let mut child = path.clone();
+        child.push(i);
+        sync_widget(c, child, changed);
+    }
+}
+
+fn sync_container(container: &mut Container, path: Path, changed: &mut Vec<Path>) {
+    let offset = container.components.len();
+    for (i, c) in container.components.iter_mut().enumerate() {
+        let mut child = path.clone();
+        child.push(i);
+        sync_widget(c, child, changed);
+    }
+    for (i, c) in container.containers.iter_mut().enumerate() {
+        let mut child = path.clone();
+        child.push(offset + i);
+        sync_container(c, child, changed);
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::structure::components::{Component, Toggle};
+    use crate::structure::window::ContainerType;
+
+    #[test]
+    fn bindings_report_the_current_value_first_and_then_only_changes() {
+        let signal = Signal::new(1);
+        let mut binding = Binding::new(signal.clone());
+        assert_eq!(binding.changed(), Some(1));
+        assert_eq!(binding.changed(), None);
+
+        signal.update(|v| *v += 1);
+        assert_eq!(binding.changed(), Some(2));
+
+        binding.set(5);
+        assert_eq!(binding.changed(), None);
+        assert_eq!(signal.get(), 5);
+    }
+
+    #[test]
+    fn sync_marks_changed_widgets_dirty_and_skips_when_nothing_changed() {
+        let signal = Signal::new(true);
+        let row = Container::new(ContainerType::Row)
+            .component(Component::Toggle(Toggle::new(false)))
+            .component(Component::Toggle(Toggle::new(false).bind(signal.clone())));
+        let mut window = Window::new(100.0, 100.0).container(row);
+
+        assert!(window.sync());
+        assert_eq!(window.take_dirty(), [vec![0, 1]]);
+        assert!(!window.sync());
+
+        signal.set(false);
+        window.restyle_pending = false;
+        assert!(window.sync());
+        assert!(window.restyle_pending);
+        assert_eq!(window.take_dirty(), [vec![0, 1]]);
+
+        // The signal changed, but not to a value the toggle does not already show
+        signal.set(false);
+        assert!(!window.sync());
+        assert!(!window.is_dirty());
+    }
+}
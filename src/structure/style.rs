@@ -0,0 +1,586 @@
+use std::str::FromStr;
+use crate::core::color::Color;
+use crate::structure::dispatch::Path;
+use crate::structure::layout::Edges;
+use crate::structure::widget::Widget;
+use crate::structure::window::{Container, ContainerType, Window};
+use crate::text::FontId;
+
+/// Look of a widget or container - unset properties fall back to the widget's own values
+#[derive(Clone, Debug, Default, PartialEq)]
+pub struct Style {
+    pub background: Option<Color>,
+    /// Text and glyph color - inherited by children
+    pub foreground: Option<Color>,
+    pub border_width: Option<f32>,
+    pub border_color: Option<Color>,
+    /// Corner radius in pixels
+    pub radius: Option<f32>,
+    pub padding: Option<Edges>,
+    /// Inherited by children
+    pub font: Option<FontId>,
+    /// Pixels per em - inherited by children
+    pub font_size: Option<f32>,
+}
+
+impl Style {
+    /// Properties a child takes from its parent when no rule sets them
+    fn inherited(&self) -> Self {
+        Self {
+            foreground: self.foreground.clone(),
+            font: self.font,
+            font_size: self.font_size,
+            ..Default::default()
+        }
+    }
+
+    /// Override every property `other` sets
+    fn apply(&mut self, other: &Style) {
+        let Style { background, foreground, border_width, border_color, radius, padding, font, font_size } = other;
+        if background.is_some() { self.background = background.clone(); }
+        if foreground.is_some() { self.foreground = foreground.clone(); }
+        if border_width.is_some() { self.border_width = *border_width; }
+        if border_color.is_some() { self.border_color = border_color.clone(); }
+        if radius.is_some() { self.radius = *radius; }
+        if padding.is_some() { self.padding = *padding; }
+        if font.is_some() { self.font = *font; }
+        if font_size.is_some() { self.font_size = *font_size; }
+    }
+
+    /// Whether switching from `other` changes the size of the widget
+    pub(crate) fn resizes(&self, other: &Style) -> bool {
+        self.padding != other.padding || self.font != other.font || self.font_size != other.font_size
+    }
+
+    /// `[r, g, b, a]` of the background, or `fallback` when unset
+    pub fn background_or(&self, fallback: [f32; 4]) -> [f32; 4] {
+        self.background.as_ref().map(rgba).unwrap_or(fallback)
+    }
+
+    pub fn foreground_or(&self, fallback: [f32; 4]) -> [f32; 4] {
+        self.foreground.as_ref().map(rgba).unwrap_or(fallback)
+    }
+
+    pub fn border_or(&self, width: f32, color: [f32; 4]) -> (f32, [f32; 4]) {
+        (self.border_width.unwrap_or(width), self.border_color.as_ref().map(rgba).unwrap_or(color))
+    }
+}
+
+/// `color` as the `[r, g, b, a]` primitives and text runs take
+pub(crate) fn rgba(color: &Color) -> [f32; 4] {
+    let c = color.rgb(1.0);
+    [c.r as f32, c.g as f32, c.b as f32, c.a as f32]
+}
+
+/// Type, id and classes of a widget or container, matched by stylesheet selectors
+#[derive(Clone, Debug, Default, PartialEq)]
+pub struct Element {
+    /// Type selector name, such as `Button`
+    pub kind: &'static str,
+    pub id: Option<String>,
+    pub classes: Vec<String>,
+}
+
+impl Element {
+    pub fn new(kind: &'static str) -> Self {
+        Self { kind, id: None, classes: Vec::new() }
+    }
+
+    pub fn has_class(&self, class: &str) -> bool {
+        self.classes.iter().any(|c| c == class)
+    }
+}
+
+/// Interaction state of a widget, matched by selectors like `Button:hover`
+#[derive(Copy, Clone, Debug, PartialEq, Eq)]
+pub enum State {
+    Hover,
+    /// Held down - also written `:active`
+    Pressed,
+    /// Also written `:focused`
+    Focus,
+    Disabled,
+    /// Switched on, as for a toggle
+    Checked,
+}
+
+impl State {
+    fn parse(name: &str) -> Option<Self> {
+        Some(match name {
+            "hover" => State::Hover,
+            "pressed" | "active" => State::Pressed,
+            "focus" | "focused" => State::Focus,
+            "disabled" => State::Disabled,
+            "checked" => State::Checked,
+            _ => return None,
+        })
+    }
+}
+
+/// Every state a widget is in at once
+#[derive(Copy, Clone, Debug, Default, PartialEq, Eq)]
+pub struct States {
+    pub hover: bool,
+    pub pressed: bool,
+    pub focus: bool,
+    pub disabled: bool,
+    pub checked: bool,
+}
+
+impl States {
+    pub fn has(&self, state: State) -> bool {
+        match state {
+            State::Hover => self.hover,
+            State::Pressed => self.pressed,
+            State::Focus => self.focus,
+            State::Disabled => self.disabled,
+            State::Checked => self.checked,
+        }
+    }
+}
+
+/// Compound selector such as `Button#ok.primary:hover` - every part must match
+#[derive(Clone, Debug, Default, PartialEq)]
+pub struct Selector {
+    /// `None` for `*` or when only ids, classes or states are given
+    pub kind: Option<String>,
+    pub id: Option<String>,
+    pub classes: Vec<String>,
+    pub states: Vec<State>,
+}
+
+impl Selector {
+    pub fn matches(&self, element: &Element, states: States) -> bool {
+        self.kind.as_ref().is_none_or(|k| k == element.kind)
+            && self.id.as_ref().is_none_or(|id| element.id.as_ref() == Some(id))
+            && self.classes.iter().all(|c| element.has_class(c))
+            && self.states.iter().all(|s| states.has(*s))
+    }
+
+    /// Ids, then classes and states, then types - more specific rules win regardless of order
+    pub fn specificity(&self) -> (usize, usize, usize) {
+        (self.id.is_some() as usize, self.classes.len() + self.states.len(), self.kind.is_some() as usize)
+    }
+
+    fn parse(source: &str) -> Option<Self> {
+        let name_end = |s: &str| s.find(|c: char| !(c.is_alphanumeric() || c == '-' || c == '_')).unwrap_or(s.len());
+        let mut selector = Selector::default();
+
+        let mut rest = source.strip_prefix('*').unwrap_or(source);
+        if rest.len() == source.len() {
+            let end = name_end(rest);
+            if end > 0 {
+                selector.kind = Some(rest[..end].to_owned());
+                rest = &rest[end..];
+            }
+        }
+
+        while let Some(marker) = rest.chars().next() {
+            let start = marker.len_utf8();
+            let end = name_end(&rest[start..]) + start;
+            let name = &rest[start..end];
+            if name.is_empty() {
+                return None;
+            }
+
+            match marker {
+                '#' if selector.id.is_none() => selector.id = Some(name.to_owned()),
+                '.' => selector.classes.push(name.to_owned()),
+                ':' => selector.states.push(State::parse(name)?),
+                _ => return None,
+            }
+            rest = &rest[end..];
+        }
+
+        Some(selector)
+    }
+}
+
+/// Properties given to every element matching any of the selectors
+#[derive(Clone, Debug, PartialEq)]
+pub struct Rule {
+    pub selectors: Vec<Selector>,
+    pub style: Style,
+    pub origin: Origin,
+}
+
+/// Where a rule comes from - author rules beat theme rules whatever their specificity
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq, PartialOrd, Ord)]
+pub enum Origin {
+    Theme,
+    #[default]
+    Author,
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum StyleError {
+    /// A rule without its closing brace
+    Unclosed { line: usize },
+    /// Text outside of a rule, or a selector that cannot be parsed
+    Selector { line: usize, selector: String },
+    Declaration { line: usize, declaration: String },
+    UnknownProperty { line: usize, property: String },
+    InvalidValue { line: usize, property: String, value: String },
+}
+
+impl std::fmt::Display for StyleError {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            StyleError::Unclosed { line } => write!(f, "line {line}: rule is never closed"),
+            StyleError::Selector { line, selector } => write!(f, "line {line}: invalid selector `{selector}`"),
+            StyleError::Declaration { line, declaration } => write!(f, "line {line}: expected `property: value`, found `{declaration}`"),
+            StyleError::UnknownProperty { line, property } => write!(f, "line {line}: unknown property `{property}`"),
+            StyleError::InvalidValue { line, property, value } => write!(f, "line {line}: invalid value `{value}` for `{property}`"),
+        }
+    }
+}
+
+impl std::error::Error for StyleError {}
+
+/// Rules written in a CSS-like syntax, applied to a whole window
+///
+/// ```ignore
+/// /* Compound selectors with types, ids, classes and states */
+/// Window { background: #1e1e24; foreground: white; font-size: 14; }
+/// Button { background: #5b3cc4; radius: 4; padding: 4 12; }
+/// Button.danger:hover { background: red; border: 1 white; }
+/// #title { font: 1; font-size: 24; }
+/// ```
+///
+/// Matching theme rules are applied before author rules, each from least to most specific and in
+/// source order when equally specific. Foreground and font properties are inherited from the
+/// parent container
+#[derive(Clone, Debug, Default, PartialEq)]
+pub struct Stylesheet {
+    pub rules: Vec<Rule>,
+}
+
+impl Stylesheet {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    pub fn parse(source: &str) -> Result<Self, StyleError> {
+        let source = strip_comments(source);
+        let line_at = |offset: usize| source[..offset].matches('\n').count() + 1;
+        let mut rules = Vec::new();
+        let mut position = 0;
+
+        while let Some(open) = source[position..].find('{').map(|o| position + o) {
+            let close = source[open..].find('}').map(|c| open + c).ok_or(StyleError::Unclosed { line: line_at(open) })?;
+            let head = &source[position..open];
+            let head_line = line_at(position + (head.len() - head.trim_start().len()));
+
+            let selectors = head.split(',')
+                .map(|s| Selector::parse(s.trim()).filter(|_| !s.trim().is_empty()))
+                .collect::<Option<Vec<_>>>()
+                .ok_or_else(|| StyleError::Selector { line: head_line, selector: head.trim().to_owned() })?;
+
+            let mut style = Style::default();
+            let mut offset = open + 1;
+            for declaration in source[open + 1..close].split(';') {
+                let line = line_at(offset + (declaration.len() - declaration.trim_start().len()));
+                offset += declaration.len() + 1;
+                if declaration.trim().is_empty() {
+                    continue;
+                }
+
+                let (property, value) = declaration.split_once(':')
+                    .ok_or_else(|| StyleError::Declaration { line, declaration: declaration.trim().to_owned() })?;
+                declare(&mut style, property.trim(), value.trim(), line)?;
+            }
+
+            rules.push(Rule { selectors, style, origin: Origin::Author });
+            position = close + 1;
+        }
+
+        match source[position..].trim() {
+            "" => Ok(Self { rules }),
+            rest => Err(StyleError::Selector { line: line_at(source.len() - source[position..].trim_start().len()), selector: rest.to_owned() }),
+        }
+    }
+
+    /// Append `rule`, taking precedence over equally specific rules already in the sheet
+    pub fn rule(mut self, selector: &str, style: Style) -> Result<Self, StyleError> {
+        let selectors = selector.split(',')
+            .map(|s| Selector::parse(s.trim()))
+            .collect::<Option<Vec<_>>>()
+            .ok_or_else(|| StyleError::Selector { line: 1, selector: selector.to_owned() })?;
+        self.rules.push(Rule { selectors, style, origin: Origin::Author });
+        Ok(self)
+    }
+
+    /// Move every rule to `origin`, such as a theme beneath the author's rules
+    pub fn origin(mut self, origin: Origin) -> Self {
+        for rule in &mut self.rules {
+            rule.origin = origin;
+        }
+        self
+    }
+
+    /// Style of `element` in `states`, inside a parent styled `parent`
+    pub fn resolve(&self, element: &Element, states: States, parent: &Style) -> Style {
+        let mut matched: Vec<_> = self.rules.iter()
+            .enumerate()
+            .filter_map(|(i, rule)| {
+                rule.selectors.iter()
+                    .filter(|s| s.matches(element, states))
+                    .map(|s| s.specificity())
+                    .max()
+                    .map(|specificity| (rule.origin, specificity, i, &rule.style))
+            })
+            .collect();
+        matched.sort_by_key(|(origin, specificity, i, _)| (*origin, *specificity, *i));
+
+        let mut style = parent.inherited();
+        for (_, _, _, rule) in matched {
+            style.apply(rule);
+        }
+        style
+    }
+}
+
+impl FromStr for Stylesheet {
+    type Err = StyleError;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        Self::parse(s)
+    }
+}
+
+/// Blank out comments, keeping line breaks so errors point at the right line
+fn strip_comments(source: &str) -> String {
+    let mut out = String::with_capacity(source.len());
+    let mut rest = source;
+    while let Some(start) = rest.find("/*") {
+        out.push_str(&rest[..start]);
+        let end = rest[start + 2..].find("*/").map(|e| start + 2 + e + 2).unwrap_or(rest.len());
+        out.extend(rest[start..end].chars().map(|c| if c == '\n' { '\n' } else { ' ' }));
+        rest = &rest[end..];
+    }
+    out.push_str(rest);
+    out
+}
+
+fn declare(style: &mut Style, property: &str, value: &str, line: usize) -> Result<(), StyleError> {
+    let invalid = || StyleError::InvalidValue { line, property: property.to_owned(), value: value.to_owned() };
+
+    match property {
+        "background" => style.background = Some(parse_color(value).ok_or_else(invalid)?),
+        "foreground" | "color" => style.foreground = Some(parse_color(value).ok_or_else(invalid)?),
+        "border-width" => style.border_width = Some(parse_length(value).ok_or_else(invalid)?),
+        "border-color" => style.border_color = Some(parse_color(value).ok_or_else(invalid)?),
+        // Width followed by a color
+        "border" => {
+            let (width, color) = value.split_once(char::is_whitespace).ok_or_else(invalid)?;
+            style.border_width = Some(parse_length(width).ok_or_else(invalid)?);
+            style.border_color = Some(parse_color(color.trim()).ok_or_else(invalid)?);
+        },
+        "radius" | "border-radius" => style.radius = Some(parse_length(value).ok_or_else(invalid)?),
+        // One to four lengths, clockwise from the top as in CSS
+        "padding" => {
+            let lengths = value.split_whitespace().map(parse_length).collect::<Option<Vec<_>>>().ok_or_else(invalid)?;
+            let [top, right, bottom, left] = match lengths[..] {
+                [all] => [all; 4],
+                [vertical, horizontal] => [vertical, horizontal, vertical, horizontal],
+                [top, horizontal, bottom] => [top, horizontal, bottom, horizontal],
+                [top, right, bottom, left] => [top, right, bottom, left],
+                _ => return Err(invalid()),
+            }.map(|l| l as f64);
+            style.padding = Some(Edges { top, right, bottom, left });
+        },
+        "font" => style.font = Some(FontId(value.parse().map_err(|_| invalid())?)),
+        "font-size" => style.font_size = Some(parse_length(value).ok_or_else(invalid)?),
+        _ => return Err(StyleError::UnknownProperty { line, property: property.to_owned() }),
+    }
+
+    Ok(())
+}
+
+/// Pixels, with or without a `px` suffix
+fn parse_length(value: &str) -> Option<f32> {
+    value.trim().trim_end_matches("px").parse().ok().filter(|v: &f32| v.is_finite())
+}
+
+/// Any CSS color `Color::parse` accepts
+fn parse_color(value: &str) -> Option<Color> {
+    Color::parse(value).ok()
+}
+
+impl Window {
+    pub fn stylesheet(mut self, stylesheet: Stylesheet) -> Self {
+        self.stylesheet = stylesheet;
+        self.cascade();
+        self
+    }
+
+    /// Swap the stylesheet, restyling every widget on the next `restyle`
+    pub fn set_stylesheet(&mut self, stylesheet: Stylesheet) {
+        self.stylesheet = stylesheet;
+        self.cascade();
+    }
+
+    /// Style resolved for the window itself - its background is the clear color
+    pub fn style(&self) -> &Style {
+        &self.style
+    }
+
+    /// Resolve the style of every widget and container for its current state, marking those
+    /// whose size changed dirty - returns true when any did
+    pub fn restyle(&mut self) -> bool {
+        let Window { cascade: stylesheet, style, components, containers, dirty, restyle_pending, .. } = self;
+        *restyle_pending = false;
+        *style = stylesheet.resolve(&Element::new("Window"), States::default(), &Style::default());
+
+        let mut resized = Vec::new();
+        let offset = components.len();
+        for (i, c) in components.iter_mut().enumerate() {
+            restyle_widget(stylesheet, c, style, vec![i], &mut resized);
+        }
+        for (i, c) in containers.iter_mut().enumerate() {
+            restyle_container(stylesheet, c, style, vec![offset + i], &mut resized);
+        }
+
+        let any = !resized.is_empty();
+        dirty.extend(resized);
+        any
+    }
+}
+
+fn restyle_widget(sheet: &Stylesheet, widget: &mut dyn Widget, parent: &Style, path: Path, resized: &mut Vec<Path>) {
+    let style = match widget.element() {
+        Some(element) => {
+            let style = sheet.resolve(element, widget.states(), parent);
+            if widget.set_style(style.clone()) {
+                resized.push(path.clone());
+            }
+            style
+        },
+        None => parent.clone(),
+    };
+
+    for (i, c) in widget.children_mut().into_iter().enumerate() {
+        let mut child = path.clone();
+        child.push(i);
+        restyle_widget(sheet, c, &style, child, resized);
+    }
+}
+
+fn restyle_container(sheet: &Stylesheet, container: &mut Container, parent: &Style, path: Path, resized: &mut Vec<Path>) {
+    let style = sheet.resolve(&container.element, States::default(), parent);
+    if style.resizes(&container.style) {
+        resized.push(path.clone());
+    }
+    container.style = style;
+
+    // Scrollbars and tab strips are drawn by their container, but styled like widgets inside it
+    match &mut container.layout {
+        ContainerType::Scrollable(scroll) => {
+            let pressed = States { pressed: true, ..States::default() };
+            scroll.style = sheet.resolve(&scroll.element, States::default(), &container.style);
+            scroll.pressed_style = sheet.resolve(&scroll.element, pressed, &container.style);
+        },
+        ContainerType::Tab(tabs) => {
+            let (tab, checked) = (Element::new("Tab"), States { checked: true, ..States::default() });
+            tabs.style = sheet.resolve(&tabs.element, States::default(), &container.style);
+            tabs.tab_style = sheet.resolve(&tab, States::default(), &tabs.style);
+            tabs.active_style = sheet.resolve(&tab, checked, &tabs.style);
+        },
+        _ => {},
+    }
+
+    let offset = container.components.len();
+    for (i, c) in container.components.iter_mut().enumerate() {
+        let mut child = path.clone();
+        child.push(i);
+        restyle_widget(sheet, c, &container.style, child, resized);
+    }
+    for (i, c) in container.containers.iter_mut().enumerate() {
+        let mut child = path.clone();
+        child.push(offset + i);
+        restyle_container(sheet, c, &container.style, child, resized);
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn button(classes: &[&str]) -> Element {
+        Element { classes: classes.iter().map(|c| c.to_string()).collect(), ..Element::new("Button") }
+    }
+
+    fn background(sheet: &Stylesheet, element: &Element) -> Option<Color> {
+        sheet.resolve(element, States::default(), &Style::default()).background
+    }
+
+    #[test]
+    fn more_specific_rules_win_and_ties_go_to_the_later_rule() {
+        let sheet = Stylesheet::parse("
+            Button.primary { background: red; }
+            Button { background: blue; }
+            Button { background: lime; }
+        ").unwrap();
+
+        assert_eq!(background(&sheet, &button(&[])), Some(Color::RGB(0, 255, 0)));
+        assert_eq!(background(&sheet, &button(&["primary"])), Some(Color::RGB(255, 0, 0)));
+    }
+
+    #[test]
+    fn author_rules_beat_more_specific_theme_rules() {
+        let mut sheet = Stylesheet::parse("Button.primary:hover { background: red; }").unwrap().origin(Origin::Theme);
+        sheet.rules.extend(Stylesheet::parse("Button { background: blue; }").unwrap().rules);
+
+        let hovered = States { hover: true, ..States::default() };
+        let style = sheet.resolve(&button(&["primary"]), hovered, &Style::default());
+        assert_eq!(style.background, Some(Color::RGB(0, 0, 255)));
+    }
+
+    #[test]
+    fn foreground_is_inherited_but_background_is_not() {
+        let sheet = Stylesheet::parse("Window { foreground: white; background: black; }").unwrap();
+        let window = sheet.resolve(&Element::new("Window"), States::default(), &Style::default());
+        let label = sheet.resolve(&Element::new("Label"), States::default(), &window);
+
+        assert_eq!(label.foreground, Some(Color::RGB(255, 255, 255)));
+        assert_eq!(label.background, None);
+    }
+
+    #[test]
+    fn scrollbars_and_tab_strips_are_styled_with_their_container() {
+        use crate::structure::scroll::Scroll;
+        use crate::structure::tabs::Tabs;
+
+        let sheet = Stylesheet::parse("
+            Window { foreground: white; }
+            Scrollbar { background: red; }
+            Scrollbar:pressed { background: blue; }
+            TabStrip { background: black; }
+            Tab:checked { background: lime; }
+        ").unwrap();
+        let mut window = Window::new(100.0, 100.0)
+            .stylesheet(sheet)
+            .container(Container::new(ContainerType::Scrollable(Scroll::default())))
+            .container(Container::new(ContainerType::Tab(Tabs::new().tab("A"))));
+        window.restyle();
+
+        let ContainerType::Scrollable(scroll) = &window.containers[0].layout else { unreachable!() };
+        assert_eq!(scroll.style.background, Some(Color::RGB(255, 0, 0)));
+        assert_eq!(scroll.pressed_style.background, Some(Color::RGB(0, 0, 255)));
+
+        let ContainerType::Tab(tabs) = &window.containers[1].layout else { unreachable!() };
+        assert_eq!(tabs.style.background, Some(Color::RGB(0, 0, 0)));
+        assert_eq!(tabs.tab_style.background, None);
+        assert_eq!(tabs.active_style.background, Some(Color::RGB(0, 255, 0)));
+        assert_eq!(tabs.tab_style.foreground, Some(Color::RGB(255, 255, 255)));
+    }
+
+    #[test]
+    fn errors_point_at_their_line() {
+        assert_eq!(Stylesheet::parse("Button {\n  background red;\n}"), Err(StyleError::Declaration {
+            line: 2,
+            declaration: "background red".to_owned(),
+        }));
+        assert_eq!(Stylesheet::parse("\nButton { radius: 2;"), Err(StyleError::Unclosed { line: 2 }));
+    }
+}
@@ -0,0 +1,462 @@
+use crate::core::primitive::Primitive;
+use crate::core::renderer::Batch;
+use crate::structure::layout::{Rect, Size};
+use crate::structure::style::{Element, Style};
+use crate::text::{FontId, Fonts, TextRun};
+
+/// A title in the tab strip and the child of the container it shows
+#[derive(Clone, Debug, PartialEq)]
+pub struct Tab {
+    pub title: String,
+    pub closable: bool,
+    /// Index of the page in the container's children, in layout order
+    pub page: usize,
+}
+
+/// Something the user did to the tab strip - positions are indices into the strip
+#[derive(Copy, Clone, Debug, PartialEq, Eq)]
+pub enum TabEvent {
+    Selected(usize),
+    /// The tab was removed along with its page
+    Closed(usize),
+    Moved { from: usize, to: usize },
+}
+
+/// Called with everything the user does to the tab strip, as it happens
+pub type TabHandler = Box<dyn FnMut(TabEvent)>;
+
+/// Tab strip of a tab container - only the active page is laid out and drawn
+pub struct Tabs {
+    pub tabs: Vec<Tab>,
+    /// Position of the selected tab in the strip
+    pub active: usize,
+    pub font: FontId,
+    /// Pixels per em of the titles
+    pub size: f32,
+    /// Strip area - resolved by layout
+    pub strip: Rect,
+    /// Matched by `TabStrip` selectors - each tab is matched as a `Tab`, the selected one
+    /// `Tab:checked`
+    pub element: Element,
+    /// Resolved from the window's stylesheet
+    pub(crate) style: Style,
+    pub(crate) tab_style: Style,
+    pub(crate) active_style: Style,
+    /// Tab widths, resolved by layout
+    widths: Vec<f64>,
+    /// How far the strip is scrolled when the tabs do not fit
+    scroll: f64,
+    /// Tab being dragged and the pointer's distance from its left edge
+    drag: Option<(usize, f64)>,
+    on_event: Vec<TabHandler>,
+}
+
+impl Default for Tabs {
+    fn default() -> Self {
+        Self {
+            tabs: Vec::new(),
+            active: 0,
+            font: FontId(0),
+            size: 14.0,
+            strip: Rect::default(),
+            element: Element::new("TabStrip"),
+            style: Style::default(),
+            tab_style: Style::default(),
+            active_style: Style::default(),
+            widths: Vec::new(),
+            scroll: 0.0,
+            drag: None,
+            on_event: Vec::new(),
+        }
+    }
+}
+
+impl Tabs {
+    /// Space between a title and the edges of its tab
+    const PADDING: f64 = 12.0;
+
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Add a tab for the next child of the container
+    pub fn tab(mut self, title: &str) -> Self {
+        let page = self.tabs.len();
+        self.tabs.push(Tab { title: title.to_owned(), closable: false, page });
+        self
+    }
+
+    /// Add a tab with a close button for the next child of the container
+    pub fn closable(mut self, title: &str) -> Self {
+        let page = self.tabs.len();
+        self.tabs.push(Tab { title: title.to_owned(), closable: true, page });
+        self
+    }
+
+    pub fn font(mut self, font: FontId, size: f32) -> Self {
+        self.font = font;
+        self.size = size;
+        self
+    }
+
+    /// Child index of the page currently shown
+    pub fn page(&self) -> Option<usize> {
+        self.tabs.get(self.active).map(|t| t.page)
+    }
+
+    /// Run `handler` every time a tab is selected, closed or moved
+    pub fn on_event(mut self, handler: impl FnMut(TabEvent) + 'static) -> Self {
+        self.on_event.push(Box::new(handler));
+        self
+    }
+
+    fn emit(&mut self, event: TabEvent) {
+        for handler in &mut self.on_event {
+            handler(event);
+        }
+    }
+
+    pub fn height(&self) -> f64 {
+        (self.size * 2.0) as f64
+    }
+
+    pub fn select(&mut self, index: usize) {
+        if index >= self.tabs.len() || index == self.active {
+            return;
+        }
+
+        self.active = index;
+        self.emit(TabEvent::Selected(index));
+        self.reveal(index);
+    }
+
+    /// Select the next tab, wrapping around - Ctrl+Tab
+    pub fn next(&mut self) {
+        if !self.tabs.is_empty() {
+            self.select((self.active + 1) % self.tabs.len());
+        }
+    }
+
+    /// Select the previous tab, wrapping around - Ctrl+Shift+Tab
+    pub fn previous(&mut self) {
+        if !self.tabs.is_empty() {
+            self.select((self.active + self.tabs.len() - 1) % self.tabs.len());
+        }
+    }
+
+    /// Remove the tab at `index`, returning the child index of its page so the container can
+    /// drop it. Closing the selected tab selects its neighbour
+    pub(crate) fn close(&mut self, index: usize) -> Option<usize> {
+        if index >= self.tabs.len() {
+            return None;
+        }
+
+        let page = self.tabs.remove(index).page;
+        if index < self.widths.len() {
+            self.widths.remove(index);
+        }
+        for tab in self.tabs.iter_mut().filter(|t| t.page > page) {
+            tab.page -= 1;
+        }
+        let was_active = self.active == index;
+        if self.active > index || (was_active && self.active == self.tabs.len() && index > 0) {
+            self.active -= 1;
+        }
+
+        self.drag = None;
+        self.emit(TabEvent::Closed(index));
+        if was_active && !self.tabs.is_empty() {
+            self.emit(TabEvent::Selected(self.active));
+        }
+        Some(page)
+    }
+
+    /// Move the tab at `from` to `to`, keeping the same tab selected
+    pub fn reorder(&mut self, from: usize, to: usize) {
+        if from == to || from >= self.tabs.len() || to >= self.tabs.len() {
+            return;
+        }
+
+        let tab = self.tabs.remove(from);
+        self.tabs.insert(to, tab);
+        if from < self.widths.len() && to < self.widths.len() {
+            let width = self.widths.remove(from);
+            self.widths.insert(to, width);
+        }
+
+        self.active = match self.active {
+            a if a == from => to,
+            a if from < a && a <= to => a - 1,
+            a if to <= a && a < from => a + 1,
+            a => a,
+        };
+        self.emit(TabEvent::Moved { from, to });
+    }
+
+    fn title_width(&self, fonts: &Fonts, title: &str) -> f64 {
+        fonts.measure_text(self.font, title, self.size)
+            .map(|m| m.width as f64)
+            // Rough guess so tabs stay usable before a font is loaded
+            .unwrap_or(title.chars().count() as f64 * self.size as f64 * 0.6)
+    }
+
+    /// Preferred size of the strip
+    pub fn measure(&self, fonts: &Fonts) -> Size {
+        let width = self.tabs.iter().map(|t| self.tab_width(fonts, t)).sum();
+        Size::new(width, self.height())
+    }
+
+    fn tab_width(&self, fonts: &Fonts, tab: &Tab) -> f64 {
+        let close = if tab.closable { self.size as f64 } else { 0.0 };
+        self.title_width(fonts, &tab.title) + Self::PADDING * 2.0 + close
+    }
+
+    /// Place the strip at the top of `content`, returning the area left for the active page
+    pub fn layout(&mut self, content: Rect, fonts: &Fonts) -> Rect {
+        self.active = self.active.min(self.tabs.len().saturating_sub(1));
+        self.widths = self.tabs.iter().map(|t| self.tab_width(fonts, t)).collect();
+
+        let height = self.height().min(content.height);
+        self.strip = Rect::new(content.x, content.y, content.width, height);
+        self.scroll_by(0.0);
+
+        Rect::new(content.x, content.y + height, content.width, content.height - height)
+    }
+
+    /// Scroll the strip horizontally - returns whether anything moved
+    pub fn scroll_by(&mut self, dx: f64) -> bool {
+        let total: f64 = self.widths.iter().sum();
+        let before = self.scroll;
+        self.scroll = (self.scroll + dx).clamp(0.0, (total - self.strip.width).max(0.0));
+        self.scroll != before
+    }
+
+    /// Scroll the strip so the tab at `index` is fully visible
+    fn reveal(&mut self, index: usize) {
+        let Some(rect) = self.tab_rect(index) else { return };
+        let strip = self.strip;
+        if rect.x < strip.x {
+            self.scroll_by(rect.x - strip.x);
+        } else if rect.x + rect.width > strip.x + strip.width {
+            self.scroll_by(rect.x + rect.width - (strip.x + strip.width));
+        }
+    }
+
+    /// Area of the tab at `index`, including the strip scroll
+    pub fn tab_rect(&self, index: usize) -> Option<Rect> {
+        let width = *self.widths.get(index)?;
+        let x = self.strip.x - self.scroll + self.widths[..index].iter().sum::<f64>();
+        Some(Rect::new(x, self.strip.y, width, self.strip.height))
+    }
+
+    /// Close button of the tab at `index`, if it has one
+    pub fn close_rect(&self, index: usize) -> Option<Rect> {
+        let tab = self.tab_rect(index)?;
+        if !self.tabs[index].closable {
+            return None;
+        }
+
+        let size = self.size as f64;
+        Some(Rect::new(tab.x + tab.width - Self::PADDING / 2.0 - size, tab.y + (tab.height - size) / 2.0, size, size))
+    }
+
+    fn hit(&self, x: f64, y: f64) -> Option<usize> {
+        if !self.strip.contains(x, y) {
+            return None;
+        }
+        (0..self.tabs.len()).find(|&i| self.tab_rect(i).is_some_and(|r| r.contains(x, y)))
+    }
+
+    /// Click at `x`, `y` - selects a tab and starts dragging it, or returns the position of the
+    /// tab whose close button was hit
+    pub(crate) fn press(&mut self, x: f64, y: f64) -> TabPress {
+        let Some(index) = self.hit(x, y) else { return TabPress::Missed };
+
+        if self.close_rect(index).is_some_and(|r| r.contains(x, y)) {
+            return TabPress::Close(index);
+        }
+
+        self.select(index);
+        let left = self.tab_rect(index).map(|r| r.x).unwrap_or(x);
+        self.drag = Some((index, x - left));
+        TabPress::Selected
+    }
+
+    /// Follow the pointer while dragging a tab, swapping it past the middle of its neighbours
+    pub fn drag_to(&mut self, x: f64) -> bool {
+        let Some((index, grab)) = self.drag else { return false };
+        let left = x - grab;
+
+        let target = match self.tab_rect(index) {
+            Some(rect) if left < rect.x => (0..index).rev()
+                .take_while(|&i| self.tab_rect(i).is_some_and(|r| left < r.x + r.width / 2.0))
+                .last(),
+            Some(rect) if left > rect.x => (index + 1..self.tabs.len())
+                .take_while(|&i| self.tab_rect(i).is_some_and(|r| left + rect.width > r.x + r.width / 2.0))
+                .last(),
+            _ => None,
+        };
+
+        match target {
+            Some(to) => {
+                self.reorder(index, to);
+                self.drag = Some((to, grab));
+                true
+            },
+            None => false,
+        }
+    }
+
+    pub fn release(&mut self) {
+        self.drag = None;
+    }
+
+    pub fn is_dragging(&self) -> bool {
+        self.drag.is_some()
+    }
+
+    pub fn draw(&self, batch: &mut Batch) {
+        let s = self.strip;
+        let background = self.style.background_or([0.12, 0.12, 0.14, 1.0]);
+        batch.primitive(Primitive::rect(s.x as f32, s.y as f32, s.width as f32, s.height as f32, background));
+        batch.push_clip([s.x as f32, s.y as f32, s.width as f32, s.height as f32]);
+
+        for (i, tab) in self.tabs.iter().enumerate() {
+            let Some(r) = self.tab_rect(i) else { continue };
+            let (style, fallback) = match i == self.active {
+                true => (&self.active_style, [0.24, 0.24, 0.28, 1.0]),
+                false => (&self.tab_style, [0.16, 0.16, 0.19, 1.0]),
+            };
+            let radius = style.radius.unwrap_or(4.0);
+            batch.primitive(
+                Primitive::rect(r.x as f32 + 1.0, r.y as f32 + 2.0, r.width as f32 - 2.0, r.height as f32 - 2.0, style.background_or(fallback))
+                    .radii([radius, radius, 0.0, 0.0]),
+            );
+
+            let color = style.foreground_or([0.9, 0.9, 0.9, 1.0]);
+
+            let text_y = r.y + (r.height - self.size as f64 * 1.2) / 2.0;
+            batch.text(TextRun {
+                font: self.font,
+                size: self.size,
+                position: [(r.x + Self::PADDING) as f32, text_y as f32],
+                color,
+                text: tab.title.clone(),
+            });
+
+            if let Some(c) = self.close_rect(i) {
+                batch.text(TextRun {
+                    font: self.font,
+                    size: self.size,
+                    position: [(c.x + c.width * 0.2) as f32, text_y as f32],
+                    // Dimmer than the title
+                    color: [color[0], color[1], color[2], color[3] * 0.75],
+                    text: "×".to_owned(),
+                });
+            }
+        }
+
+        batch.pop_clip();
+    }
+}
+
+/// Outcome of a click on the tab strip
+pub(crate) enum TabPress {
+    Missed,
+    Selected,
+    Close(usize),
+}
+
+#[cfg(test)]
+mod tests {
+    use std::cell::RefCell;
+    use std::rc::Rc;
+    use super::*;
+    use crate::core::color::Color;
+    use crate::structure::components::{Button, Component};
+    use crate::structure::window::{Container, ContainerType, Window};
+
+    fn recorded(tabs: Tabs) -> (Tabs, Rc<RefCell<Vec<TabEvent>>>) {
+        let events = Rc::new(RefCell::new(Vec::new()));
+        let sink = events.clone();
+        (tabs.on_event(move |e| sink.borrow_mut().push(e)), events)
+    }
+
+    fn button(label: &str) -> Component {
+        Component::Button(Button::with_size(label, Color::Blue, 80.0, 30.0))
+    }
+
+    #[test]
+    fn closing_the_active_tab_selects_its_neighbour() {
+        let (mut tabs, events) = recorded(Tabs::new().closable("A").closable("B").closable("C"));
+        tabs.select(1);
+
+        assert_eq!(tabs.close(1), Some(1));
+        assert_eq!(tabs.active, 1);
+        assert_eq!(tabs.page(), Some(1));
+        assert_eq!(*events.borrow(), [TabEvent::Selected(1), TabEvent::Closed(1), TabEvent::Selected(1)]);
+    }
+
+    #[test]
+    fn closing_the_last_active_tab_moves_left() {
+        let (mut tabs, events) = recorded(Tabs::new().closable("A").closable("B"));
+        tabs.select(1);
+        events.borrow_mut().clear();
+
+        tabs.close(1);
+        assert_eq!(tabs.active, 0);
+        assert_eq!(*events.borrow(), [TabEvent::Closed(1), TabEvent::Selected(0)]);
+    }
+
+    #[test]
+    fn closing_another_tab_keeps_the_selection() {
+        let (mut tabs, events) = recorded(Tabs::new().closable("A").closable("B").closable("C"));
+        tabs.select(2);
+        events.borrow_mut().clear();
+
+        tabs.close(0);
+        assert_eq!(tabs.active, 1);
+        assert_eq!(tabs.tabs[tabs.active].title, "C");
+        assert_eq!(*events.borrow(), [TabEvent::Closed(0)]);
+    }
+
+    #[test]
+    fn reorder_keeps_the_selected_tab() {
+        let mut tabs = Tabs::new().tab("A").tab("B").tab("C");
+        tabs.select(0);
+        tabs.reorder(0, 2);
+
+        assert_eq!(tabs.active, 2);
+        assert_eq!(tabs.tabs.iter().map(|t| t.title.as_str()).collect::<Vec<_>>(), ["B", "C", "A"]);
+        assert_eq!(tabs.page(), Some(0));
+    }
+
+    #[test]
+    fn next_and_previous_wrap_around() {
+        let mut tabs = Tabs::new().tab("A").tab("B").tab("C");
+        tabs.previous();
+        assert_eq!(tabs.active, 2);
+        tabs.next();
+        assert_eq!(tabs.active, 0);
+    }
+
+    #[test]
+    fn cycle_tabs_follows_the_path() {
+        let inner = Container::new(ContainerType::Tab(Tabs::new().tab("A").tab("B")))
+            .component(button("a"))
+            .component(button("b"));
+        let outer = Container::new(ContainerType::Tab(Tabs::new().tab("Inner").tab("Other")))
+            .container(inner)
+            .component(button("other"));
+        let mut window = Window::new(400.0, 300.0).component(button("top")).container(outer);
+
+        // Components come first in layout order, so the inner container is the outer one's child 1
+        assert_eq!(window.cycle_tabs(&[1, 1, 0], true), Some(vec![1, 1]));
+        let ContainerType::Tab(tabs) = &window.containers[0].containers[0].layout else { unreachable!() };
+        assert_eq!(tabs.active, 1);
+
+        // Outside any tab container nothing cycles
+        assert_eq!(window.cycle_tabs(&[0], true), None);
+        assert_eq!(window.first_tabs(), Some(vec![1]));
+    }
+}
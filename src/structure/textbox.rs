@@ -0,0 +1,657 @@
+use unicode_segmentation::UnicodeSegmentation;
+use winit::event::{Ime, KeyEvent, MouseButton};
+use winit::keyboard::{Key, ModifiersState, NamedKey};
+use crate::core::color;
+use crate::core::primitive::Primitive;
+use crate::core::renderer::Batch;
+use crate::structure::event::{Event, EventContext, Phase};
+use crate::structure::layout::{Edges, Rect, Size};
+use crate::structure::style::{self, Element, States, Style};
+use crate::structure::widget::Widget;
+use crate::text::{default_clipboard, Editor, FontId, Fonts, Movement, SharedClipboard, TextRun};
+
+/// A displayed line with the position of every caret stop on it
+#[derive(Clone, Debug, Default)]
+struct CaretLine {
+    /// Offsets into the real text paired with x relative to the start of the text
+    stops: Vec<(usize, f64)>,
+}
+
+/// Called with the text when Enter is pressed in a single line text box
+pub type SubmitHandler = Box<dyn FnMut(&str)>;
+
+/// Single or multi-line text editor
+pub struct TextBox {
+    pub editor: Editor,
+    pub font: FontId,
+    /// Pixels per em
+    pub size: f32,
+    pub color: color::Color,
+    /// Show every character as a dot and refuse to copy
+    pub password: bool,
+    pub clipboard: SharedClipboard,
+    pub x: f64,
+    pub y: f64,
+    pub width: f64,
+    pub height: f64,
+    pub focused: bool,
+    pub tab_index: i32,
+    pub element: Element,
+    /// Resolved by layout
+    lines: Vec<CaretLine>,
+    line_height: f64,
+    /// How far single line text is scrolled to keep the caret visible
+    scroll: f64,
+    dragging: bool,
+    /// Text being composed by an input method and the byte range of its cursor within it
+    preedit: Option<(String, Option<(usize, usize)>)>,
+    /// Line, start and end x of the composed text, and the x of its cursor - resolved by layout
+    preedit_span: Option<(usize, f64, f64, Option<f64>)>,
+    on_submit: Vec<SubmitHandler>,
+    /// Resolved from the window's stylesheet
+    style: Style,
+}
+
+impl TextBox {
+    const PADDING: f64 = 4.0;
+    const MASK: &'static str = "\u{2022}";
+
+    pub fn new(text: &str, font: FontId, size: f32) -> Self {
+        Self {
+            editor: Editor::new(text, false),
+            font,
+            size,
+            color: color::Color::White,
+            password: false,
+            clipboard: default_clipboard(),
+            x: 0.0,
+            y: 0.0,
+            width: 0.0,
+            height: 0.0,
+            focused: false,
+            tab_index: 0,
+            element: Element::new("TextBox"),
+            lines: Vec::new(),
+            line_height: size as f64 * 1.2,
+            scroll: 0.0,
+            dragging: false,
+            preedit: None,
+            preedit_span: None,
+            on_submit: Vec::new(),
+            style: Style::default(),
+        }
+    }
+
+    pub fn multiline(text: &str, font: FontId, size: f32) -> Self {
+        Self { editor: Editor::new(text, true), ..Self::new("", font, size) }
+    }
+
+    pub fn password(mut self, password: bool) -> Self {
+        self.password = password;
+        self
+    }
+
+    /// Copy and paste through another clipboard than the default one, such as a `MemoryClipboard`
+    /// in tests
+    pub fn clipboard(mut self, clipboard: SharedClipboard) -> Self {
+        self.clipboard = clipboard;
+        self
+    }
+
+    pub fn id(mut self, id: &str) -> Self {
+        self.element.id = Some(id.to_owned());
+        self
+    }
+
+    pub fn class(mut self, class: &str) -> Self {
+        self.element.classes.push(class.to_owned());
+        self
+    }
+
+    /// Run `handler` with the text every time Enter is pressed in a single line text box
+    pub fn on_submit(mut self, handler: impl FnMut(&str) + 'static) -> Self {
+        self.on_submit.push(Box::new(handler));
+        self
+    }
+
+    pub fn text(&self) -> &str {
+        self.editor.text()
+    }
+
+    /// Fire the submit handlers - returns false when there are none, leaving Enter to others
+    fn submit(&mut self) -> bool {
+        for handler in &mut self.on_submit {
+            handler(self.editor.text());
+        }
+        !self.on_submit.is_empty()
+    }
+
+    /// The text as shown, with the real offset of every grapheme boundary paired with its
+    /// offset in the shown text. Composed text sits at the caret without any real offsets
+    fn display(&self) -> (String, Vec<(usize, usize)>) {
+        let text = self.editor.text();
+        let caret = self.editor.caret();
+        let mut shown = String::new();
+        let mut boundaries = Vec::new();
+
+        let compose = |shown: &mut String, at: usize| {
+            if let Some((preedit, _)) = self.preedit.as_ref().filter(|_| at == caret) {
+                shown.push_str(preedit);
+            }
+        };
+
+        for (i, grapheme) in text.grapheme_indices(true) {
+            boundaries.push((i, shown.len()));
+            compose(&mut shown, i);
+            shown.push_str(if self.password && grapheme != "\n" { Self::MASK } else { grapheme });
+        }
+        boundaries.push((text.len(), shown.len()));
+        compose(&mut shown, text.len());
+
+        (shown, boundaries)
+    }
+
+    /// Start of every glyph cluster on `line` with its x, ending with the line's width
+    fn clusters(&self, fonts: &Fonts, line: &str) -> Vec<(usize, f64)> {
+        let mut clusters: Vec<(usize, f64)> = match fonts.shape(self.text_font(), line, self.text_size()) {
+            Ok(shaped) => shaped.glyphs.iter().map(|g| (g.cluster, g.x as f64)).collect(),
+            // Rough guess so the caret still moves before a font is loaded
+            Err(_) => line.grapheme_indices(true)
+                .enumerate()
+                .map(|(n, (i, _))| (i, n as f64 * self.text_size() as f64 * 0.6))
+                .collect(),
+        };
+        let width = match fonts.measure_text(self.text_font(), line, self.text_size()) {
+            Ok(metrics) => metrics.width as f64,
+            Err(_) => line.graphemes(true).count() as f64 * self.text_size() as f64 * 0.6,
+        };
+
+        clusters.dedup_by_key(|(cluster, _)| *cluster);
+        clusters.push((line.len(), width));
+        clusters
+    }
+
+    /// Work out where every caret stop sits and scroll the caret into view
+    fn place_stops(&mut self, fonts: &Fonts) {
+        self.line_height = fonts.line_metrics(self.text_font(), self.text_size())
+            .map(|m| m.line_height as f64)
+            .unwrap_or(self.text_size() as f64 * 1.2);
+
+        let (shown, boundaries) = self.display();
+        let mut lines = Vec::new();
+        let mut start = 0;
+        self.preedit_span = None;
+        for line in shown.split('\n') {
+            let clusters = self.clusters(fonts, line);
+            let end = start + line.len();
+
+            let stops = boundaries.iter()
+                .filter(|(_, d)| *d >= start && *d <= end)
+                .map(|&(real, d)| (real, interpolate(&clusters, d - start)))
+                .collect();
+
+            if let Some((preedit, cursor)) = &self.preedit {
+                let at = boundaries.iter().find(|(real, _)| *real == self.editor.caret()).map(|b| b.1);
+                if let Some(at) = at.filter(|&at| at >= start && at <= end) {
+                    let x = |offset: usize| interpolate(&clusters, (at + offset).min(end) - start);
+                    self.preedit_span = Some((lines.len(), x(0), x(preedit.len()), cursor.map(|c| x(c.1))));
+                }
+            }
+            lines.push(CaretLine { stops });
+            start = end + 1;
+        }
+        self.lines = lines;
+
+        if !self.editor.is_multiline() {
+            let padding = self.padding();
+            let visible = (self.width - padding.left - padding.right).max(0.0);
+            let (caret, _) = self.caret();
+            if caret - self.scroll > visible {
+                self.scroll = caret - visible;
+            } else if caret < self.scroll {
+                self.scroll = caret;
+            }
+            let width = self.lines.first().and_then(|l| l.stops.last()).map(|s| s.1).unwrap_or(0.0);
+            self.scroll = self.scroll.min((width - visible).max(0.0)).max(0.0);
+        }
+    }
+
+    /// Line index and x of the caret stop at `offset`
+    fn caret_position(&self, offset: usize) -> (f64, usize) {
+        for (i, line) in self.lines.iter().enumerate() {
+            if let Some(&(_, x)) = line.stops.iter().find(|(o, _)| *o == offset) {
+                return (x, i);
+            }
+        }
+        (0.0, 0)
+    }
+
+    /// Where the caret is drawn - inside the composed text while composing
+    fn caret(&self) -> (f64, usize) {
+        match self.preedit_span {
+            Some((line, start, _, cursor)) => (cursor.unwrap_or(start), line),
+            None => self.caret_position(self.editor.caret()),
+        }
+    }
+
+    /// Apply input method composition
+    fn ime(&mut self, ime: &Ime) {
+        match ime {
+            Ime::Preedit(text, _) if text.is_empty() => self.preedit = None,
+            Ime::Preedit(text, cursor) => self.preedit = Some((text.clone(), *cursor)),
+            Ime::Commit(text) => {
+                self.preedit = None;
+                self.editor.insert(text);
+            },
+            Ime::Enabled | Ime::Disabled => self.preedit = None,
+        }
+    }
+
+    fn text_font(&self) -> FontId {
+        self.style.font.unwrap_or(self.font)
+    }
+
+    /// Pixels per em, as overridden by the stylesheet
+    fn text_size(&self) -> f32 {
+        self.style.font_size.unwrap_or(self.size)
+    }
+
+    /// Space between the edges of the box and the text
+    fn padding(&self) -> Edges {
+        self.style.padding.unwrap_or(Edges::all(Self::PADDING))
+    }
+
+    /// Top left of the text inside the box
+    fn origin(&self) -> (f64, f64) {
+        let padding = self.padding();
+        (self.x + padding.left - self.scroll, self.y + padding.top)
+    }
+
+    /// Offset of the caret stop nearest to a point in window coordinates
+    fn offset_at(&self, x: f64, y: f64) -> usize {
+        let (left, top) = self.origin();
+        let line = ((y - top) / self.line_height).floor().clamp(0.0, self.lines.len().saturating_sub(1) as f64);
+        self.nearest(line as usize, x - left)
+    }
+
+    fn nearest(&self, line: usize, x: f64) -> usize {
+        self.lines.get(line)
+            .and_then(|l| l.stops.iter().min_by(|a, b| (a.1 - x).abs().total_cmp(&(b.1 - x).abs())))
+            .map(|s| s.0)
+            .unwrap_or(0)
+    }
+
+    /// Move the caret to the line above or below, keeping its x
+    fn vertical(&mut self, down: bool, extend: bool) {
+        let (x, line) = self.caret_position(self.editor.caret());
+        let target = match down {
+            true if line + 1 < self.lines.len() => line + 1,
+            false if line > 0 => line - 1,
+            _ => return self.editor.move_caret(if down { Movement::End } else { Movement::Start }, extend),
+        };
+        self.editor.set_caret(self.nearest(target, x), extend);
+    }
+
+    /// Apply a key event - returns false for keys the text box leaves to others, such as Tab
+    fn key(&mut self, event: &KeyEvent, ctx: &EventContext) -> bool {
+        event.state.is_pressed() && self.press(&event.logical_key, event.text.as_deref(), ctx.modifiers())
+    }
+
+    /// Apply a key press producing `text`, if any
+    fn press(&mut self, key: &Key, text: Option<&str>, modifiers: ModifiersState) -> bool {
+        let command = modifiers.control_key() || modifiers.super_key();
+        let shift = modifiers.shift_key();
+        let word = modifiers.control_key() || modifiers.alt_key();
+        let multiline = self.editor.is_multiline();
+
+        match key {
+            Key::Named(NamedKey::Tab | NamedKey::Escape) => return false,
+            Key::Named(NamedKey::ArrowLeft) => {
+                self.editor.move_caret(if word { Movement::WordLeft } else { Movement::Left }, shift)
+            },
+            Key::Named(NamedKey::ArrowRight) => {
+                self.editor.move_caret(if word { Movement::WordRight } else { Movement::Right }, shift)
+            },
+            Key::Named(NamedKey::Home) => {
+                self.editor.move_caret(if command { Movement::Start } else { Movement::LineStart }, shift)
+            },
+            Key::Named(NamedKey::End) => {
+                self.editor.move_caret(if command { Movement::End } else { Movement::LineEnd }, shift)
+            },
+            Key::Named(NamedKey::ArrowUp) if multiline => self.vertical(false, shift),
+            Key::Named(NamedKey::ArrowDown) if multiline => self.vertical(true, shift),
+            Key::Named(NamedKey::Backspace) => self.editor.backspace(word),
+            Key::Named(NamedKey::Delete) => self.editor.delete(word),
+            Key::Named(NamedKey::Enter) if multiline => self.editor.insert("\n"),
+            Key::Named(NamedKey::Enter) => return self.submit(),
+            Key::Character(c) if command => match c.to_lowercase().as_str() {
+                "a" => self.editor.select_all(),
+                "c" if !self.password => self.editor.copy(&mut *self.clipboard.borrow_mut()),
+                "x" if !self.password => self.editor.cut(&mut *self.clipboard.borrow_mut()),
+                "v" => self.editor.paste(&mut *self.clipboard.borrow_mut()),
+                "z" if shift => { self.editor.redo(); },
+                "z" => { self.editor.undo(); },
+                "y" => { self.editor.redo(); },
+                _ => return false,
+            },
+            _ => match text {
+                Some(text) if !command => self.editor.insert(text),
+                _ => return false,
+            },
+        }
+
+        true
+    }
+}
+
+/// x of `offset` within a line, between the clusters around it for offsets inside a ligature
+fn interpolate(clusters: &[(usize, f64)], offset: usize) -> f64 {
+    let after = clusters.iter().position(|c| c.0 >= offset).unwrap_or(clusters.len() - 1);
+    let (end, end_x) = clusters[after];
+    if end == offset || after == 0 {
+        return end_x;
+    }
+
+    let (start, start_x) = clusters[after - 1];
+    start_x + (end_x - start_x) * (offset - start) as f64 / (end - start) as f64
+}
+
+impl Widget for TextBox {
+    fn measure(&self, fonts: &Fonts) -> Size {
+        let (shown, _) = self.display();
+        let metrics = fonts.measure_text(self.text_font(), &shown, self.text_size()).ok();
+        let lines = shown.split('\n').count() as f64;
+        let line_height = metrics.as_ref().map(|m| m.line_height as f64).unwrap_or(self.text_size() as f64 * 1.2);
+        let width = metrics.map(|m| m.width as f64).unwrap_or(0.0);
+
+        // Single line boxes keep a usable width however little they hold
+        let min_width = self.text_size() as f64 * 10.0;
+        let padding = self.padding();
+        Size::new(
+            width.max(min_width) + padding.left + padding.right,
+            line_height * lines + padding.top + padding.bottom,
+        )
+    }
+
+    fn layout(&mut self, rect: Rect, fonts: &Fonts) {
+        self.x = rect.x;
+        self.y = rect.y;
+        self.width = rect.width;
+        self.height = rect.height;
+        self.place_stops(fonts);
+    }
+
+    fn bounds(&self) -> Rect {
+        Rect::new(self.x, self.y, self.width, self.height)
+    }
+
+    fn paint(&self, batch: &mut Batch) {
+        let (width, border) = self.style.border_or(1.0, if self.focused { [0.3, 0.6, 1.0, 1.0] } else { [0.4, 0.4, 0.45, 1.0] });
+        batch.primitive(
+            Primitive::rect(self.x as f32, self.y as f32, self.width as f32, self.height as f32, self.style.background_or([0.1, 0.1, 0.12, 1.0]))
+                .radius(self.style.radius.unwrap_or(3.0))
+                .border(width, border),
+        );
+
+        let inner = self.bounds().inset(self.padding());
+        batch.push_clip([inner.x as f32, inner.y as f32, inner.width as f32, inner.height as f32]);
+        let (left, top) = self.origin();
+
+        let selection = self.editor.selection();
+        if !selection.is_empty() {
+            for (i, line) in self.lines.iter().enumerate() {
+                let inside: Vec<f64> = line.stops.iter()
+                    .filter(|(o, _)| selection.contains(o) || *o == selection.end)
+                    .map(|s| s.1)
+                    .collect();
+                let (Some(&start), Some(&end)) = (inside.first(), inside.last()) else { continue };
+                // Selected line breaks show as a little extra width
+                let end = if line.stops.last().is_some_and(|s| s.0 < selection.end) { end + self.text_size() as f64 * 0.3 } else { end };
+
+                let y = top + i as f64 * self.line_height;
+                batch.primitive(Primitive::rect(
+                    (left + start) as f32,
+                    y as f32,
+                    (end - start) as f32,
+                    self.line_height as f32,
+                    [0.25, 0.4, 0.7, 1.0],
+                ));
+            }
+        }
+
+        batch.text(TextRun {
+            font: self.text_font(),
+            size: self.text_size(),
+            position: [left as f32, top as f32],
+            color: self.style.foreground_or(style::rgba(&self.color)),
+            text: self.display().0,
+        });
+
+        if let Some((line, start, end, _)) = self.preedit_span {
+            let y = top + (line + 1) as f64 * self.line_height - 2.0;
+            batch.primitive(Primitive::rect((left + start) as f32, y as f32, (end - start) as f32, 1.0, [0.9, 0.9, 0.9, 1.0]));
+        }
+
+        if self.focused && self.preedit_span.is_none_or(|p| p.3.is_some()) {
+            let (x, line) = self.caret();
+            let y = top + line as f64 * self.line_height;
+            batch.primitive(Primitive::rect((left + x) as f32, y as f32, 1.5, self.line_height as f32, [0.9, 0.9, 0.9, 1.0]));
+        }
+
+        batch.pop_clip();
+    }
+
+    fn handle_event(&mut self, event: &Event, ctx: &mut EventContext) {
+        if ctx.phase() != Phase::Target {
+            return;
+        }
+
+        let used = match event {
+            Event::Focus(focused) => {
+                self.focused = *focused;
+                self.preedit = None;
+                false
+            },
+            Event::Ime(ime) if self.focused => {
+                self.ime(ime);
+                true
+            },
+            // The input method owns the keyboard while composing
+            Event::Key(_) if self.preedit.is_some() => true,
+            Event::PointerPressed { x, y, button: MouseButton::Left } => {
+                let offset = self.offset_at(*x, *y);
+                self.editor.set_caret(offset, ctx.modifiers().shift_key());
+                self.dragging = true;
+                true
+            },
+            Event::PointerMoved { x, y } if self.dragging => {
+                let offset = self.offset_at(*x, *y);
+                self.editor.set_caret(offset, true);
+                true
+            },
+            Event::PointerReleased { button: MouseButton::Left, .. } => {
+                self.dragging = false;
+                false
+            },
+            Event::Key(key) if self.focused => self.key(key, ctx),
+            _ => false,
+        };
+
+        if used {
+            ctx.stop_propagation();
+        }
+    }
+
+    fn focusable(&self) -> bool {
+        true
+    }
+
+    fn tab_index(&self) -> i32 {
+        self.tab_index
+    }
+
+    fn ime_area(&self) -> Option<Rect> {
+        if !self.focused || self.password {
+            return None;
+        }
+
+        let (left, top) = self.origin();
+        let (x, line) = self.caret();
+        Some(Rect::new(left + x, top + line as f64 * self.line_height, 1.0, self.line_height))
+    }
+
+    fn element(&self) -> Option<&Element> {
+        Some(&self.element)
+    }
+
+    fn states(&self) -> States {
+        States { focus: self.focused, ..Default::default() }
+    }
+
+    fn set_style(&mut self, style: Style) -> bool {
+        let resized = style.resizes(&self.style);
+        self.style = style;
+        resized
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::cell::RefCell;
+    use std::rc::Rc;
+    use super::*;
+    use crate::text::MemoryClipboard;
+
+    fn text_box(text: &str, clipboard: &SharedClipboard) -> TextBox {
+        let mut text_box = TextBox::new(text, FontId(0), 14.0).clipboard(clipboard.clone());
+        text_box.focused = true;
+        text_box
+    }
+
+    fn command(text_box: &mut TextBox, key: &str) -> bool {
+        text_box.press(&Key::Character(key.into()), Some(key), ModifiersState::CONTROL)
+    }
+
+    #[test]
+    fn copy_and_paste_between_text_boxes() {
+        let clipboard = MemoryClipboard::shared();
+        let mut from = text_box("hello world", &clipboard);
+        let mut to = text_box("say ", &clipboard);
+
+        from.editor.select(6..11);
+        assert!(command(&mut from, "c"));
+        assert_eq!(clipboard.borrow_mut().get().as_deref(), Some("world"));
+
+        assert!(command(&mut to, "v"));
+        assert_eq!(to.text(), "say world");
+
+        assert!(command(&mut from, "x"));
+        assert_eq!(from.text(), "hello ");
+    }
+
+    #[test]
+    fn password_boxes_refuse_to_copy() {
+        let clipboard = MemoryClipboard::shared();
+        let mut text_box = text_box("secret", &clipboard).password(true);
+
+        text_box.editor.select_all();
+        assert!(!command(&mut text_box, "c"));
+        assert!(!command(&mut text_box, "x"));
+        assert_eq!(clipboard.borrow_mut().get(), None);
+        assert_eq!(text_box.text(), "secret");
+        assert_eq!(text_box.display().0, TextBox::MASK.repeat(6));
+    }
+
+    #[test]
+    fn enter_submits_single_line_text() {
+        let clipboard = MemoryClipboard::shared();
+        let enter = Key::Named(NamedKey::Enter);
+
+        let mut text_box = text_box("query", &clipboard);
+        assert!(!text_box.press(&enter, Some("\r"), ModifiersState::empty()));
+        assert_eq!(text_box.text(), "query");
+
+        let submitted = Rc::new(RefCell::new(Vec::new()));
+        let sink = submitted.clone();
+        let mut text_box = text_box.on_submit(move |text| sink.borrow_mut().push(text.to_owned()));
+        assert!(text_box.press(&enter, Some("\r"), ModifiersState::empty()));
+        assert_eq!(text_box.text(), "query");
+        assert_eq!(*submitted.borrow(), ["query"]);
+    }
+
+    #[test]
+    fn enter_breaks_lines_in_multiline_text() {
+        let mut text_box = TextBox::multiline("one", FontId(0), 14.0).clipboard(MemoryClipboard::shared());
+        assert!(text_box.press(&Key::Named(NamedKey::Enter), Some("\r"), ModifiersState::empty()));
+        assert_eq!(text_box.text(), "one\n");
+    }
+
+    #[test]
+    fn undo_and_redo_through_shortcuts() {
+        let clipboard = MemoryClipboard::shared();
+        let mut text_box = text_box("", &clipboard);
+
+        text_box.press(&Key::Character("a".into()), Some("a"), ModifiersState::empty());
+        assert!(command(&mut text_box, "z"));
+        assert_eq!(text_box.text(), "");
+        assert!(command(&mut text_box, "y"));
+        assert_eq!(text_box.text(), "a");
+    }
+
+    fn ime(text_box: &mut TextBox, ime: Ime) -> bool {
+        let mut ctx = EventContext::new(Phase::Target, ModifiersState::empty());
+        text_box.handle_event(&Event::Ime(ime), &mut ctx);
+        ctx.is_stopped()
+    }
+
+    #[test]
+    fn preedit_shows_at_the_caret_until_committed() {
+        let clipboard = MemoryClipboard::shared();
+        let mut text_box = text_box("ab", &clipboard);
+        text_box.editor.set_caret(1, false);
+
+        assert!(ime(&mut text_box, Ime::Preedit("\u{3042}".to_owned(), Some((0, 3)))));
+        assert_eq!(text_box.text(), "ab");
+        assert_eq!(text_box.display().0, "a\u{3042}b");
+
+        text_box.layout(Rect::new(0.0, 0.0, 200.0, 30.0), &Fonts::new());
+        let (line, start, end, cursor) = text_box.preedit_span.expect("composed text is placed by layout");
+        assert_eq!(line, 0);
+        assert!(start < end);
+        assert!(cursor.is_some());
+
+        assert!(ime(&mut text_box, Ime::Commit("\u{3044}".to_owned())));
+        assert_eq!(text_box.text(), "a\u{3044}b");
+        assert_eq!(text_box.display().0, "a\u{3044}b");
+    }
+
+    #[test]
+    fn empty_preedit_and_focus_loss_cancel_composition() {
+        let clipboard = MemoryClipboard::shared();
+        let mut text_box = text_box("", &clipboard);
+
+        ime(&mut text_box, Ime::Preedit("k".to_owned(), None));
+        ime(&mut text_box, Ime::Preedit(String::new(), None));
+        assert_eq!(text_box.display().0, "");
+
+        ime(&mut text_box, Ime::Preedit("k".to_owned(), None));
+        let mut ctx = EventContext::new(Phase::Target, ModifiersState::empty());
+        text_box.handle_event(&Event::Focus(false), &mut ctx);
+        assert_eq!(text_box.display().0, "");
+        assert!(!ime(&mut text_box, Ime::Commit("x".to_owned())));
+        assert_eq!(text_box.text(), "");
+    }
+
+    #[test]
+    fn input_method_follows_the_caret_except_in_passwords() {
+        let clipboard = MemoryClipboard::shared();
+        let mut text_box = text_box("", &clipboard);
+        text_box.layout(Rect::new(10.0, 20.0, 200.0, 30.0), &Fonts::new());
+
+        let area = text_box.ime_area().expect("focused text boxes take input method text");
+        assert!(text_box.bounds().contains(area.x, area.y));
+
+        text_box.password = true;
+        assert_eq!(text_box.ime_area(), None);
+    }
+}
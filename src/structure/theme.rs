@@ -0,0 +1,207 @@
+use std::fmt;
+use crate::core::color::Color;
+use crate::structure::style::{Origin, Style, Stylesheet};
+use crate::structure::window::Window;
+
+/// Built-in look for every widget, applied beneath the window's own stylesheet
+///
+/// Windows are unthemed, drawing the colors passed to widget constructors, until given a theme
+/// with `Window::theme` or told to follow the light or dark setting of the OS with
+/// `Window::follow_system_theme`. A theme's colors replace those passed to constructors
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
+pub enum Theme {
+    Light,
+    #[default]
+    Dark,
+}
+
+impl Theme {
+    /// Rules of the theme, built on the `Color` palette and its `korple` brand color - they are of
+    /// theme origin, so any author rule overrides them
+    pub fn stylesheet(self) -> Stylesheet {
+        let (background, foreground) = self.surface();
+        let button = Color::Korple;
+        let background_of = |color: Color| Style { background: Some(color), ..Style::default() };
+
+        // Button states are derived from the brand color, disabled last so it wins while hovered
+        Stylesheet::parse(self.source())
+            .and_then(|s| s.rule("Window", Style {
+                background: Some(background.clone()),
+                foreground: Some(foreground.clone()),
+                ..Style::default()
+            }))
+            .and_then(|s| s.rule("Button:hover", background_of(button.lighten(0.06))))
+            .and_then(|s| s.rule("Button:active", background_of(button.darken(0.08))))
+            .and_then(|s| s.rule("Button:disabled", Style {
+                background: Some(button.saturate(-0.8).mix(&background, 0.6)),
+                foreground: Some(foreground.mix(&background, 0.5)),
+                ..Style::default()
+            }))
+            .expect("built-in themes are valid stylesheets")
+            .origin(Origin::Theme)
+    }
+
+    /// Window background and text color
+    fn surface(self) -> (Color, Color) {
+        match self {
+            Theme::Light => (Color::RGB(0xf4, 0xf4, 0xf7), Color::RGB(0x1c, 0x1c, 0x22)),
+            Theme::Dark => (Color::RGB(0x1e, 0x1e, 0x24), Color::RGB(0xe8, 0xe8, 0xec)),
+        }
+    }
+
+    fn source(self) -> &'static str {
+        match self {
+            Theme::Light => LIGHT,
+            Theme::Dark => DARK,
+        }
+    }
+}
+
+impl From<winit::window::Theme> for Theme {
+    fn from(theme: winit::window::Theme) -> Self {
+        match theme {
+            winit::window::Theme::Light => Theme::Light,
+            winit::window::Theme::Dark => Theme::Dark,
+        }
+    }
+}
+
+impl fmt::Display for Theme {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            Theme::Light => write!(f, "light"),
+            Theme::Dark => write!(f, "dark"),
+        }
+    }
+}
+
+const LIGHT: &str = "
+Button { background: korple; foreground: white; }
+Button:focused { border: 2 #1c1c22; }
+
+TextBox { background: white; border: 1 #c4c4cc; }
+TextBox:focused { border: 1 korple; }
+
+Toggle { background: #c4c4cc; foreground: white; }
+Toggle:checked { background: korple; }
+
+Slider { background: #c4c4cc; foreground: korple; }
+
+Modal { background: white; border: 1 #c4c4cc; }
+
+TabStrip { background: #e4e4ea; }
+Tab { background: #ececf0; }
+Tab:checked { background: white; }
+
+Scrollbar { background: #1c1c2266; }
+Scrollbar:pressed { background: #1c1c22b3; }
+";
+
+const DARK: &str = "
+Button { background: korple; foreground: white; }
+Button:focused { border: 2 #e8e8ec; }
+
+TextBox { background: #111116; border: 1 #44444c; }
+TextBox:focused { border: 1 korple; }
+
+Toggle { background: #4a4a52; foreground: #f2f2f2; }
+Toggle:checked { background: korple; }
+
+Slider { background: #4a4a52; foreground: korple; }
+
+Modal { background: #26262e; border: 1 #44444c; }
+
+TabStrip { background: #1f1f24; }
+Tab { background: #29292f; }
+Tab:checked { background: #3d3d47; }
+
+Scrollbar { background: #80808066; }
+Scrollbar:pressed { background: #808080b3; }
+";
+
+impl Window {
+    /// Use `theme` whatever the OS setting
+    pub fn theme(mut self, theme: Theme) -> Self {
+        self.set_theme(Some(theme));
+        self
+    }
+
+    /// Follow the light or dark setting of the OS - `false` also drops the current theme, leaving
+    /// only the window's stylesheet
+    pub fn follow_system_theme(mut self, follow: bool) -> Self {
+        self.follow_system_theme = follow;
+        if !follow {
+            self.set_theme(None);
+        }
+        self
+    }
+
+    /// Swap the theme, restyling every widget on the next `restyle` - the OS setting is no longer
+    /// followed
+    pub fn set_theme(&mut self, theme: Option<Theme>) {
+        self.follow_system_theme = false;
+        self.active_theme = theme;
+        self.cascade();
+    }
+
+    /// Theme beneath the stylesheet, if any
+    pub fn active_theme(&self) -> Option<Theme> {
+        self.active_theme
+    }
+
+    /// Called with the OS setting when the window opens and whenever it changes
+    pub(crate) fn system_theme(&mut self, theme: Theme) {
+        if self.follow_system_theme && self.active_theme != Some(theme) {
+            self.active_theme = Some(theme);
+            self.cascade();
+        }
+    }
+
+    /// Rebuild the rules `restyle` resolves - the theme's, then the stylesheet's, which win by
+    /// origin
+    pub(crate) fn cascade(&mut self) {
+        let mut cascade = self.active_theme.map(Theme::stylesheet).unwrap_or_default();
+        cascade.rules.extend(self.stylesheet.rules.iter().cloned());
+        self.cascade = cascade;
+        self.restyle_pending = true;
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::structure::style::{Element, States};
+
+    #[test]
+    fn system_theme_is_only_followed_when_asked() {
+        let mut window = Window::new(100.0, 100.0);
+        window.system_theme(Theme::Dark);
+        assert_eq!(window.active_theme(), None);
+
+        let mut window = Window::new(100.0, 100.0).follow_system_theme(true);
+        window.system_theme(Theme::Light);
+        assert_eq!(window.active_theme(), Some(Theme::Light));
+    }
+
+    #[test]
+    fn themes_color_every_styled_surface() {
+        for theme in [Theme::Light, Theme::Dark] {
+            let sheet = theme.stylesheet();
+            for kind in ["Button", "TextBox", "Toggle", "Slider", "Modal", "TabStrip", "Tab", "Scrollbar"] {
+                let style = sheet.resolve(&Element::new(kind), States::default(), &Style::default());
+                assert!(style.background.is_some(), "{theme} theme leaves {kind} unstyled");
+            }
+        }
+    }
+
+    #[test]
+    fn author_rules_override_theme_states() {
+        let window = Window::new(100.0, 100.0)
+            .theme(Theme::Light)
+            .stylesheet(Stylesheet::parse("Button { background: #102030; }").unwrap());
+
+        let hovered = States { hover: true, ..States::default() };
+        let style = window.cascade.resolve(&Element::new("Button"), hovered, &Style::default());
+        assert_eq!(style.background, Some(Color::RGB(0x10, 0x20, 0x30)));
+    }
+}
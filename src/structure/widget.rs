@@ -0,0 +1,87 @@
+use crate::core::renderer::Batch;
+use crate::structure::event::{Event, EventContext};
+use crate::structure::layout::{Rect, Size};
+use crate::structure::style::{Element, States, Style};
+use crate::text::Fonts;
+
+/// Something that can be measured, placed, drawn and receive input. Every built-in component
+/// implements it, and `Component::Custom` holds widgets from other crates
+pub trait Widget {
+    /// Preferred size for layout
+    fn measure(&self, _fonts: &Fonts) -> Size {
+        Size::ZERO
+    }
+
+    /// Move the widget to the rectangle resolved by its container, placing any children inside it
+    fn layout(&mut self, _rect: Rect, _fonts: &Fonts) {}
+
+    /// Pull new values from bound signals - returns true when anything changed, marking the
+    /// widget dirty
+    fn sync(&mut self) -> bool {
+        false
+    }
+
+    /// Area covered by the widget, used for hit testing
+    fn bounds(&self) -> Rect {
+        Rect::default()
+    }
+
+    /// Add the widget's geometry to `batch`, children included
+    fn paint(&self, _batch: &mut Batch) {}
+
+    /// React to input - called for each phase the event passes through this widget in
+    fn handle_event(&mut self, _event: &Event, _ctx: &mut EventContext) {}
+
+    /// Whether the widget can take keyboard focus
+    fn focusable(&self) -> bool {
+        false
+    }
+
+    /// Position in Tab order - positive indices come first in ascending order, then zero in
+    /// layout order. Negative indices can be focused by clicking but are skipped by Tab
+    fn tab_index(&self) -> i32 {
+        0
+    }
+
+    /// Keep Tab traversal and clicks inside this widget while it returns true, as for an open
+    /// modal
+    fn focus_scope(&self) -> bool {
+        false
+    }
+
+    /// Draw above the rest of the window and take the pointer first while it returns true, as for
+    /// an open modal. Only honoured for components placed directly in the window
+    fn overlay(&self) -> bool {
+        false
+    }
+
+    /// Where text typed through an input method would go, while the widget accepts it - the
+    /// candidate window is placed next to this area
+    fn ime_area(&self) -> Option<Rect> {
+        None
+    }
+
+    /// Type, id and classes matched by stylesheet selectors - `None` leaves the widget unstyled
+    fn element(&self) -> Option<&Element> {
+        None
+    }
+
+    /// States matched by selectors like `Button:hover`
+    fn states(&self) -> States {
+        States::default()
+    }
+
+    /// Take the style resolved from the window's stylesheet - returns true when the widget's
+    /// size may have changed
+    fn set_style(&mut self, _style: Style) -> bool {
+        false
+    }
+
+    fn children(&self) -> Vec<&dyn Widget> {
+        Vec::new()
+    }
+
+    fn children_mut(&mut self) -> Vec<&mut dyn Widget> {
+        Vec::new()
+    }
+}
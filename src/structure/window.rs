@@ -1,4 +1,15 @@
+use std::collections::HashMap;
+use crate::core::primitive::Primitive;
+use crate::core::renderer::Batch;
 use crate::structure::components;
+use crate::structure::dispatch::Path;
+use crate::structure::grid::Grid;
+use crate::structure::layout::{Align, Edges, Flex, Justify, Rect, Size};
+use crate::structure::scroll::Scroll;
+use crate::structure::style::{Element, Style, Stylesheet};
+use crate::structure::tabs::Tabs;
+use crate::structure::theme::Theme;
+use crate::structure::widget::Widget;
 
 
 
@@ -11,18 +22,290 @@ pub struct Window {
     pub height: f64,
     pub components: Vec<components::Component>,
     pub containers: Vec<Container>,
+    /// Widgets whose bound data changed since layout last ran
+    pub(crate) dirty: Vec<Path>,
+    /// Signal changes seen by the last `sync`
+    pub(crate) synced: Option<u64>,
+    /// Widget states or the stylesheet changed since the last `restyle`
+    pub(crate) restyle_pending: bool,
+    /// Paths of the widgets and containers named in a layout file
+    pub(crate) ids: HashMap<String, Path>,
+    pub(crate) stylesheet: Stylesheet,
+    pub(crate) active_theme: Option<Theme>,
+    pub(crate) follow_system_theme: bool,
+    /// Rules of the theme followed by those of the stylesheet
+    pub(crate) cascade: Stylesheet,
+    /// Resolved for the window itself by `restyle`
+    pub(crate) style: Style,
 }
 
+#[allow(clippy::large_enum_variant)]
 pub enum ContainerType {
-    Scrollable,
+    Scrollable(Scroll),
     Column,
     Row,
-    Grid,
-    Tab,
+    Grid(Grid),
+    Tab(Tabs),
 }
 
 pub struct Container {
     pub layout: ContainerType,
     pub components: Vec<components::Component>,
-    pub containers: Vec<Container>
+    pub containers: Vec<Container>,
+    pub padding: Edges,
+    /// Gap between children
+    pub spacing: f64,
+    /// Placement of children across the stacking direction
+    pub align: Align,
+    /// Distribution of leftover space along the stacking direction of rows and columns
+    pub justify: Justify,
+    /// Start a new line when children overflow a row or column
+    pub wrap: bool,
+    /// Sizing of this container within a parent row or column
+    pub flex: Flex,
+    pub min_size: Size,
+    pub max_size: Size,
+    pub element: Element,
+    /// Resolved by the layout pass
+    pub rect: Rect,
+    /// Resolved from the window's stylesheet
+    pub(crate) style: Style,
+    /// Children dropped since the dispatcher last looked, by their index at the time
+    pub(crate) removed: Vec<usize>,
+    /// Preferred sizes of the children at the last layout, to tell whether a changed child
+    /// moves its siblings
+    pub(crate) sizes: Vec<Size>,
+}
+
+impl Container {
+    pub fn new(layout: ContainerType) -> Self {
+        let kind = match layout {
+            ContainerType::Scrollable(_) => "Scrollable",
+            ContainerType::Column => "Column",
+            ContainerType::Row => "Row",
+            ContainerType::Grid(_) => "Grid",
+            ContainerType::Tab(_) => "Tab",
+        };
+
+        Self {
+            layout,
+            components: Vec::new(),
+            containers: Vec::new(),
+            padding: Edges::default(),
+            spacing: 0.0,
+            align: Align::default(),
+            justify: Justify::default(),
+            wrap: false,
+            flex: Flex::default(),
+            min_size: Size::ZERO,
+            max_size: Size::INFINITE,
+            element: Element::new(kind),
+            rect: Rect::default(),
+            style: Style::default(),
+            removed: Vec::new(),
+            sizes: Vec::new(),
+        }
+    }
+
+    pub fn component(mut self, component: components::Component) -> Self {
+        self.components.push(component);
+        self
+    }
+
+    pub fn container(mut self, container: Container) -> Self {
+        self.containers.push(container);
+        self
+    }
+
+    pub fn padding(mut self, padding: Edges) -> Self {
+        self.padding = padding;
+        self
+    }
+
+    pub fn spacing(mut self, spacing: f64) -> Self {
+        self.spacing = spacing;
+        self
+    }
+
+    pub fn align(mut self, align: Align) -> Self {
+        self.align = align;
+        self
+    }
+
+    pub fn justify(mut self, justify: Justify) -> Self {
+        self.justify = justify;
+        self
+    }
+
+    pub fn wrap(mut self, wrap: bool) -> Self {
+        self.wrap = wrap;
+        self
+    }
+
+    pub fn flex(mut self, flex: Flex) -> Self {
+        self.flex = flex;
+        self
+    }
+
+    pub fn min_size(mut self, min_size: Size) -> Self {
+        self.min_size = min_size;
+        self
+    }
+
+    pub fn max_size(mut self, max_size: Size) -> Self {
+        self.max_size = max_size;
+        self
+    }
+
+    pub fn id(mut self, id: &str) -> Self {
+        self.element.id = Some(id.to_owned());
+        self
+    }
+
+    pub fn class(mut self, class: &str) -> Self {
+        self.element.classes.push(class.to_owned());
+        self
+    }
+
+    /// Padding used by layout - the stylesheet's when it sets one
+    pub(crate) fn inner_padding(&self) -> Edges {
+        self.style.padding.unwrap_or(self.padding)
+    }
+}
+
+impl Window {
+    pub fn new(width: f64, height: f64) -> Self {
+        Self {
+            active: true,
+            x: 0.0,
+            y: 0.0,
+            width,
+            height,
+            components: Vec::new(),
+            containers: Vec::new(),
+            dirty: Vec::new(),
+            synced: None,
+            restyle_pending: true,
+            ids: HashMap::new(),
+            stylesheet: Stylesheet::default(),
+            active_theme: None,
+            follow_system_theme: false,
+            cascade: Stylesheet::default(),
+            style: Style::default(),
+        }
+    }
+
+    pub fn component(mut self, component: components::Component) -> Self {
+        self.components.push(component);
+        self
+    }
+
+    pub fn container(mut self, container: Container) -> Self {
+        self.containers.push(container);
+        self
+    }
+
+    /// Add every component in the window to `batch`, in tree order - overlays go last so they sit
+    /// above the containers
+    pub fn draw(&self, batch: &mut Batch) {
+        self.components.iter().filter(|c| !c.overlay()).for_each(|c| c.paint(batch));
+        self.containers.iter().for_each(|c| c.draw(batch));
+        self.components.iter().filter(|c| c.overlay()).for_each(|c| c.paint(batch));
+    }
+}
+
+impl Container {
+    pub fn draw(&self, batch: &mut Batch) {
+        let (border, border_color) = self.style.border_or(0.0, [0.0; 4]);
+        if self.style.background.is_some() || border > 0.0 {
+            let r = self.rect;
+            batch.primitive(
+                Primitive::rect(r.x as f32, r.y as f32, r.width as f32, r.height as f32, self.style.background_or([0.0; 4]))
+                    .radius(self.style.radius.unwrap_or(0.0))
+                    .border(border, border_color),
+            );
+        }
+
+        match &self.layout {
+            ContainerType::Scrollable(scroll) => {
+                let v = scroll.viewport;
+                batch.push_clip([v.x as f32, v.y as f32, v.width as f32, v.height as f32]);
+                self.components.iter().for_each(|c| c.paint(batch));
+                self.containers.iter().for_each(|c| c.draw(batch));
+                batch.pop_clip();
+                scroll.draw(batch);
+            },
+            ContainerType::Tab(tabs) => {
+                match tabs.page() {
+                    Some(page) if page < self.components.len() => self.components[page].paint(batch),
+                    Some(page) => {
+                        if let Some(c) = self.containers.get(page - self.components.len()) {
+                            c.draw(batch);
+                        }
+                    },
+                    None => {},
+                }
+                tabs.draw(batch);
+            },
+            _ => {
+                self.components.iter().for_each(|c| c.paint(batch));
+                self.containers.iter().for_each(|c| c.draw(batch));
+            },
+        }
+    }
+
+    /// Whether the child at `index` in layout order is shown - a tab container only shows its
+    /// active page
+    pub(crate) fn shows(&self, index: usize) -> bool {
+        match &self.layout {
+            ContainerType::Tab(tabs) => tabs.page() == Some(index),
+            _ => true,
+        }
+    }
+
+    /// Child containers currently shown
+    pub(crate) fn visible_containers_mut(&mut self) -> impl Iterator<Item = &mut Container> {
+        let page = match &self.layout {
+            ContainerType::Tab(tabs) => Some(tabs.page().and_then(|p| p.checked_sub(self.components.len()))),
+            _ => None,
+        };
+
+        self.containers.iter_mut()
+            .enumerate()
+            .filter(move |(i, _)| page.is_none_or(|p| p == Some(*i)))
+            .map(|(_, c)| c)
+    }
+
+    /// Drop the child at `index` in layout order
+    pub(crate) fn remove_child(&mut self, index: usize) {
+        if index < self.components.len() {
+            self.components.remove(index);
+        } else if index - self.components.len() < self.containers.len() {
+            self.containers.remove(index - self.components.len());
+        } else {
+            return;
+        }
+        self.removed.push(index);
+    }
+
+    fn take_removed(&mut self, path: Path, found: &mut Vec<Path>) {
+        found.extend(self.removed.drain(..).map(|i| [path.as_slice(), &[i]].concat()));
+
+        let offset = self.components.len();
+        for (i, c) in self.containers.iter_mut().enumerate() {
+            c.take_removed([path.as_slice(), &[offset + i]].concat(), found);
+        }
+    }
+}
+
+impl Window {
+    /// Paths of the children removed since the last call, containers before their descendants
+    pub(crate) fn take_removed(&mut self) -> Vec<Path> {
+        let mut found = Vec::new();
+        let offset = self.components.len();
+        for (i, c) in self.containers.iter_mut().enumerate() {
+            c.take_removed(vec![offset + i], &mut found);
+        }
+        found
+    }
 }
@@ -0,0 +1,281 @@
+use std::collections::HashMap;
+use crate::text::FontId;
+
+/// Identifies a rasterized glyph - sizes are stored in 1/64th pixels so they can be hashed
+#[derive(Copy, Clone, Debug, PartialEq, Eq, Hash)]
+pub struct GlyphKey {
+    pub font: FontId,
+    pub glyph: u16,
+    pub size: u32,
+}
+
+impl GlyphKey {
+    pub fn new(font: FontId, glyph: u16, size: f32) -> Self {
+        Self { font, glyph, size: (size * 64.0).round() as u32 }
+    }
+}
+
+/// Where a glyph lives in the atlas
+#[derive(Copy, Clone, Debug, PartialEq)]
+pub struct AtlasEntry {
+    /// x, y, width, height in atlas pixels
+    pub rect: [u32; 4],
+    /// Offset of the bitmap's top left corner from the pen position on the baseline
+    pub bearing: [f32; 2],
+}
+
+struct Shelf {
+    y: u32,
+    height: u32,
+    cursor: u32,
+    /// Frame in which a glyph on this shelf was last used
+    last_used: u64,
+    glyphs: Vec<GlyphKey>,
+}
+
+/// Shelf packer for glyph bitmaps. When the atlas is full the least recently used shelf is
+/// evicted as a whole, or adjacent shelves together for a glyph taller than any of them - glyphs
+/// used in the current frame are never evicted
+pub struct Atlas {
+    size: u32,
+    shelves: Vec<Shelf>,
+    entries: HashMap<GlyphKey, (usize, AtlasEntry)>,
+    frame: u64,
+}
+
+impl Atlas {
+    /// Empty space kept between glyphs so linear sampling does not bleed
+    const PADDING: u32 = 1;
+
+    pub fn new(size: u32) -> Self {
+        Self { size, shelves: Vec::new(), entries: HashMap::new(), frame: 0 }
+    }
+
+    pub fn size(&self) -> u32 {
+        self.size
+    }
+
+    pub fn len(&self) -> usize {
+        self.entries.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.entries.is_empty()
+    }
+
+    /// Start a new frame - glyphs used from here on are protected from eviction until the next
+    pub fn begin_frame(&mut self) {
+        self.frame += 1;
+    }
+
+    /// Look up a glyph, marking it as used this frame
+    pub fn get(&mut self, key: &GlyphKey) -> Option<AtlasEntry> {
+        let (shelf, entry) = *self.entries.get(key)?;
+        self.shelves[shelf].last_used = self.frame;
+        Some(entry)
+    }
+
+    /// Reserve space for a `width` x `height` bitmap - `None` when the glyph is larger than the
+    /// atlas or every shelf is in use this frame
+    pub fn insert(&mut self, key: GlyphKey, width: u32, height: u32, bearing: [f32; 2]) -> Option<AtlasEntry> {
+        let (w, h) = (width + Self::PADDING, height + Self::PADDING);
+        if w > self.size || h > self.size {
+            return None;
+        }
+
+        let shelf = self.find(w, h)
+            .or_else(|| self.open(h))
+            .or_else(|| self.evict(h))
+            .or_else(|| self.merge(h))?;
+        let s = &mut self.shelves[shelf];
+        let entry = AtlasEntry { rect: [s.cursor, s.y, width, height], bearing };
+        s.cursor += w;
+        s.last_used = self.frame;
+        s.glyphs.push(key);
+        self.entries.insert(key, (shelf, entry));
+
+        Some(entry)
+    }
+
+    /// Best fitting existing shelf with room left
+    fn find(&self, w: u32, h: u32) -> Option<usize> {
+        self.shelves.iter()
+            .enumerate()
+            .filter(|(_, s)| s.height >= h && s.height <= h * 2 && self.size - s.cursor >= w)
+            .min_by_key(|(_, s)| s.height)
+            .map(|(i, _)| i)
+    }
+
+    /// Open a new shelf below the last one
+    fn open(&mut self, h: u32) -> Option<usize> {
+        let y = self.shelves.last().map(|s| s.y + s.height).unwrap_or(0);
+        if self.size - y < h {
+            return None;
+        }
+
+        self.shelves.push(Shelf { y, height: h, cursor: 0, last_used: self.frame, glyphs: Vec::new() });
+        Some(self.shelves.len() - 1)
+    }
+
+    /// Clear the least recently used shelf tall enough for the glyph
+    fn evict(&mut self, h: u32) -> Option<usize> {
+        let frame = self.frame;
+        let (index, shelf) = self.shelves.iter_mut()
+            .enumerate()
+            .filter(|(_, s)| s.height >= h && s.last_used < frame)
+            .min_by_key(|(_, s)| (s.last_used, s.height))?;
+
+        for key in shelf.glyphs.drain(..) {
+            self.entries.remove(&key);
+        }
+        shelf.cursor = 0;
+
+        Some(index)
+    }
+
+    /// Clear the least recently used run of adjacent shelves that together fit the glyph, along
+    /// with any unused space below the last shelf, and join them into one shelf of height `h` -
+    /// the rest of the run becomes a free shelf below it
+    fn merge(&mut self, h: u32) -> Option<usize> {
+        let frame = self.frame;
+        let below = self.shelves.last().map_or(self.size, |s| self.size - s.y - s.height);
+        let mut best: Option<(u64, std::ops::Range<usize>)> = None;
+
+        for start in 0..self.shelves.len() {
+            let (mut height, mut newest) = (0, 0);
+            for end in start..self.shelves.len() {
+                let shelf = &self.shelves[end];
+                if shelf.last_used >= frame {
+                    break;
+                }
+                height += shelf.height;
+                newest = newest.max(shelf.last_used);
+
+                let last = end + 1 == self.shelves.len();
+                if height >= h || (last && height + below >= h) {
+                    if best.as_ref().is_none_or(|(n, _)| newest < *n) {
+                        best = Some((newest, start..end + 1));
+                    }
+                    break;
+                }
+            }
+        }
+
+        let (_, run) = best?;
+        let y = self.shelves[run.start].y;
+        let height: u32 = self.shelves[run.clone()].iter().map(|s| s.height).sum();
+        for shelf in &self.shelves[run.clone()] {
+            for key in &shelf.glyphs {
+                self.entries.remove(key);
+            }
+        }
+
+        let mut joined = vec![Shelf { y, height: h, cursor: 0, last_used: frame, glyphs: Vec::new() }];
+        // A run ending at the last shelf simply gives its leftover back to the space below
+        if height > h && run.end < self.shelves.len() {
+            joined.push(Shelf { y: y + h, height: height - h, cursor: 0, last_used: 0, glyphs: Vec::new() });
+        }
+
+        let shift = joined.len() as isize - run.len() as isize;
+        for (shelf, _) in self.entries.values_mut() {
+            if *shelf >= run.end {
+                *shelf = (*shelf as isize + shift) as usize;
+            }
+        }
+        let index = run.start;
+        self.shelves.splice(run, joined);
+
+        Some(index)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn key(glyph: u16) -> GlyphKey {
+        GlyphKey::new(FontId(0), glyph, 12.0)
+    }
+
+    #[test]
+    fn glyphs_share_a_shelf_of_similar_height() {
+        let mut atlas = Atlas::new(64);
+        let a = atlas.insert(key(0), 10, 12, [1.0, -12.0]).unwrap();
+        let b = atlas.insert(key(1), 8, 10, [0.0; 2]).unwrap();
+        assert_eq!(a.rect, [0, 0, 10, 12]);
+        assert_eq!(b.rect, [11, 0, 8, 10]);
+        assert_eq!(atlas.get(&key(0)), Some(a));
+        assert_eq!(atlas.len(), 2);
+
+        // Less than half the shelf height opens a new shelf rather than wasting the space
+        let c = atlas.insert(key(2), 4, 4, [0.0; 2]).unwrap();
+        assert_eq!(c.rect, [0, 13, 4, 4]);
+
+        // A full shelf also opens a new one
+        let d = atlas.insert(key(3), 50, 12, [0.0; 2]).unwrap();
+        assert_eq!(d.rect, [0, 18, 50, 12]);
+    }
+
+    #[test]
+    fn glyphs_larger_than_the_atlas_are_refused() {
+        let mut atlas = Atlas::new(16);
+        assert!(atlas.insert(key(0), 16, 4, [0.0; 2]).is_none());
+        assert!(atlas.insert(key(0), 4, 16, [0.0; 2]).is_none());
+        assert!(atlas.insert(key(0), 15, 15, [0.0; 2]).is_some());
+    }
+
+    #[test]
+    fn least_recently_used_shelf_is_evicted() {
+        let mut atlas = Atlas::new(32);
+        atlas.insert(key(0), 31, 15, [0.0; 2]).unwrap();
+        atlas.begin_frame();
+        atlas.insert(key(1), 31, 15, [0.0; 2]).unwrap();
+
+        atlas.begin_frame();
+        let entry = atlas.insert(key(2), 20, 15, [0.0; 2]).unwrap();
+        assert_eq!(entry.rect, [0, 0, 20, 15]);
+        assert!(atlas.get(&key(0)).is_none());
+        assert!(atlas.get(&key(1)).is_some());
+        assert_eq!(atlas.len(), 2);
+    }
+
+    #[test]
+    fn glyphs_used_this_frame_are_never_evicted() {
+        let mut atlas = Atlas::new(32);
+        atlas.insert(key(0), 31, 15, [0.0; 2]).unwrap();
+        atlas.insert(key(1), 31, 15, [0.0; 2]).unwrap();
+
+        atlas.begin_frame();
+        atlas.get(&key(0));
+        atlas.get(&key(1));
+        assert!(atlas.insert(key(2), 10, 10, [0.0; 2]).is_none());
+        assert_eq!(atlas.len(), 2);
+
+        // Next frame the same glyph fits by evicting a shelf nobody drew
+        atlas.begin_frame();
+        atlas.get(&key(1));
+        assert_eq!(atlas.insert(key(2), 10, 10, [0.0; 2]).unwrap().rect, [0, 0, 10, 10]);
+        assert!(atlas.get(&key(0)).is_none());
+    }
+
+    #[test]
+    fn tall_glyphs_join_adjacent_unused_shelves() {
+        // Four 16 pixel shelves fill the atlas, so a 40 pixel glyph needs three of them
+        let mut atlas = Atlas::new(64);
+        for glyph in 0..4 {
+            atlas.insert(key(glyph), 63, 15, [0.0; 2]).unwrap();
+        }
+
+        atlas.begin_frame();
+        atlas.get(&key(3));
+        let entry = atlas.insert(key(9), 20, 39, [0.0; 2]).unwrap();
+        assert_eq!(entry.rect, [0, 0, 20, 39]);
+        assert_eq!(atlas.len(), 2);
+        assert!(atlas.get(&key(0)).is_none());
+
+        // The leftover of the joined shelves is free again
+        let rest = atlas.insert(key(10), 10, 7, [0.0; 2]).unwrap();
+        assert_eq!(rest.rect[1], 40);
+        assert_eq!(atlas.get(&key(3)).unwrap().rect, [0, 48, 63, 15]);
+    }
+}
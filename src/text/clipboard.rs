@@ -0,0 +1,88 @@
+use std::cell::RefCell;
+use std::rc::Rc;
+use logfather::*;
+
+/// Where copied text goes - [`SystemClipboard`] connects the one of the OS
+pub trait Clipboard {
+    fn get(&mut self) -> Option<String>;
+    fn set(&mut self, text: String);
+}
+
+/// Clipboard shared between every widget that copies and pastes
+pub type SharedClipboard = Rc<RefCell<dyn Clipboard>>;
+
+/// Clipboard that only lives as long as the program - the fallback where there is no system
+/// clipboard, and a stand-in for tests
+#[derive(Clone, Debug, Default)]
+pub struct MemoryClipboard {
+    text: Option<String>,
+}
+
+impl MemoryClipboard {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    pub fn shared() -> SharedClipboard {
+        Rc::new(RefCell::new(Self::new()))
+    }
+}
+
+impl Clipboard for MemoryClipboard {
+    fn get(&mut self) -> Option<String> {
+        self.text.clone()
+    }
+
+    fn set(&mut self, text: String) {
+        self.text = Some(text);
+    }
+}
+
+/// The clipboard of the OS, shared with other programs
+#[cfg(not(target_arch = "wasm32"))]
+pub struct SystemClipboard {
+    clipboard: arboard::Clipboard,
+}
+
+#[cfg(not(target_arch = "wasm32"))]
+impl SystemClipboard {
+    /// Connect to the system clipboard - fails where there is none, such as without a display
+    /// server
+    pub fn new() -> Result<Self, arboard::Error> {
+        Ok(Self { clipboard: arboard::Clipboard::new()? })
+    }
+
+    pub fn shared() -> Result<SharedClipboard, arboard::Error> {
+        Ok(Rc::new(RefCell::new(Self::new()?)))
+    }
+}
+
+#[cfg(not(target_arch = "wasm32"))]
+impl Clipboard for SystemClipboard {
+    fn get(&mut self) -> Option<String> {
+        self.clipboard.get_text().ok()
+    }
+
+    fn set(&mut self, text: String) {
+        if let Err(e) = self.clipboard.set_text(text) {
+            warning!("Could not copy to the system clipboard: {e}");
+        }
+    }
+}
+
+thread_local! {
+    static DEFAULT: SharedClipboard = {
+        #[cfg(not(target_arch = "wasm32"))]
+        match SystemClipboard::shared() {
+            Ok(clipboard) => return clipboard,
+            Err(e) => warning!("No system clipboard, copied text stays inside the program: {e}"),
+        }
+        MemoryClipboard::shared()
+    };
+}
+
+/// The clipboard text boxes use unless given another - the system clipboard where there is one,
+/// connected once per thread
+pub fn default_clipboard() -> SharedClipboard {
+    DEFAULT.with(Rc::clone)
+}
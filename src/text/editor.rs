@@ -0,0 +1,380 @@
+use std::ops::Range;
+use std::time::{Duration, Instant};
+use unicode_segmentation::UnicodeSegmentation;
+use crate::text::clipboard::Clipboard;
+
+/// Where a caret movement goes
+#[derive(Copy, Clone, Debug, PartialEq, Eq)]
+pub enum Movement {
+    Left,
+    Right,
+    WordLeft,
+    WordRight,
+    LineStart,
+    LineEnd,
+    Start,
+    End,
+}
+
+#[derive(Copy, Clone, Debug, PartialEq, Eq)]
+enum EditKind {
+    Insert,
+    Delete,
+    Other,
+}
+
+#[derive(Clone, Debug, PartialEq)]
+struct State {
+    text: String,
+    caret: usize,
+    anchor: usize,
+}
+
+/// Editable text with a caret, a selection and undo history. Offsets are byte offsets that
+/// always sit on grapheme boundaries
+#[derive(Clone, Debug)]
+pub struct Editor {
+    text: String,
+    caret: usize,
+    /// Other end of the selection - equal to `caret` when nothing is selected
+    anchor: usize,
+    multiline: bool,
+    undo: Vec<State>,
+    redo: Vec<State>,
+    /// Kind, time and resulting caret of the last edit, for coalescing
+    last_edit: Option<(EditKind, Instant, usize)>,
+}
+
+impl Editor {
+    /// Edits of the same kind closer together than this are undone together
+    const COALESCE: Duration = Duration::from_secs(1);
+    const HISTORY: usize = 100;
+
+    pub fn new(text: &str, multiline: bool) -> Self {
+        let text = if multiline { text.to_owned() } else { text.replace('\n', " ") };
+        let caret = text.len();
+        Self { text, caret, anchor: caret, multiline, undo: Vec::new(), redo: Vec::new(), last_edit: None }
+    }
+
+    pub fn text(&self) -> &str {
+        &self.text
+    }
+
+    /// Replace the whole text, keeping it undoable
+    pub fn set_text(&mut self, text: &str) {
+        self.record(EditKind::Other);
+        self.text = self.filter(text);
+        self.caret = self.text.len();
+        self.anchor = self.caret;
+    }
+
+    pub fn is_multiline(&self) -> bool {
+        self.multiline
+    }
+
+    pub fn caret(&self) -> usize {
+        self.caret
+    }
+
+    pub fn anchor(&self) -> usize {
+        self.anchor
+    }
+
+    pub fn selection(&self) -> Range<usize> {
+        self.caret.min(self.anchor)..self.caret.max(self.anchor)
+    }
+
+    pub fn has_selection(&self) -> bool {
+        self.caret != self.anchor
+    }
+
+    pub fn selected_text(&self) -> &str {
+        &self.text[self.selection()]
+    }
+
+    /// Place the caret at `offset`, snapped to a grapheme boundary - `extend` keeps the anchor
+    /// where it is to grow the selection
+    pub fn set_caret(&mut self, offset: usize, extend: bool) {
+        self.caret = self.snap(offset.min(self.text.len()));
+        if !extend {
+            self.anchor = self.caret;
+        }
+        self.last_edit = None;
+    }
+
+    pub fn select(&mut self, range: Range<usize>) {
+        self.anchor = self.snap(range.start.min(self.text.len()));
+        self.caret = self.snap(range.end.min(self.text.len()));
+        self.last_edit = None;
+    }
+
+    pub fn select_all(&mut self) {
+        self.select(0..self.text.len());
+    }
+
+    pub fn move_caret(&mut self, movement: Movement, extend: bool) {
+        // Moving without Shift collapses a selection towards the direction of travel
+        if self.has_selection() && !extend {
+            let range = self.selection();
+            match movement {
+                Movement::Left => return self.set_caret(range.start, false),
+                Movement::Right => return self.set_caret(range.end, false),
+                _ => {}
+            }
+        }
+
+        let offset = match movement {
+            Movement::Left => self.previous_grapheme(self.caret),
+            Movement::Right => self.next_grapheme(self.caret),
+            Movement::WordLeft => self.previous_word(self.caret),
+            Movement::WordRight => self.next_word(self.caret),
+            Movement::LineStart => self.line_start(self.caret),
+            Movement::LineEnd => self.line_end(self.caret),
+            Movement::Start => 0,
+            Movement::End => self.text.len(),
+        };
+        self.set_caret(offset, extend);
+    }
+
+    /// Type `text` over the selection
+    pub fn insert(&mut self, text: &str) {
+        let text = self.filter(text);
+        if text.is_empty() && !self.has_selection() {
+            return;
+        }
+
+        // A space ends the word being typed, so words undo one at a time
+        let kind = if text.chars().all(char::is_whitespace) { EditKind::Other } else { EditKind::Insert };
+        self.record(kind);
+        self.replace_selection(&text);
+    }
+
+    /// Delete the selection, or the grapheme or word before the caret
+    pub fn backspace(&mut self, word: bool) {
+        let start = match self.has_selection() {
+            true => self.anchor,
+            false if word => self.previous_word(self.caret),
+            false => self.previous_grapheme(self.caret),
+        };
+        self.delete_to(start);
+    }
+
+    /// Delete the selection, or the grapheme or word after the caret
+    pub fn delete(&mut self, word: bool) {
+        let end = match self.has_selection() {
+            true => self.anchor,
+            false if word => self.next_word(self.caret),
+            false => self.next_grapheme(self.caret),
+        };
+        self.delete_to(end);
+    }
+
+    /// Delete between the caret and `offset`
+    fn delete_to(&mut self, offset: usize) {
+        if offset != self.caret {
+            self.record(EditKind::Delete);
+            self.anchor = offset;
+            self.replace_selection("");
+        }
+    }
+
+    pub fn copy(&self, clipboard: &mut dyn Clipboard) {
+        if self.has_selection() {
+            clipboard.set(self.selected_text().to_owned());
+        }
+    }
+
+    pub fn cut(&mut self, clipboard: &mut dyn Clipboard) {
+        if self.has_selection() {
+            self.copy(clipboard);
+            self.record(EditKind::Other);
+            self.replace_selection("");
+        }
+    }
+
+    pub fn paste(&mut self, clipboard: &mut dyn Clipboard) {
+        if let Some(text) = clipboard.get() {
+            self.record(EditKind::Other);
+            let text = self.filter(&text);
+            self.replace_selection(&text);
+        }
+    }
+
+    pub fn undo(&mut self) -> bool {
+        let Some(state) = self.undo.pop() else { return false };
+        self.redo.push(self.state());
+        self.restore(state);
+        true
+    }
+
+    pub fn redo(&mut self) -> bool {
+        let Some(state) = self.redo.pop() else { return false };
+        self.undo.push(self.state());
+        self.restore(state);
+        true
+    }
+
+    fn state(&self) -> State {
+        State { text: self.text.clone(), caret: self.caret, anchor: self.anchor }
+    }
+
+    fn restore(&mut self, state: State) {
+        self.text = state.text;
+        self.caret = state.caret;
+        self.anchor = state.anchor;
+        self.last_edit = None;
+    }
+
+    /// Save the state before an edit, unless it continues the previous edit
+    fn record(&mut self, kind: EditKind) {
+        let now = Instant::now();
+        let continues = kind != EditKind::Other && self.last_edit.is_some_and(|(last, time, caret)| {
+            last == kind && caret == self.caret && !self.has_selection() && now - time < Self::COALESCE
+        });
+
+        if !continues {
+            self.undo.push(self.state());
+            if self.undo.len() > Self::HISTORY {
+                self.undo.remove(0);
+            }
+        }
+        self.redo.clear();
+        self.last_edit = Some((kind, now, self.caret));
+    }
+
+    fn replace_selection(&mut self, text: &str) {
+        let range = self.selection();
+        self.text.replace_range(range.clone(), text);
+        self.caret = range.start + text.len();
+        self.anchor = self.caret;
+
+        if let Some((kind, time, _)) = self.last_edit {
+            self.last_edit = Some((kind, time, self.caret));
+        }
+    }
+
+    /// Single line editors turn line breaks into spaces
+    fn filter(&self, text: &str) -> String {
+        let text = text.replace("\r\n", "\n").replace('\r', "\n");
+        let keep = |c: &char| !c.is_control() || (*c == '\n' && self.multiline) || *c == '\t';
+        if self.multiline {
+            text.chars().filter(keep).collect()
+        } else {
+            text.replace('\n', " ").chars().filter(keep).collect()
+        }
+    }
+
+    fn snap(&self, offset: usize) -> usize {
+        self.text.grapheme_indices(true)
+            .map(|(i, _)| i)
+            .chain(std::iter::once(self.text.len()))
+            .take_while(|&i| i <= offset)
+            .last()
+            .unwrap_or(0)
+    }
+
+    pub fn previous_grapheme(&self, offset: usize) -> usize {
+        self.text[..offset].grapheme_indices(true).next_back().map(|(i, _)| i).unwrap_or(0)
+    }
+
+    pub fn next_grapheme(&self, offset: usize) -> usize {
+        self.text[offset..].graphemes(true).next().map(|g| offset + g.len()).unwrap_or(offset)
+    }
+
+    /// Start of the word before `offset`, skipping whitespace and punctuation
+    pub fn previous_word(&self, offset: usize) -> usize {
+        self.text[..offset].split_word_bound_indices()
+            .rev()
+            .find(|(_, w)| is_word(w))
+            .map(|(i, _)| i)
+            .unwrap_or(0)
+    }
+
+    /// End of the word after `offset`
+    pub fn next_word(&self, offset: usize) -> usize {
+        self.text[offset..].split_word_bound_indices()
+            .find(|(_, w)| is_word(w))
+            .map(|(i, w)| offset + i + w.len())
+            .unwrap_or(self.text.len())
+    }
+
+    /// Range of the word at `offset`, for double click selection
+    pub fn word_at(&self, offset: usize) -> Range<usize> {
+        self.text.split_word_bound_indices()
+            .map(|(i, w)| i..i + w.len())
+            .find(|r| r.contains(&offset) || r.end == offset && offset == self.text.len())
+            .unwrap_or(offset..offset)
+    }
+
+    pub fn line_start(&self, offset: usize) -> usize {
+        self.text[..offset].rfind('\n').map(|i| i + 1).unwrap_or(0)
+    }
+
+    pub fn line_end(&self, offset: usize) -> usize {
+        self.text[offset..].find('\n').map(|i| offset + i).unwrap_or(self.text.len())
+    }
+}
+
+fn is_word(segment: &str) -> bool {
+    segment.chars().any(char::is_alphanumeric)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::text::MemoryClipboard;
+
+    #[test]
+    fn caret_moves_by_grapheme_and_word() {
+        let mut editor = Editor::new("e\u{301}t\u{e9} rain", false);
+        editor.set_caret(0, false);
+
+        editor.move_caret(Movement::Right, false);
+        assert_eq!(editor.caret(), "e\u{301}".len());
+        editor.move_caret(Movement::WordRight, false);
+        assert_eq!(&editor.text()[..editor.caret()], "e\u{301}t\u{e9}");
+        editor.move_caret(Movement::End, true);
+        assert_eq!(editor.selected_text(), " rain");
+    }
+
+    #[test]
+    fn offsets_snap_to_graphemes() {
+        let mut editor = Editor::new("e\u{301}", false);
+        // Inside the combining accent, which belongs to the grapheme starting at 0
+        editor.set_caret(2, false);
+        assert_eq!(editor.caret(), 0);
+    }
+
+    #[test]
+    fn typing_undoes_a_word_at_a_time() {
+        let mut editor = Editor::new("", false);
+        for text in ["h", "i", " ", "y", "o", "u"] {
+            editor.insert(text);
+        }
+
+        assert!(editor.undo());
+        assert_eq!(editor.text(), "hi ");
+        assert!(editor.undo());
+        assert_eq!(editor.text(), "hi");
+        assert!(editor.undo());
+        assert_eq!(editor.text(), "");
+        assert!(!editor.undo());
+
+        assert!(editor.redo());
+        assert_eq!(editor.text(), "hi");
+    }
+
+    #[test]
+    fn single_line_editors_flatten_pasted_lines() {
+        let mut clipboard = MemoryClipboard::new();
+        clipboard.set("one\r\ntwo\n".to_owned());
+
+        let mut editor = Editor::new("", false);
+        editor.paste(&mut clipboard);
+        assert_eq!(editor.text(), "one two ");
+
+        let mut editor = Editor::new("", true);
+        editor.paste(&mut clipboard);
+        assert_eq!(editor.text(), "one\ntwo\n");
+    }
+}
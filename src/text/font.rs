@@ -0,0 +1,262 @@
+use ab_glyph::Font as _;
+use crate::text::TextError;
+
+/// Handle to a font loaded into [`Fonts`]
+#[derive(Copy, Clone, Debug, PartialEq, Eq, Hash, PartialOrd, Ord)]
+pub struct FontId(pub usize);
+
+struct Font {
+    /// Owns the font file - shaping borrows it through a `rustybuzz::Face` made per call, which
+    /// only reads the table directory up front
+    raster: ab_glyph::FontVec,
+    /// Face within a font collection
+    index: u32,
+    units_per_em: f32,
+    ascender: f32,
+    descender: f32,
+    line_gap: f32,
+}
+
+impl Font {
+    fn face(&self) -> Result<rustybuzz::Face<'_>, TextError> {
+        rustybuzz::Face::from_slice(self.raster.as_slice(), self.index).ok_or(TextError::InvalidFont)
+    }
+}
+
+/// A positioned glyph in a shaped run, in pixels relative to the run origin on the baseline
+#[derive(Copy, Clone, Debug, PartialEq)]
+pub struct ShapedGlyph {
+    pub glyph: u16,
+    /// Byte offset of the first character this glyph was shaped from
+    pub cluster: usize,
+    pub x: f32,
+    pub y: f32,
+    pub advance: f32,
+}
+
+#[derive(Clone, Debug, Default, PartialEq)]
+pub struct TextMetrics {
+    pub width: f32,
+    pub height: f32,
+    /// Distance from the top of a line to its baseline
+    pub ascent: f32,
+    /// Distance from the baseline to the bottom of a line, positive downwards
+    pub descent: f32,
+    pub line_height: f32,
+}
+
+/// Shaped text - lines are split on `\n` and stacked by the font's line height
+#[derive(Clone, Debug, Default, PartialEq)]
+pub struct ShapedText {
+    pub glyphs: Vec<ShapedGlyph>,
+    pub metrics: TextMetrics,
+}
+
+/// Every font known to the application - shaping applies the font's default OpenType features,
+/// so ligatures and kerning come for free
+#[derive(Default)]
+pub struct Fonts {
+    fonts: Vec<Font>,
+}
+
+impl Fonts {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Load a TTF or OTF font from memory
+    pub fn load(&mut self, data: Vec<u8>) -> Result<FontId, TextError> {
+        self.load_index(data, 0)
+    }
+
+    /// Load face `index` of a font collection
+    pub fn load_index(&mut self, data: Vec<u8>, index: u32) -> Result<FontId, TextError> {
+        let face = rustybuzz::Face::from_slice(&data, index).ok_or(TextError::InvalidFont)?;
+        let (units_per_em, ascender, descender, line_gap) = (
+            face.units_per_em() as f32,
+            face.ascender() as f32,
+            face.descender() as f32,
+            face.line_gap() as f32,
+        );
+
+        let raster = ab_glyph::FontVec::try_from_vec_and_index(data, index)
+            .map_err(|_| TextError::InvalidFont)?;
+
+        self.fonts.push(Font { raster, index, units_per_em, ascender, descender, line_gap });
+
+        Ok(FontId(self.fonts.len() - 1))
+    }
+
+    pub fn load_file(&mut self, path: impl AsRef<std::path::Path>) -> Result<FontId, TextError> {
+        self.load(std::fs::read(path)?)
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.fonts.is_empty()
+    }
+
+    fn font(&self, id: FontId) -> Result<&Font, TextError> {
+        self.fonts.get(id.0).ok_or(TextError::UnknownFont(id))
+    }
+
+    /// Vertical metrics of a single line at `size` pixels per em
+    pub fn line_metrics(&self, font: FontId, size: f32) -> Result<TextMetrics, TextError> {
+        let font = self.font(font)?;
+        let scale = size / font.units_per_em;
+        let ascent = font.ascender * scale;
+        let descent = -font.descender * scale;
+        let line_height = ascent + descent + font.line_gap * scale;
+
+        Ok(TextMetrics { width: 0.0, height: ascent + descent, ascent, descent, line_height })
+    }
+
+    /// Shape `text` at `size` pixels per em
+    pub fn shape(&self, font_id: FontId, text: &str, size: f32) -> Result<ShapedText, TextError> {
+        let font = self.font(font_id)?;
+        let scale = size / font.units_per_em;
+        let mut metrics = self.line_metrics(font_id, size)?;
+        let face = font.face()?;
+
+        let mut glyphs = Vec::new();
+        let mut offset = 0;
+        let mut lines = 0;
+        for line in text.split('\n') {
+            let y = lines as f32 * metrics.line_height;
+            let mut x = 0.0;
+
+            if !line.is_empty() {
+                let mut buffer = rustybuzz::UnicodeBuffer::new();
+                buffer.push_str(line);
+                let shaped = rustybuzz::shape(&face, &[], buffer);
+
+                for (info, position) in shaped.glyph_infos().iter().zip(shaped.glyph_positions()) {
+                    glyphs.push(ShapedGlyph {
+                        glyph: info.glyph_id as u16,
+                        cluster: offset + info.cluster as usize,
+                        x: x + position.x_offset as f32 * scale,
+                        // Font units are y-up, pixels are y-down
+                        y: y - position.y_offset as f32 * scale,
+                        advance: position.x_advance as f32 * scale,
+                    });
+                    x += position.x_advance as f32 * scale;
+                }
+            }
+
+            metrics.width = metrics.width.max(x);
+            offset += line.len() + 1;
+            lines += 1;
+        }
+
+        metrics.height = (lines - 1) as f32 * metrics.line_height + metrics.ascent + metrics.descent;
+
+        Ok(ShapedText { glyphs, metrics })
+    }
+
+    /// Size of `text` when drawn at `size` pixels per em - for layout
+    pub fn measure_text(&self, font: FontId, text: &str, size: f32) -> Result<TextMetrics, TextError> {
+        Ok(self.shape(font, text, size)?.metrics)
+    }
+
+    /// Rasterize a glyph to an 8-bit coverage bitmap - returns the bitmap size, the offset of its
+    /// top left corner from the pen position on the baseline, and the pixels. `None` for glyphs
+    /// with no outline, such as spaces
+    pub fn rasterize(&self, font: FontId, glyph: u16, size: f32) -> Option<([u32; 2], [f32; 2], Vec<u8>)> {
+        let font = self.font(font).ok()?;
+        // ab_glyph scales by the ascender-descender height rather than the em
+        let scale = size * font.raster.height_unscaled() / font.units_per_em;
+        let outline = font.raster.outline_glyph(ab_glyph::GlyphId(glyph).with_scale(scale))?;
+
+        let bounds = outline.px_bounds();
+        let (width, height) = (bounds.width() as u32, bounds.height() as u32);
+        if width == 0 || height == 0 {
+            return None;
+        }
+
+        let mut pixels = vec![0; (width * height) as usize];
+        outline.draw(|x, y, coverage| {
+            pixels[(y * width + x) as usize] = (coverage.clamp(0.0, 1.0) * 255.0).round() as u8;
+        });
+
+        Some(([width, height], [bounds.min.x, bounds.min.y], pixels))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn dejavu() -> (Fonts, FontId) {
+        let mut fonts = Fonts::new();
+        let id = fonts.load_file(concat!(env!("CARGO_MANIFEST_DIR"), "/assets/fonts/DejaVuSans.ttf")).unwrap();
+        (fonts, id)
+    }
+
+    #[test]
+    fn ligatures_keep_the_cluster_of_their_first_character() {
+        let (fonts, id) = dejavu();
+        let shaped = fonts.shape(id, "office", 16.0).unwrap();
+
+        // o, ffi, c, e
+        let clusters: Vec<_> = shaped.glyphs.iter().map(|g| g.cluster).collect();
+        assert_eq!(clusters, [0, 1, 4, 5]);
+    }
+
+    #[test]
+    fn kerning_pulls_pairs_together() {
+        let (fonts, id) = dejavu();
+        let pair = fonts.measure_text(id, "AV", 32.0).unwrap().width;
+        let apart = fonts.measure_text(id, "A", 32.0).unwrap().width
+            + fonts.measure_text(id, "V", 32.0).unwrap().width;
+        assert!(pair < apart - 1.0, "{pair} vs {apart}");
+    }
+
+    #[test]
+    fn glyphs_advance_along_the_baseline() {
+        let (fonts, id) = dejavu();
+        let shaped = fonts.shape(id, "Hello", 20.0).unwrap();
+        assert_eq!(shaped.glyphs.len(), 5);
+
+        let mut x = 0.0;
+        for glyph in &shaped.glyphs {
+            assert_eq!(glyph.x, x);
+            assert_eq!(glyph.y, 0.0);
+            x += glyph.advance;
+        }
+        assert_eq!(shaped.metrics.width, x);
+    }
+
+    #[test]
+    fn lines_stack_by_the_line_height() {
+        let (fonts, id) = dejavu();
+        let line = fonts.line_metrics(id, 16.0).unwrap();
+        assert!(line.ascent > 0.0 && line.descent > 0.0);
+        assert!(line.line_height >= line.ascent + line.descent);
+
+        let shaped = fonts.shape(id, "ab\nc", 16.0).unwrap();
+        let c = shaped.glyphs[2];
+        assert_eq!((c.cluster, c.x, c.y), (3, 0.0, line.line_height));
+        assert_eq!(shaped.metrics.height, line.line_height + line.height);
+        assert_eq!(shaped.metrics.width, shaped.glyphs[0].advance + shaped.glyphs[1].advance);
+
+        assert_eq!(fonts.measure_text(id, "", 16.0).unwrap(), line);
+    }
+
+    #[test]
+    fn spaces_have_no_bitmap() {
+        let (fonts, id) = dejavu();
+        let glyphs = fonts.shape(id, "A ", 16.0).unwrap().glyphs;
+
+        let (size, bearing, pixels) = fonts.rasterize(id, glyphs[0].glyph, 16.0).unwrap();
+        assert_eq!(pixels.len(), (size[0] * size[1]) as usize);
+        assert!(bearing[1] < 0.0);
+        assert!(pixels.contains(&255));
+        assert!(fonts.rasterize(id, glyphs[1].glyph, 16.0).is_none());
+    }
+
+    #[test]
+    fn unknown_fonts_and_bad_data_are_errors() {
+        let (fonts, _) = dejavu();
+        assert!(matches!(fonts.shape(FontId(3), "a", 16.0), Err(TextError::UnknownFont(FontId(3)))));
+        assert!(matches!(Fonts::new().load(vec![0; 64]), Err(TextError::InvalidFont)));
+    }
+}
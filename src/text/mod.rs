@@ -0,0 +1,48 @@
+pub mod atlas;
+pub mod clipboard;
+pub mod editor;
+pub mod font;
+
+pub use clipboard::{default_clipboard, Clipboard, MemoryClipboard, SharedClipboard};
+#[cfg(not(target_arch = "wasm32"))]
+pub use clipboard::SystemClipboard;
+pub use editor::{Editor, Movement};
+pub use font::{FontId, Fonts, ShapedGlyph, ShapedText, TextMetrics};
+
+#[derive(Debug)]
+pub enum TextError {
+    Io(std::io::Error),
+    /// The data is not a font rustybuzz and ab_glyph can both parse
+    InvalidFont,
+    UnknownFont(FontId),
+}
+
+impl std::fmt::Display for TextError {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            TextError::Io(e) => write!(f, "could not read font: {e}"),
+            TextError::InvalidFont => write!(f, "invalid or unsupported font data"),
+            TextError::UnknownFont(id) => write!(f, "no font loaded with id {}", id.0),
+        }
+    }
+}
+
+impl std::error::Error for TextError {}
+
+impl From<std::io::Error> for TextError {
+    fn from(e: std::io::Error) -> Self {
+        TextError::Io(e)
+    }
+}
+
+/// A single run of text queued for drawing
+#[derive(Clone, Debug, PartialEq)]
+pub struct TextRun {
+    pub font: FontId,
+    /// Pixels per em
+    pub size: f32,
+    /// Top left corner of the first line
+    pub position: [f32; 2],
+    pub color: [f32; 4],
+    pub text: String,
+}